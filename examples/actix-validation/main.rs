@@ -0,0 +1,40 @@
+use actix_web::{web, App, HttpResponse, HttpServer};
+use oasert::actix::OpenApiValidation;
+use oasert::cache::ValidatorCollection;
+use std::sync::Arc;
+
+const VALIDATOR_ID: &str = "petstore";
+
+async fn list_pets() -> HttpResponse {
+    HttpResponse::Ok().body("[]")
+}
+
+async fn show_pet_by_id(pet_id: web::Path<String>) -> HttpResponse {
+    HttpResponse::Ok().body(format!("{{\"id\": \"{}\"}}", pet_id))
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    let collection = Arc::new(ValidatorCollection::<String>::new());
+    match collection.insert_from_file_path(
+        VALIDATOR_ID.to_string(),
+        &"examples/actix-validation/openapi-v3.1.0.json",
+    ) {
+        Ok(_) => {}
+        Err(_) => panic!("Failed to insert spec into validator collection"),
+    };
+
+    // The collection is shared by every worker through the cloned middleware.
+    let validation = OpenApiValidation::new(collection, VALIDATOR_ID.to_string());
+
+    println!("Listening on http://127.0.0.1:3000");
+    HttpServer::new(move || {
+        App::new()
+            .wrap(validation.clone())
+            .route("/pets", web::get().to(list_pets))
+            .route("/pets/{petId}", web::get().to(show_pet_by_id))
+    })
+    .bind(("127.0.0.1", 3000))?
+    .run()
+    .await
+}
@@ -39,7 +39,7 @@ async fn validation_function(
     request: http::Request<Body>,
 ) -> Result<impl IntoResponse, std::convert::Infallible> {
     let _context = request.lambda_context_ref();
-    let response = match validator.validate_request(&request, None) {
+    let response = match validator.validate_lambda_request(&request) {
         Ok(_) => http::Response::builder()
             .status(200)
             .body(String::from("OK")),
@@ -0,0 +1,513 @@
+use crate::cache::ValidatorCollection;
+use crate::traverser::TraverserError;
+use crate::validator::{OpenApiPayloadValidator, ValidationError};
+use actix_web::body::EitherBody;
+use actix_web::dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::header::CONTENT_TYPE;
+use actix_web::http::StatusCode;
+use actix_web::{HttpMessage, HttpResponse};
+use bytes::{Bytes, BytesMut};
+use futures_util::future::LocalBoxFuture;
+use futures_util::StreamExt;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::future::{ready, Ready};
+use std::hash::Hash;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Default upper bound (in bytes) for buffered request payloads.
+const DEFAULT_PAYLOAD_LIMIT: usize = 262_144;
+
+type KeyResolver<K> = Arc<dyn Fn(&ServiceRequest) -> Option<K> + Send + Sync>;
+
+/// Error types that can occur while adapting an actix-web request for validation.
+#[derive(Debug)]
+pub enum ActixError {
+    /// The buffered payload grew beyond the configured limit.
+    PayloadTooLarge(usize),
+
+    /// The payload stream returned an error while being read.
+    FailedToReadPayload(String),
+
+    /// The payload could not be parsed as JSON.
+    InvalidJson(String),
+
+    /// The incoming request could not be converted into an `http::Request`.
+    InvalidRequest(String),
+
+    /// The resolver returned a key that is missing from the `ValidatorCollection`.
+    ValidatorNotFound,
+
+    /// The request does not match the OpenAPI specification.
+    ValidationFailed(ValidationError),
+}
+
+impl ActixError {
+    /// The status of the response the middleware sends for the error.
+    ///
+    /// Requests to undocumented (or not served) operations are answered with `404 Not Found`,
+    /// failed security requirements with `401 Unauthorized` or `403 Forbidden` and any other
+    /// invalid request with `400 Bad Request`.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ActixError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            ActixError::ValidatorNotFound => StatusCode::INTERNAL_SERVER_ERROR,
+            ActixError::ValidationFailed(e) => match e {
+                ValidationError::ValidationTraversalError(
+                    TraverserError::PathNotFound(_) | TraverserError::OperationNotServed(_),
+                ) => StatusCode::NOT_FOUND,
+                ValidationError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+                ValidationError::Forbidden(_) => StatusCode::FORBIDDEN,
+                _ => StatusCode::BAD_REQUEST,
+            },
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+impl Display for ActixError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActixError::PayloadTooLarge(limit) => {
+                write!(f, "Payload exceeds the limit of {} bytes", limit)
+            }
+            ActixError::FailedToReadPayload(msg) => {
+                write!(f, "Failed to read payload: {}", msg)
+            }
+            ActixError::InvalidJson(msg) => write!(f, "Invalid JSON: {}", msg),
+            ActixError::InvalidRequest(msg) => write!(f, "Invalid request: {}", msg),
+            ActixError::ValidatorNotFound => {
+                write!(f, "No validator is registered for the request")
+            }
+            ActixError::ValidationFailed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ActixError {}
+
+/// actix-web middleware that validates every request against an OpenAPI specification.
+///
+/// The middleware looks up an `OpenApiPayloadValidator` in a shared `ValidatorCollection`,
+/// buffers the payload (up to the configured limit), validates the request and then re-injects
+/// the buffered payload so downstream extractors can read it again. Because the collection is
+/// held behind an `Arc`, cloning the middleware into each worker shares the same validators.
+///
+/// Rejected requests are answered with the status of the `ActixError`, see
+/// `ActixError::status_code`.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// use actix_web::{App, HttpServer};
+/// use oasert::actix::OpenApiValidation;
+/// use oasert::cache::ValidatorCollection;
+/// use std::sync::Arc;
+///
+/// let collection = Arc::new(ValidatorCollection::<String>::new());
+/// collection.insert_from_file_path("api".to_string(), &"openapi.json").unwrap();
+/// let validation = OpenApiValidation::new(collection, "api".to_string());
+///
+/// HttpServer::new(move || App::new().wrap(validation.clone()));
+/// ```
+pub struct OpenApiValidation<K> {
+    collection: Arc<ValidatorCollection<K>>,
+    resolver: KeyResolver<K>,
+    payload_limit: usize,
+}
+
+impl<K> Clone for OpenApiValidation<K> {
+    fn clone(&self) -> Self {
+        Self {
+            collection: Arc::clone(&self.collection),
+            resolver: Arc::clone(&self.resolver),
+            payload_limit: self.payload_limit,
+        }
+    }
+}
+
+impl<K> OpenApiValidation<K>
+where
+    K: Hash + Eq + 'static,
+{
+    /// Creates a middleware that validates every request with the validator stored under `id`.
+    ///
+    /// # Arguments
+    ///
+    /// * `collection` - The shared collection holding the validator
+    /// * `id` - The key of the validator inside the collection
+    pub fn new(collection: Arc<ValidatorCollection<K>>, id: K) -> Self
+    where
+        K: Clone + Send + Sync,
+    {
+        Self::with_resolver(collection, move |_| Some(id.clone()))
+    }
+
+    /// Creates a middleware that selects the validator for each request using `resolver`.
+    ///
+    /// Requests for which the resolver returns `None` are passed through without validation.
+    /// A key missing from the collection is a configuration error, and the request is answered
+    /// with `500 Internal Server Error`.
+    ///
+    /// # Arguments
+    ///
+    /// * `collection` - The shared collection holding the validators
+    /// * `resolver` - Maps an incoming request to the key of its validator
+    pub fn with_resolver<F>(collection: Arc<ValidatorCollection<K>>, resolver: F) -> Self
+    where
+        F: Fn(&ServiceRequest) -> Option<K> + Send + Sync + 'static,
+    {
+        Self {
+            collection,
+            resolver: Arc::new(resolver),
+            payload_limit: DEFAULT_PAYLOAD_LIMIT,
+        }
+    }
+
+    /// Sets the maximum number of payload bytes buffered for validation.
+    pub fn payload_limit(mut self, limit: usize) -> Self {
+        self.payload_limit = limit;
+        self
+    }
+}
+
+impl<S, B, K> Transform<S, ServiceRequest> for OpenApiValidation<K>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+    K: Hash + Eq + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Transform = OpenApiValidationMiddleware<S, K>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(OpenApiValidationMiddleware {
+            service: Rc::new(service),
+            config: self.clone(),
+        }))
+    }
+}
+
+/// The per-worker service produced by [`OpenApiValidation`].
+pub struct OpenApiValidationMiddleware<S, K> {
+    service: Rc<S>,
+    config: OpenApiValidation<K>,
+}
+
+impl<S, B, K> Service<ServiceRequest> for OpenApiValidationMiddleware<S, K>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = actix_web::Error> + 'static,
+    S::Future: 'static,
+    B: 'static,
+    K: Hash + Eq + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = actix_web::Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = Rc::clone(&self.service);
+        let validator = (self.config.resolver)(&req).map(|id| self.config.collection.get(&id));
+        let payload_limit = self.config.payload_limit;
+
+        Box::pin(async move {
+            let validator = match validator {
+                Some(Ok(validator)) => validator,
+                Some(Err(_)) => {
+                    log::warn!("No validator registered for '{}'", req.path());
+                    return Ok(reject(req, ActixError::ValidatorNotFound));
+                }
+                None => {
+                    log::debug!("No validator selected for '{}', skipping", req.path());
+                    return service.call(req).await.map(|res| res.map_into_left_body());
+                }
+            };
+
+            let bytes = match buffer_payload(&mut req, payload_limit).await {
+                Ok(bytes) => bytes,
+                Err(e) => return Ok(reject(req, e)),
+            };
+
+            if let Err(e) = validate_service_request(&validator, &req, &bytes) {
+                log::debug!("Request to '{}' failed validation: {}", req.path(), e);
+                return Ok(reject(req, e));
+            }
+
+            // Hand the buffered payload back so extractors further down the chain can read it.
+            req.set_payload(Payload::from(bytes));
+            service.call(req).await.map(|res| res.map_into_left_body())
+        })
+    }
+}
+
+/// Answers a request with the status and message of an error.
+fn reject<B>(req: ServiceRequest, e: ActixError) -> ServiceResponse<EitherBody<B>> {
+    let response = HttpResponse::build(e.status_code()).body(e.to_string());
+    req.into_response(response).map_into_right_body()
+}
+
+/// Drains the request payload into memory, failing once `limit` bytes are exceeded.
+async fn buffer_payload(req: &mut ServiceRequest, limit: usize) -> Result<Bytes, ActixError> {
+    let mut payload = req.take_payload();
+    let mut buffer = BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => return Err(ActixError::FailedToReadPayload(e.to_string())),
+        };
+        if buffer.len() + chunk.len() > limit {
+            return Err(ActixError::PayloadTooLarge(limit));
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(buffer.freeze())
+}
+
+/// Converts an actix-web request and its buffered payload into an `http::Request` and validates it.
+///
+/// Payloads of JSON media types are parsed, any other payload is validated as a string.
+fn validate_service_request(
+    validator: &OpenApiPayloadValidator,
+    req: &ServiceRequest,
+    bytes: &Bytes,
+) -> Result<(), ActixError> {
+    let body: Value = if bytes.is_empty() {
+        Value::Null
+    } else if is_json(req) {
+        match serde_json::from_slice(bytes) {
+            Ok(body) => body,
+            Err(e) => return Err(ActixError::InvalidJson(e.to_string())),
+        }
+    } else {
+        Value::String(String::from_utf8_lossy(bytes).into_owned())
+    };
+
+    // actix-web is built on a different major version of the `http` crate, so the request is
+    // rebuilt from its string representation.
+    let mut builder = http::Request::builder()
+        .method(req.method().as_str())
+        .uri(req.uri().to_string());
+    for (name, value) in req.headers() {
+        builder = builder.header(name.as_str(), value.as_bytes());
+    }
+    let request = match builder.body(body) {
+        Ok(request) => request,
+        Err(e) => return Err(ActixError::InvalidRequest(e.to_string())),
+    };
+
+    match validator.validate_request(&request, None) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(ActixError::ValidationFailed(e)),
+    }
+}
+
+/// Whether the request declares a JSON media type, e.g. `application/json` or
+/// `application/problem+json`.
+fn is_json(req: &ServiceRequest) -> bool {
+    let content_type = match req.headers().get(CONTENT_TYPE) {
+        Some(content_type) => content_type,
+        None => return false,
+    };
+    let media_type = match content_type.to_str() {
+        Ok(content_type) => content_type.split(';').next().unwrap_or_default().trim(),
+        Err(_) => return false,
+    };
+    let media_type = media_type.to_ascii_lowercase();
+    media_type == "application/json" || media_type.ends_with("+json")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::types::security::{Credential, SecurityHandler, SecurityRejection};
+    use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+    use actix_web::{test, web, App};
+    use serde_json::json;
+
+    struct ApiKeyStore;
+
+    impl SecurityHandler for ApiKeyStore {
+        fn verify(
+            &self,
+            _scheme_name: &str,
+            credential: &Credential,
+        ) -> Result<Vec<String>, SecurityRejection> {
+            match credential {
+                Credential::ApiKey(key) if key == "admin-key" => Ok(vec!["admin".to_string()]),
+                Credential::ApiKey(key) if key == "guest-key" => Ok(vec![]),
+                _ => Err(SecurityRejection::new("unknown API key")),
+            }
+        }
+    }
+
+    fn create_collection() -> Arc<ValidatorCollection<String>> {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["name"],
+                                        "properties": { "name": { "type": "string" } }
+                                    }
+                                },
+                                "text/plain": { "schema": { "type": "string", "maxLength": 8 } }
+                            }
+                        }
+                    }
+                },
+                "/admin": {
+                    "get": { "security": [{ "apiKey": ["admin"] }] }
+                }
+            },
+            "components": {
+                "securitySchemes": {
+                    "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" }
+                }
+            }
+        });
+        let collection = Arc::new(ValidatorCollection::new());
+        let builder = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(spec)
+            .security_handler("apiKey", ApiKeyStore);
+        collection
+            .insert_builder("api".to_string(), builder)
+            .unwrap();
+        collection
+    }
+
+    /// Echoes the payload, to check that it is handed back after validation.
+    async fn echo(body: Bytes) -> HttpResponse {
+        HttpResponse::Ok().body(body)
+    }
+
+    fn post_pets(content_type: &str, payload: &'static str) -> actix_web::test::TestRequest {
+        test::TestRequest::post()
+            .uri("/pets")
+            .insert_header((CONTENT_TYPE, content_type))
+            .set_payload(payload)
+    }
+
+    #[actix_web::test]
+    async fn test_validation() {
+        let validation = OpenApiValidation::new(create_collection(), "api".to_string());
+        let app = test::init_service(
+            App::new()
+                .wrap(validation)
+                .route("/pets", web::post().to(echo))
+                .route("/admin", web::get().to(HttpResponse::Ok))
+                .route("/undocumented", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let req = post_pets("application/json", r#"{"name":"Rex"}"#).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(test::read_body(response).await, r#"{"name":"Rex"}"#);
+
+        let req = post_pets("application/json", r#"{"name":1}"#).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Only JSON media types are parsed as JSON
+        let req = post_pets("text/plain; charset=utf-8", "Rex").to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(test::read_body(response).await, "Rex");
+        let req = post_pets("text/plain", "Rex the third").to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let req = test::TestRequest::get().uri("/undocumented").to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[actix_web::test]
+    async fn test_security_rejections() {
+        let validation = OpenApiValidation::new(create_collection(), "api".to_string());
+        let app = test::init_service(
+            App::new()
+                .wrap(validation)
+                .route("/admin", web::get().to(HttpResponse::Ok)),
+        )
+        .await;
+
+        let cases = [
+            (Some("admin-key"), StatusCode::OK),
+            (Some("guest-key"), StatusCode::FORBIDDEN),
+            (Some("stolen-key"), StatusCode::UNAUTHORIZED),
+            (None, StatusCode::UNAUTHORIZED),
+        ];
+        for (api_key, status) in cases {
+            let mut req = test::TestRequest::get().uri("/admin");
+            if let Some(api_key) = api_key {
+                req = req.insert_header(("X-API-Key", api_key));
+            }
+            let response = test::call_service(&app, req.to_request()).await;
+            assert_eq!(response.status(), status);
+        }
+    }
+
+    #[actix_web::test]
+    async fn test_payload_limit() {
+        let validation =
+            OpenApiValidation::new(create_collection(), "api".to_string()).payload_limit(8);
+        let app = test::init_service(
+            App::new()
+                .wrap(validation)
+                .route("/pets", web::post().to(echo)),
+        )
+        .await;
+
+        let req = post_pets("application/json", r#"{"name":"Rex"}"#).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[actix_web::test]
+    async fn test_resolver() {
+        let validation = OpenApiValidation::with_resolver(create_collection(), |req| {
+            req.headers()
+                .get("x-api-version")
+                .map(|version| version.to_str().unwrap_or_default().to_string())
+        });
+        let app = test::init_service(
+            App::new()
+                .wrap(validation)
+                .route("/pets", web::post().to(echo)),
+        )
+        .await;
+
+        // No key, passed through without validation
+        let req = post_pets("application/json", r#"{"name":1}"#).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let req = post_pets("application/json", r#"{"name":1}"#)
+            .insert_header(("x-api-version", "api"))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // A key without a validator
+        let req = post_pets("application/json", r#"{"name":"Rex"}"#)
+            .insert_header(("x-api-version", "v2"))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,798 @@
+//! Bundling of a multi-file specification into a single, self-contained document.
+//!
+//! A `Bundler` loads the documents a specification references, like
+//! `OpenApiPayloadValidatorBuilder` does, and moves every part of them that is referenced into
+//! `components`, under a name no other component has. Optionally, it then dereferences the
+//! document, inlining every `$ref` that is not recursive, and prunes the components nothing
+//! references anymore.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oasert::bundle::Bundler;
+//!
+//! let bundled = Bundler::new()
+//!     .sandbox("./test/multi-file")
+//!     .bundle_file("./test/multi-file/openapi.yaml")
+//!     .unwrap();
+//! let operation = &bundled["paths"]["/users"]["post"];
+//! assert_eq!(
+//!     operation["parameters"][0]["$ref"],
+//!     "#/components/parameters/Tracing"
+//! );
+//! assert!(bundled["components"]["schemas"]["Address"].is_object());
+//! ```
+use crate::document::{escape, resolve_reference, INSTANCE_KEYWORDS, SUBSCHEMA_MAP_KEYWORDS};
+use crate::external::{self, EXTERNAL_DOCUMENTS_FIELD};
+use crate::loader::{self, SpecificationFormat};
+use crate::traverser::OpenApiTraverser;
+use crate::types::resolver::ReferenceResolver;
+use crate::{COMPONENTS_FIELD, PARAMETERS_FIELD, PATHS_FIELD, REF_FIELD, SCHEMA_FIELD};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const SCHEMAS_FIELD: &str = "schemas";
+const REQUEST_BODIES_FIELD: &str = "requestBodies";
+const PATH_ITEMS_FIELD: &str = "pathItems";
+const CALLBACKS_FIELD: &str = "callbacks";
+const SECURITY_SCHEMES_FIELD: &str = "securitySchemes";
+const DISCRIMINATOR_FIELD: &str = "discriminator";
+const MAPPING_FIELD: &str = "mapping";
+const ID_FIELD: &str = "$id";
+const LOCAL_PREFIX: &str = "#/";
+
+/// Fields whose value is a map of components of the kind of the same name.
+const COMPONENT_MAP_FIELDS: [&str; 8] = [
+    "responses",
+    "headers",
+    "examples",
+    "links",
+    SCHEMAS_FIELD,
+    REQUEST_BODIES_FIELD,
+    SECURITY_SCHEMES_FIELD,
+    PATH_ITEMS_FIELD,
+];
+
+/// The bundling failed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BundleError(pub String);
+
+impl Display for BundleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bundle Failure: {}", self.0)
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+/// Bundles specifications into a single document.
+#[derive(Clone, Default)]
+pub struct Bundler {
+    sandbox: Option<PathBuf>,
+    reference_resolver: Option<Arc<dyn ReferenceResolver>>,
+    dereference: bool,
+    prune_components: bool,
+}
+
+impl Bundler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restricts the files a specification can reference to those inside `directory`, see
+    /// `OpenApiPayloadValidatorBuilder::sandbox`.
+    pub fn sandbox(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.sandbox = Some(directory.into());
+        self
+    }
+
+    /// Registers the resolver that serves the documents of `$ref` URIs that are not files, see
+    /// `OpenApiPayloadValidatorBuilder::reference_resolver`.
+    pub fn reference_resolver(mut self, resolver: impl ReferenceResolver + 'static) -> Self {
+        self.reference_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Turns full dereferencing on or off. This is off by default.
+    ///
+    /// When on, every `$ref` is replaced by what it references, except where that would recurse
+    /// forever: a `$ref` to a node it is itself inside of is kept. Sibling fields of a `$ref`
+    /// take precedence over those of what it references.
+    pub fn dereference(mut self, dereference: bool) -> Self {
+        self.dereference = dereference;
+        self
+    }
+
+    /// Turns removal of the components nothing references on or off. This is off by default.
+    ///
+    /// Security schemes are kept, security requirements name them rather than reference them.
+    pub fn prune_components(mut self, prune_components: bool) -> Self {
+        self.prune_components = prune_components;
+        self
+    }
+
+    /// Bundles the specification of a JSON or YAML file. Relative references are resolved
+    /// against the directory of the file.
+    pub fn bundle_file(&self, path: impl AsRef<Path>) -> Result<Value, BundleError> {
+        let path = path.as_ref();
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(BundleError(e.to_string())),
+        };
+        let specification =
+            match loader::parse_specification(&content, SpecificationFormat::from_path(path)) {
+                Ok(specification) => specification,
+                Err(e) => return Err(BundleError(e.to_string())),
+            };
+        self.bundle_document(specification, Some(path))
+    }
+
+    /// Bundles an already parsed specification. Relative references are resolved against the
+    /// sandbox, or else the current directory.
+    pub fn bundle(&self, specification: Value) -> Result<Value, BundleError> {
+        self.bundle_document(specification, None)
+    }
+
+    fn bundle_document(
+        &self,
+        mut specification: Value,
+        path: Option<&Path>,
+    ) -> Result<Value, BundleError> {
+        let sources = match external::embed_external_documents(
+            &mut specification,
+            path,
+            self.sandbox.as_deref(),
+            self.reference_resolver.as_deref(),
+        ) {
+            Ok(sources) => sources,
+            Err(e) => return Err(BundleError(e)),
+        };
+        let mut bundled = match sources.is_empty() {
+            true => specification,
+            false => move_into_components(specification, &sources)?,
+        };
+        if self.dereference {
+            bundled = dereference(&bundled);
+        }
+        if self.prune_components {
+            prune_components(&mut bundled);
+        }
+        Ok(bundled)
+    }
+}
+
+/// Moves every referenced part of the embedded documents into `components`, and drops the
+/// embedded documents.
+///
+/// # Arguments
+/// * `specification` - The specification, with the documents it references embedded
+/// * `sources` - The sources of the embedded documents, by index, that unnamed parts (e.g. a
+///   whole file) are named after
+fn move_into_components(specification: Value, sources: &[String]) -> Result<Value, BundleError> {
+    let mut collector = ReferenceCollector {
+        specification: &specification,
+        targets: vec![],
+        seen: HashSet::new(),
+    };
+    collector.walk_root();
+    let targets = collector.targets;
+
+    // Name the targets, avoiding the names of the components of the root document.
+    let mut taken: HashSet<(&str, String)> = HashSet::new();
+    if let Some(Value::Object(components)) = specification.get(COMPONENTS_FIELD) {
+        for (kind, section) in components {
+            if let Value::Object(section) = section {
+                taken.extend(section.keys().map(|name| (kind.as_str(), name.clone())));
+            }
+        }
+    }
+    let mut renames: HashMap<String, String> = HashMap::new();
+    let mut moved = vec![];
+    for (reference, kind) in &targets {
+        let value = match resolve_reference(&specification, reference) {
+            Some(value) => value,
+            None => {
+                return Err(BundleError(format!(
+                    "Reference '{}' does not resolve",
+                    reference
+                )));
+            }
+        };
+        if *kind == PATH_ITEMS_FIELD {
+            // Path items are inlined, OAS 3.0 has no components for them.
+            continue;
+        }
+        let base = component_name(reference, sources);
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while !taken.insert((kind, name.clone())) {
+            suffix += 1;
+            name = format!("{}{}", base, suffix);
+        }
+        renames.insert(
+            reference.clone(),
+            format!("#/{}/{}/{}", COMPONENTS_FIELD, kind, name),
+        );
+        moved.push((*kind, name, value.clone()));
+    }
+
+    let inlined: HashMap<String, Value> = targets
+        .iter()
+        .filter(|(_, kind)| *kind == PATH_ITEMS_FIELD)
+        .filter_map(|(reference, _)| {
+            let path_item = resolve_reference(&specification, reference)?;
+            Some((reference.clone(), path_item.clone()))
+        })
+        .collect();
+
+    let mut bundled = specification.clone();
+    let root = match bundled.as_object_mut() {
+        Some(root) => root,
+        None => {
+            return Err(BundleError(
+                "The root document is not an object".to_string(),
+            ))
+        }
+    };
+    root.remove(EXTERNAL_DOCUMENTS_FIELD);
+    let components = root
+        .entry(COMPONENTS_FIELD)
+        .or_insert_with(|| Value::Object(Map::new()));
+    for (kind, name, value) in moved {
+        if let Value::Object(components) = components {
+            let section = components
+                .entry(kind)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(section) = section {
+                section.insert(name, value);
+            }
+        }
+    }
+    rewrite_refs(&mut bundled, &renames, &inlined);
+    Ok(bundled)
+}
+
+/// Finds the references into embedded documents that are reachable from the root document,
+/// with the kind of component each is of (e.g. `schemas` or `parameters`).
+struct ReferenceCollector<'s> {
+    specification: &'s Value,
+    targets: Vec<(String, &'static str)>,
+    seen: HashSet<String>,
+}
+
+impl ReferenceCollector<'_> {
+    fn walk_root(&mut self) {
+        if let Value::Object(root) = self.specification {
+            for (key, value) in root {
+                if key != EXTERNAL_DOCUMENTS_FIELD {
+                    self.walk_field(key, value);
+                }
+            }
+        }
+    }
+
+    /// Walks the value of a field of a part of the document that is not a schema.
+    fn walk_field(&mut self, key: &str, value: &Value) {
+        match (key, value) {
+            (SCHEMA_FIELD, schema) => self.visit(schema, SCHEMAS_FIELD),
+            (PARAMETERS_FIELD, Value::Array(parameters)) => parameters
+                .iter()
+                .for_each(|parameter| self.visit(parameter, PARAMETERS_FIELD)),
+            (PARAMETERS_FIELD, Value::Object(parameters)) => parameters
+                .values()
+                .for_each(|parameter| self.visit(parameter, PARAMETERS_FIELD)),
+            ("requestBody", request_body) => self.visit(request_body, REQUEST_BODIES_FIELD),
+            (PATHS_FIELD | "webhooks", Value::Object(path_items)) => path_items
+                .values()
+                .for_each(|path_item| self.visit(path_item, PATH_ITEMS_FIELD)),
+            (CALLBACKS_FIELD, Value::Object(callbacks)) => callbacks
+                .values()
+                .for_each(|callback| self.visit(callback, CALLBACKS_FIELD)),
+            (key, Value::Object(components)) if COMPONENT_MAP_FIELDS.contains(&key) => {
+                let kind = COMPONENT_MAP_FIELDS
+                    .into_iter()
+                    .find(|field| *field == key)
+                    .unwrap_or(SCHEMAS_FIELD);
+                components
+                    .values()
+                    .for_each(|component| self.visit(component, kind));
+            }
+            (key, _) if INSTANCE_KEYWORDS.contains(&key) => {}
+            (_, Value::Object(map)) => map
+                .iter()
+                .for_each(|(key, value)| self.walk_field(key, value)),
+            (_, Value::Array(items)) => items.iter().for_each(|item| self.walk_field("", item)),
+            _ => {}
+        }
+    }
+
+    /// Visits a node that may be a reference to a component of `kind`.
+    fn visit(&mut self, node: &Value, kind: &'static str) {
+        if let Some(reference) = node.get(REF_FIELD).and_then(Value::as_str) {
+            self.reference(reference, kind);
+        }
+        match (kind, node) {
+            (SCHEMAS_FIELD, schema) => self.walk_schema(schema),
+            // A callback maps expressions to path items.
+            (CALLBACKS_FIELD, Value::Object(path_items)) => path_items
+                .values()
+                .for_each(|path_item| self.visit(path_item, PATH_ITEMS_FIELD)),
+            (_, Value::Object(map)) => map
+                .iter()
+                .for_each(|(key, value)| self.walk_field(key, value)),
+            _ => {}
+        }
+    }
+
+    fn walk_schema(&mut self, schema: &Value) {
+        let map = match schema {
+            Value::Object(map) if !map.contains_key(ID_FIELD) => map,
+            _ => return,
+        };
+        for (key, value) in map {
+            match (key.as_str(), value) {
+                (key, _) if INSTANCE_KEYWORDS.contains(&key) || key == "examples" => {}
+                (DISCRIMINATOR_FIELD, discriminator) => {
+                    if let Some(Value::Object(mapping)) = discriminator.get(MAPPING_FIELD) {
+                        for target in mapping.values().filter_map(Value::as_str) {
+                            self.reference(target, SCHEMAS_FIELD);
+                        }
+                    }
+                }
+                (key, Value::Object(subschemas)) if SUBSCHEMA_MAP_KEYWORDS.contains(&key) => {
+                    subschemas
+                        .values()
+                        .for_each(|subschema| self.visit(subschema, SCHEMAS_FIELD));
+                }
+                (_, Value::Object(_)) => self.visit(value, SCHEMAS_FIELD),
+                (_, Value::Array(items)) => items
+                    .iter()
+                    .for_each(|item| self.visit(item, SCHEMAS_FIELD)),
+                _ => {}
+            }
+        }
+    }
+
+    /// Records a reference into an embedded document, and walks what it references once.
+    fn reference(&mut self, reference: &str, kind: &'static str) {
+        let prefix = format!("{}{}/", LOCAL_PREFIX, EXTERNAL_DOCUMENTS_FIELD);
+        if !reference.starts_with(&prefix) || !self.seen.insert(reference.to_string()) {
+            return;
+        }
+        self.targets.push((reference.to_string(), kind));
+        if let Some(target) = resolve_reference(self.specification, reference) {
+            self.visit(target, kind);
+        }
+    }
+}
+
+/// Rewrites the references into embedded documents into references to the components they
+/// were moved to, and inlines the path items.
+fn rewrite_refs(
+    value: &mut Value,
+    renames: &HashMap<String, String>,
+    inlined: &HashMap<String, Value>,
+) {
+    let inline = value
+        .get(REF_FIELD)
+        .and_then(Value::as_str)
+        .and_then(|reference| inlined.get(reference));
+    if let Some(path_item) = inline {
+        let mut path_item = path_item.clone();
+        if let (Value::Object(path_item), Value::Object(map)) = (&mut path_item, &*value) {
+            for (key, sibling) in map.iter().filter(|(key, _)| *key != REF_FIELD) {
+                path_item.insert(key.clone(), sibling.clone());
+            }
+        }
+        *value = path_item;
+    }
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value) {
+                    (REF_FIELD, Value::String(reference)) => {
+                        if let Some(renamed) = renames.get(reference.as_str()) {
+                            *reference = renamed.clone();
+                        }
+                    }
+                    (DISCRIMINATOR_FIELD, discriminator) => {
+                        if let Some(Value::Object(mapping)) = discriminator.get_mut(MAPPING_FIELD) {
+                            for target in mapping.values_mut() {
+                                let renamed =
+                                    target.as_str().and_then(|target| renames.get(target));
+                                if let Some(renamed) = renamed {
+                                    *target = Value::String(renamed.clone());
+                                }
+                            }
+                        }
+                    }
+                    (key, _) if INSTANCE_KEYWORDS.contains(&key) => {}
+                    (_, value) => rewrite_refs(value, renames, inlined),
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| rewrite_refs(item, renames, inlined)),
+        _ => {}
+    }
+}
+
+/// The name of the component a part of an embedded document is moved to: the last segment of
+/// its pointer (e.g. `User` for `user.yaml#/User`), or the name of its file for a whole document.
+/// Characters not allowed in component names are replaced with `_`.
+fn component_name(reference: &str, sources: &[String]) -> String {
+    let prefix = format!("{}{}/", LOCAL_PREFIX, EXTERNAL_DOCUMENTS_FIELD);
+    let pointer = reference.strip_prefix(&prefix).unwrap_or(reference);
+    let (index, fragment) = match pointer.split_once('/') {
+        Some((index, fragment)) => (index, fragment),
+        None => (pointer, ""),
+    };
+    let name = match fragment.rsplit('/').find(|segment| !segment.is_empty()) {
+        Some(segment) => segment.replace("~1", "/").replace("~0", "~"),
+        None => {
+            let source = index
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| sources.get(index))
+                .map(String::as_str)
+                .unwrap_or(index);
+            let file = source.rsplit(['/', '\\', ':']).next().unwrap_or(source);
+            match file.split_once('.') {
+                Some((stem, _)) => stem.to_string(),
+                None => file.to_string(),
+            }
+        }
+    };
+    let name: String = name
+        .chars()
+        .map(
+            |c| match c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '_') {
+                true => c,
+                false => '_',
+            },
+        )
+        .collect();
+    match name.is_empty() {
+        true => "Component".to_string(),
+        false => name,
+    }
+}
+
+/// Replaces every local `$ref` with a copy of what it references, except for those that would
+/// recurse: a reference to a node it is itself inside of is kept.
+///
+/// Reference chains are followed, and recursion detected, by
+/// `OpenApiTraverser::get_reference_path`, with the references being expanded as the seen ones.
+fn dereference(specification: &Value) -> Value {
+    // A component is inside of itself, so that its recursive references point to it.
+    let mut own_pointers = vec![];
+    if let Some(Value::Object(components)) = specification.get(COMPONENTS_FIELD) {
+        for (kind, section) in components {
+            if let Value::Object(section) = section {
+                for name in section.keys() {
+                    own_pointers.push((
+                        kind.clone(),
+                        name.clone(),
+                        format!("#/{}/{}/{}", COMPONENTS_FIELD, kind, escape(name)),
+                    ));
+                }
+            }
+        }
+    }
+
+    let mut dereferenced = dereference_node(specification, specification, &HashSet::new());
+    if let Some(Value::Object(components)) = dereferenced.get_mut(COMPONENTS_FIELD) {
+        for (kind, name, pointer) in &own_pointers {
+            let component = specification
+                .get(COMPONENTS_FIELD)
+                .and_then(|components| components.get(kind))
+                .and_then(|section| section.get(name));
+            let (component, section) = match (component, components.get_mut(kind)) {
+                (Some(component), Some(Value::Object(section))) => (component, section),
+                _ => continue,
+            };
+            let expanding = HashSet::from([pointer.as_str()]);
+            section.insert(
+                name.clone(),
+                dereference_node(specification, component, &expanding),
+            );
+        }
+    }
+    dereferenced
+}
+
+fn dereference_node<'s>(
+    specification: &'s Value,
+    node: &'s Value,
+    expanding: &HashSet<&'s str>,
+) -> Value {
+    let map = match node {
+        Value::Object(map) => map,
+        Value::Array(items) => {
+            return Value::Array(
+                items
+                    .iter()
+                    .map(|item| dereference_node(specification, item, expanding))
+                    .collect(),
+            );
+        }
+        _ => return node.clone(),
+    };
+    if map.contains_key(ID_FIELD) {
+        // The references of a schema with its own base URI are relative to it.
+        return node.clone();
+    }
+
+    let reference = map
+        .get(REF_FIELD)
+        .and_then(Value::as_str)
+        .filter(|reference| reference.starts_with(LOCAL_PREFIX));
+    if let Some(reference) = reference {
+        let mut seen = expanding.clone();
+        match OpenApiTraverser::get_reference_path(specification, reference, &mut seen) {
+            Ok(target) if target.get(ID_FIELD).is_none() => {
+                let mut dereferenced = dereference_node(specification, target, &seen);
+                if let Value::Object(dereferenced) = &mut dereferenced {
+                    for (key, sibling) in map.iter().filter(|(key, _)| *key != REF_FIELD) {
+                        dereferenced.insert(
+                            key.clone(),
+                            dereference_node(specification, sibling, expanding),
+                        );
+                    }
+                }
+                return dereferenced;
+            }
+            // Recursive, unresolvable, or a schema with its own base URI: the reference stays.
+            _ => {}
+        }
+    }
+
+    let mut dereferenced = Map::new();
+    for (key, value) in map {
+        let value = match key.as_str() {
+            key if INSTANCE_KEYWORDS.contains(&key) => value.clone(),
+            _ => dereference_node(specification, value, expanding),
+        };
+        dereferenced.insert(key.clone(), value);
+    }
+    Value::Object(dereferenced)
+}
+
+/// Removes the components that are not referenced, directly or through other components, from
+/// outside of `components`. Sections left empty are removed, and so is `components` if empty.
+fn prune_components(specification: &mut Value) {
+    let mut used: HashSet<String> = HashSet::new();
+    let mut pending = vec![];
+    if let Value::Object(root) = &*specification {
+        for (key, value) in root.iter().filter(|(key, _)| *key != COMPONENTS_FIELD) {
+            collect_references(key, value, &mut pending);
+        }
+    }
+    while let Some(reference) = pending.pop() {
+        let component = match component_of(&reference) {
+            Some(component) => component,
+            None => continue,
+        };
+        if !used.insert(component.clone()) {
+            continue;
+        }
+        if let Some(value) = resolve_reference(specification, &component) {
+            collect_references("", value, &mut pending);
+        }
+    }
+
+    let components = match specification.get_mut(COMPONENTS_FIELD) {
+        Some(Value::Object(components)) => components,
+        _ => return,
+    };
+    for (kind, section) in components.iter_mut() {
+        if kind == SECURITY_SCHEMES_FIELD {
+            continue;
+        }
+        if let Value::Object(section) = section {
+            section.retain(|name, _| {
+                let pointer = format!("#/{}/{}/{}", COMPONENTS_FIELD, kind, escape(name));
+                used.contains(&pointer)
+            });
+        }
+    }
+    components
+        .retain(|_, section| !matches!(section, Value::Object(section) if section.is_empty()));
+    if let (true, Value::Object(root)) = (components.is_empty(), specification) {
+        root.remove(COMPONENTS_FIELD);
+    }
+}
+
+/// Collects the local references of a node, `$ref`s and discriminator mappings.
+fn collect_references(key: &str, value: &Value, references: &mut Vec<String>) {
+    match (key, value) {
+        (key, _) if INSTANCE_KEYWORDS.contains(&key) => {}
+        (DISCRIMINATOR_FIELD, discriminator) => {
+            if let Some(Value::Object(mapping)) = discriminator.get(MAPPING_FIELD) {
+                references.extend(mapping.values().filter_map(Value::as_str).map(String::from));
+            }
+        }
+        (_, Value::Object(map)) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    (REF_FIELD, Value::String(reference)) => references.push(reference.clone()),
+                    (key, value) => collect_references(key, value, references),
+                }
+            }
+        }
+        (_, Value::Array(items)) => items
+            .iter()
+            .for_each(|item| collect_references("", item, references)),
+        _ => {}
+    }
+}
+
+/// The pointer of the component a local reference is to, or into, e.g.
+/// `#/components/schemas/User` for `#/components/schemas/User/properties/id`.
+fn component_of(reference: &str) -> Option<String> {
+    let pointer = reference.strip_prefix(LOCAL_PREFIX)?;
+    let mut segments = pointer.splitn(4, '/');
+    match (segments.next(), segments.next(), segments.next()) {
+        (Some(COMPONENTS_FIELD), Some(kind), Some(name)) => {
+            Some(format!("#/{}/{}/{}", COMPONENTS_FIELD, kind, name))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const SPEC: &str = "./test/multi-file/openapi.yaml";
+
+    fn bundler() -> Bundler {
+        Bundler::new().sandbox("./test/multi-file")
+    }
+
+    #[test]
+    fn test_bundle() {
+        let bundled = bundler().bundle_file(SPEC).unwrap();
+        assert!(bundled.get(EXTERNAL_DOCUMENTS_FIELD).is_none());
+        let operation = &bundled["paths"]["/users"]["post"];
+        assert_eq!(
+            operation["requestBody"]["content"]["application/json"]["schema"],
+            json!({ "$ref": "#/components/schemas/User" })
+        );
+        let schemas = &bundled["components"]["schemas"];
+        assert_eq!(
+            schemas["User"]["properties"]["id"],
+            json!({ "$ref": "#/components/schemas/Id" })
+        );
+        assert_eq!(
+            schemas["User"]["properties"]["address"],
+            json!({ "$ref": "#/components/schemas/Address" })
+        );
+        assert_eq!(
+            schemas["Address"]["properties"]["resident"],
+            json!({ "$ref": "#/components/schemas/User" })
+        );
+        assert_eq!(
+            bundled["components"]["parameters"]["Tracing"]["name"],
+            "x-trace-id"
+        );
+
+        // The bundled document validates like the files it came from.
+        let validator = crate::validator::builder::OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(bundled)
+            .build()
+            .unwrap();
+        assert!(validator.find_operation("/users", "POST").is_ok());
+    }
+
+    #[test]
+    fn test_conflicting_names() {
+        let mut specification = loader::parse_specification(
+            &std::fs::read_to_string(SPEC).unwrap(),
+            SpecificationFormat::from_path(SPEC),
+        )
+        .unwrap();
+        specification["components"] = json!({
+            "schemas": { "User": { "type": "string" } }
+        });
+        let bundled = bundler().bundle(specification).unwrap();
+        let schemas = &bundled["components"]["schemas"];
+        assert_eq!(schemas["User"], json!({ "type": "string" }));
+        assert_eq!(schemas["User2"]["type"], "object");
+        assert_eq!(
+            schemas["Address"]["properties"]["resident"],
+            json!({ "$ref": "#/components/schemas/User2" })
+        );
+    }
+
+    #[test]
+    fn test_dereference() {
+        let dereferenced = bundler()
+            .dereference(true)
+            .prune_components(true)
+            .bundle_file(SPEC)
+            .unwrap();
+        let operation = &dereferenced["paths"]["/users"]["post"];
+        assert_eq!(operation["parameters"][0]["name"], "x-trace-id");
+        let user = &operation["requestBody"]["content"]["application/json"]["schema"];
+        assert_eq!(
+            user["properties"]["id"],
+            json!({ "type": "integer", "minimum": 1 })
+        );
+        // The recursion through the address is the only reference left.
+        assert_eq!(
+            user["properties"]["address"]["properties"]["resident"],
+            json!({ "$ref": "#/components/schemas/User" })
+        );
+        let schemas = dereferenced["components"]["schemas"].as_object().unwrap();
+        assert_eq!(schemas.keys().collect::<Vec<_>>(), vec!["User"]);
+        assert!(dereferenced["components"].get("parameters").is_none());
+        assert_eq!(
+            schemas["User"]["properties"]["address"]["properties"]["resident"],
+            json!({ "$ref": "#/components/schemas/User" })
+        );
+    }
+
+    #[test]
+    fn test_prune_components() {
+        let pruned = Bundler::new()
+            .prune_components(true)
+            .bundle(json!({
+                "openapi": "3.1.0",
+                "info": { "title": "Pets", "version": "1.0.0" },
+                "paths": {
+                    "/pets": {
+                        "get": {
+                            "responses": {
+                                "200": { "$ref": "#/components/responses/Pets" }
+                            }
+                        }
+                    }
+                },
+                "components": {
+                    "responses": {
+                        "Pets": {
+                            "description": "Pets",
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Pet/properties/name" }
+                                }
+                            }
+                        },
+                        "Unused": { "description": "Unused" }
+                    },
+                    "schemas": {
+                        "Pet": { "properties": { "name": { "type": "string" } } },
+                        "Orphan": { "$ref": "#/components/schemas/Pet" }
+                    },
+                    "securitySchemes": {
+                        "apiKey": { "type": "apiKey", "name": "key", "in": "header" }
+                    }
+                }
+            }))
+            .unwrap();
+        let components = &pruned["components"];
+        assert_eq!(
+            components["responses"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["Pets"]
+        );
+        assert_eq!(
+            components["schemas"]
+                .as_object()
+                .unwrap()
+                .keys()
+                .collect::<Vec<_>>(),
+            vec!["Pet"]
+        );
+        assert!(components["securitySchemes"]["apiKey"].is_object());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::error::ValidationErrorType;
+use crate::validator::builder::{OpenApiPayloadValidatorBuilder, ValidatorBuilderError};
 use crate::validator::OpenApiPayloadValidator;
 use dashmap::{DashMap, Entry, VacantEntry};
-use serde_json::Value;
 use std::fmt::{Display, Formatter};
 use std::hash::Hash;
 use std::path::Path;
@@ -67,51 +67,58 @@ where
     where
         P: AsRef<Path>,
     {
-        let path = file_path.as_ref();
-        let content = match std::fs::read_to_string(path) {
-            Ok(x) => x,
-            Err(e) => return Err(CacheError::io_error(format!("Could not read file: {}", e))),
-        };
-        let content: Value = match serde_json::from_str(&content) {
-            Ok(val) => val,
-            Err(e) => {
-                return Err(CacheError::io_error(format!(
-                    "Could not parse content as JSON: {}",
-                    e
-                )));
-            }
-        };
-        self.insert(id, content)
+        let path = file_path.as_ref().to_string_lossy().into_owned();
+        self.insert_builder(id, OpenApiPayloadValidatorBuilder::new().load_from_file(path))
     }
 
     pub fn insert<V>(&self, id: K, spec: V) -> Result<Arc<OpenApiPayloadValidator>, CacheError>
     where
         V: serde::Serialize,
     {
+        let spec = match serde_json::to_value(spec) {
+            Ok(val) => val,
+            Err(e) => {
+                return Err(CacheError::io_error(format!("Could not serialize content: {}", e)));
+            }
+        };
+        self.insert_builder(id, OpenApiPayloadValidatorBuilder::new().load_from_value(spec))
+    }
+
+    /// Builds a validator and caches it under `id`. The builder must have a specification loader,
+    /// its options apply as they do to `OpenApiPayloadValidatorBuilder::build`.
+    ///
+    /// # Arguments
+    /// * `id` - The id to cache the validator under
+    /// * `builder` - The builder of the validator
+    ///
+    /// # Returns
+    /// * `Ok(Arc<OpenApiPayloadValidator>)` - The cached validator
+    /// * `Err(CacheError)` - If `id` is taken, or the validator cannot be built.
+    pub fn insert_builder(
+        &self,
+        id: K,
+        builder: OpenApiPayloadValidatorBuilder,
+    ) -> Result<Arc<OpenApiPayloadValidator>, CacheError> {
         match self.cache.entry(id) {
             Entry::Occupied(_) => Err(CacheError::ValidatorAlreadyExists),
-            Entry::Vacant(entry) => Self::create_validator(entry, spec),
+            Entry::Vacant(entry) => Self::create_validator(entry, builder),
         }
     }
 
-    fn create_validator<V>(
+    fn create_validator(
         entry: VacantEntry<K, Arc<OpenApiPayloadValidator>>,
-        spec: V,
-    ) -> Result<Arc<OpenApiPayloadValidator>, CacheError>
-    where
-        V: serde::Serialize,
-    {
-        let spec = match serde_json::to_value(spec) {
-            Ok(val) => val,
-            Err(_) => todo!(),
-        };
-        match OpenApiPayloadValidator::new(spec) {
+        builder: OpenApiPayloadValidatorBuilder,
+    ) -> Result<Arc<OpenApiPayloadValidator>, CacheError> {
+        match builder.build() {
             Ok(validator) => {
                 let validator = Arc::new(validator);
                 entry.insert(validator.clone());
                 Ok(validator)
             }
-            Err(e) => Err(CacheError::FailedToCreateValidator(e)),
+            Err(ValidatorBuilderError::LoadFailure(e)) => Err(CacheError::io_error(e)),
+            Err(e) => Err(CacheError::FailedToCreateValidator(
+                ValidationErrorType::builder_failed(e, "Failed to create new validator."),
+            )),
         }
     }
 
@@ -0,0 +1,877 @@
+//! Translation of OpenAPI schema dialects into plain JSON Schema.
+//!
+//! OAS 3.0 Schema Objects are an extended subset of JSON Schema Draft 4 (actually Draft Wright-00).
+//! Before a 3.0 specification is compiled with `Draft4`, its schemas are rewritten so that the
+//! OpenAPI specific keywords keep the meaning the specification gives them. Alternatively, a 3.0
+//! document can be upgraded to 3.1 as a whole (see `upgrade_oas30_document`).
+//!
+//! OAS 3.1 Schema Objects are plain JSON Schema, in the dialect named by `jsonSchemaDialect` or by
+//! a schema's own `$schema`. As an OpenAPI document is not itself a schema, schemas declaring an
+//! identifier or a different dialect are registered as resources of their own.
+//!
+//! In both versions, a `discriminator` relaxes the `oneOf` it accompanies, where the discriminator
+//! is validated (see `normalize_discriminators`).
+use crate::document::{
+    escape, follow_refs, mapping_ref, SUBSCHEMA_ARRAY_KEYWORDS, SUBSCHEMA_KEYWORDS,
+    SUBSCHEMA_MAP_KEYWORDS,
+};
+use crate::{COMPONENTS_FIELD, CONTENT_FIELD, OPENAPI_FIELD, REF_FIELD, SCHEMA_FIELD, TYPE_FIELD};
+use jsonschema::{Draft, Resource};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+
+const SCHEMAS_FIELD: &str = "schemas";
+const NULLABLE_FIELD: &str = "nullable";
+const ENUM_FIELD: &str = "enum";
+const NULL_TYPE: &str = "null";
+const JSON_SCHEMA_DIALECT_FIELD: &str = "jsonSchemaDialect";
+const DIALECT_FIELD: &str = "$schema";
+const DEFS_FIELD: &str = "$defs";
+const ID_FIELD: &str = "$id";
+const DISCRIMINATOR_FIELD: &str = "discriminator";
+const ONE_OF_FIELD: &str = "oneOf";
+const ANY_OF_FIELD: &str = "anyOf";
+const EXAMPLE_FIELD: &str = "example";
+const EXAMPLES_FIELD: &str = "examples";
+const FORMAT_FIELD: &str = "format";
+const CONTENT_MEDIA_TYPE_FIELD: &str = "contentMediaType";
+const BINARY_FORMAT: &str = "binary";
+const OCTET_STREAM_MEDIA_TYPE: &str = "application/octet-stream";
+
+/// The version of the documents `upgrade_oas30_document` produces.
+const OAS31_VERSION: &str = "3.1.0";
+
+/// Prefix of the OAS 3.1 dialect identifiers, which extend Draft 2020-12.
+const OAS31_DIALECT_PREFIX: &str = "https://spec.openapis.org/oas/3.1/dialect/";
+
+/// Keywords which make a schema addressable by something other than a JSON pointer.
+const IDENTIFIER_KEYWORDS: [&str; 3] = [ID_FIELD, "$anchor", "$dynamicAnchor"];
+
+/// Keywords the `DiscriminatorWalker` follows from a schema to the schemas of the parts of a
+/// payload.
+const FOLLOWED_KEYWORDS: [&str; 5] = [
+    "allOf",
+    "properties",
+    "additionalProperties",
+    "items",
+    "prefixItems",
+];
+
+/// OAS 3.0 keywords that carry no validation meaning and are not part of JSON Schema.
+const ANNOTATION_KEYWORDS: [&str; 3] = ["example", "xml", "externalDocs"];
+
+/// Calls `visit` with every top-level Schema Object of an OpenAPI document.
+///
+/// Schemas are found under `components/schemas` and in every `schema` field (parameters,
+/// headers and media types). Examples are left untouched.
+fn for_each_schema(specification: &mut Value, visit: &mut dyn FnMut(&mut Value)) {
+    if let Some(schemas) = specification
+        .get_mut(COMPONENTS_FIELD)
+        .and_then(|components| components.get_mut(SCHEMAS_FIELD))
+        .and_then(Value::as_object_mut)
+    {
+        schemas.values_mut().for_each(&mut *visit);
+    }
+
+    if let Some(document) = specification.as_object_mut() {
+        for (key, value) in document.iter_mut() {
+            if key == COMPONENTS_FIELD {
+                if let Some(components) = value.as_object_mut() {
+                    for (component_type, component) in components.iter_mut() {
+                        if component_type != SCHEMAS_FIELD {
+                            for_each_nested_schema(component, visit);
+                        }
+                    }
+                }
+            } else {
+                for_each_nested_schema(value, visit);
+            }
+        }
+    }
+}
+
+/// Walks a non-schema part of the document and visits the value of every `schema` field.
+fn for_each_nested_schema(value: &mut Value, visit: &mut dyn FnMut(&mut Value)) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if key == SCHEMA_FIELD {
+                    visit(value);
+                } else if key != "example" && key != "examples" {
+                    for_each_nested_schema(value, visit);
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| for_each_nested_schema(item, visit)),
+        _ => {}
+    }
+}
+
+/// Rewrites the `oneOf` of the schemas with a `discriminator` into an `anyOf`, where the
+/// `DiscriminatorValidator` covers them.
+///
+/// The discriminator names the subschema a payload is meant for, which is validated on its own
+/// before the body as a whole. A payload matching other subschemas as well is therefore valid,
+/// and the remaining subschemas only need evaluating until one matches. This only holds for the
+/// schemas the validator reaches: from request and response bodies, through `$ref`s, `allOf`,
+/// `properties`, `additionalProperties`, `items`, `prefixItems` and the subschemas discriminators
+/// select. A schema that is also used anywhere else (e.g. by a parameter or under a `not`) keeps
+/// its `oneOf`.
+pub(crate) fn normalize_discriminators(specification: &mut Value) {
+    let mut pending = vec![];
+    collect_schema_roots(specification, &mut vec![], &mut pending);
+
+    // Each schema is reached either only through the keywords the validator follows from a
+    // body (`true`), or also in another way (`false`).
+    let mut reached: HashSet<(String, bool)> = HashSet::new();
+    while let Some((pointer, covered)) = pending.pop() {
+        if !reached.insert((pointer.clone(), covered)) {
+            continue;
+        }
+        if let Some(schema) = specification.pointer(&pointer) {
+            push_subschemas(specification, schema, &pointer, covered, &mut pending);
+        }
+    }
+
+    for (pointer, covered) in &reached {
+        if !covered || reached.contains(&(pointer.clone(), false)) {
+            continue;
+        }
+        if let Some(Value::Object(schema)) = specification.pointer_mut(pointer) {
+            let discriminated = schema
+                .get(DISCRIMINATOR_FIELD)
+                .is_some_and(|discriminator| discriminator.get("propertyName").is_some());
+            if discriminated
+                && !schema.contains_key(ANY_OF_FIELD)
+                && schema.contains_key(ONE_OF_FIELD)
+            {
+                let subschemas = schema.remove(ONE_OF_FIELD).unwrap_or_default();
+                schema.insert(ANY_OF_FIELD.to_string(), subschemas);
+            }
+        }
+    }
+}
+
+/// Collects the pointer of every `schema` field outside of Schema Objects, flagged with whether
+/// it is the schema of a request or response body.
+fn collect_schema_roots(value: &Value, path: &mut Vec<String>, roots: &mut Vec<(String, bool)>) {
+    let map = match value {
+        Value::Object(map) => map,
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                path.push(index.to_string());
+                collect_schema_roots(item, path, roots);
+                path.pop();
+            }
+            return;
+        }
+        _ => return,
+    };
+    for (key, value) in map {
+        // Component schemas are only used through references.
+        let is_component_schemas =
+            key == SCHEMAS_FIELD && path.len() == 1 && path[0] == COMPONENTS_FIELD;
+        if key == EXAMPLE_FIELD || key == EXAMPLES_FIELD || is_component_schemas {
+            continue;
+        }
+        path.push(escape(key));
+        if key == SCHEMA_FIELD {
+            roots.push((format!("/{}", path.join("/")), is_body_schema(path)));
+        } else {
+            collect_schema_roots(value, path, roots);
+        }
+        path.pop();
+    }
+}
+
+/// Whether a `schema` field belongs to the content of a Request Body or Response Object, e.g.
+/// `.../requestBody/content/{media type}/schema` or `.../responses/200/content/{media type}/schema`.
+fn is_body_schema(path: &[String]) -> bool {
+    let length = path.len();
+    if length < 5 || path[length - 3] != CONTENT_FIELD {
+        return false;
+    }
+    let (parent, owner) = (path[length - 5].as_str(), path[length - 4].as_str());
+    match parent {
+        "responses" | "requestBodies" => true,
+        "headers" | "parameters" => false,
+        _ => owner == "requestBody",
+    }
+}
+
+/// Queues the subschemas of a schema, flagged as covered when the schema is and the
+/// `DiscriminatorValidator` follows the keyword.
+fn push_subschemas(
+    document: &Value,
+    schema: &Value,
+    pointer: &str,
+    covered: bool,
+    pending: &mut Vec<(String, bool)>,
+) {
+    let map = match schema.as_object() {
+        Some(map) => map,
+        None => return,
+    };
+    let push_ref = |pending: &mut Vec<(String, bool)>, node: &Value, node_pointer: String| {
+        let (target, target_pointer) = follow_refs(document, node, node_pointer.clone());
+        if target_pointer != node_pointer && target.get(REF_FIELD).is_none() {
+            pending.push((target_pointer, covered));
+        }
+    };
+    push_ref(pending, schema, pointer.to_string());
+
+    let discriminated = map
+        .get(DISCRIMINATOR_FIELD)
+        .is_some_and(|discriminator| discriminator.get("propertyName").is_some());
+    if discriminated {
+        // Mapped schemas are selected, and validated on their own.
+        if let Some(mapping) = map
+            .get(DISCRIMINATOR_FIELD)
+            .and_then(|discriminator| discriminator.get("mapping"))
+            .and_then(Value::as_object)
+        {
+            for target in mapping.values().filter_map(Value::as_str) {
+                let mut reference = Map::new();
+                reference.insert(REF_FIELD.to_string(), Value::String(mapping_ref(target)));
+                push_ref(pending, &Value::Object(reference), pointer.to_string());
+            }
+        }
+    }
+
+    for (keyword, value) in map {
+        let keyword = keyword.as_str();
+        let followed = FOLLOWED_KEYWORDS.contains(&keyword);
+        let keyword_pointer = format!("{}/{}", pointer, escape(keyword));
+        if SUBSCHEMA_MAP_KEYWORDS.contains(&keyword) {
+            // Definitions are only used through references.
+            if keyword == DEFS_FIELD || keyword == "definitions" {
+                continue;
+            }
+            if let Some(subschemas) = value.as_object() {
+                for name in subschemas.keys() {
+                    let subschema_pointer = format!("{}/{}", keyword_pointer, escape(name));
+                    pending.push((subschema_pointer, covered && followed));
+                }
+            }
+        } else if SUBSCHEMA_ARRAY_KEYWORDS.contains(&keyword)
+            || SUBSCHEMA_KEYWORDS.contains(&keyword)
+        {
+            let subschemas = match value {
+                Value::Array(subschemas) => subschemas.iter().collect(),
+                Value::Object(_) => vec![value],
+                _ => continue,
+            };
+            let selectable = discriminated && (keyword == ONE_OF_FIELD || keyword == ANY_OF_FIELD);
+            for (index, subschema) in subschemas.into_iter().enumerate() {
+                let subschema_pointer = match value.is_array() {
+                    true => format!("{}/{}", keyword_pointer, index),
+                    false => keyword_pointer.clone(),
+                };
+                if selectable && subschema.get(REF_FIELD).is_some() {
+                    // The referenced subschemas of a discriminator are selected by name.
+                    push_ref(pending, subschema, subschema_pointer);
+                } else {
+                    pending.push((subschema_pointer, covered && followed));
+                }
+            }
+        }
+    }
+}
+
+/// Rewrites every Schema Object in an OAS 3.0 document into an equivalent Draft 4 schema.
+pub(crate) fn normalize_oas30_document(specification: &mut Value) {
+    for_each_schema(specification, &mut normalize_oas30_schema);
+    for_each_referenced_schema(specification, &mut normalize_oas30_schema);
+}
+
+/// Rewrites an OAS 3.0 document into an equivalent OAS 3.1 document, whose Schema Objects are
+/// Draft 2020-12 schemas.
+pub(crate) fn upgrade_oas30_document(specification: &mut Value) {
+    for_each_schema(specification, &mut upgrade_oas30_schema);
+    for_each_referenced_schema(specification, &mut upgrade_oas30_schema);
+    specification[OPENAPI_FIELD] = json!(OAS31_VERSION);
+}
+
+/// Visits the schemas only reachable through a `$ref` from another schema, such as those of
+/// embedded external documents. Schemas can be visited more than once, so `visit` must leave a
+/// schema it already rewrote unchanged.
+fn for_each_referenced_schema(specification: &mut Value, visit: &mut dyn FnMut(&mut Value)) {
+    let mut pending = vec![];
+    for_each_schema(specification, &mut |schema| {
+        collect_refs(schema, &mut pending)
+    });
+    let mut visited = HashSet::new();
+    while let Some(reference) = pending.pop() {
+        if !visited.insert(reference.clone()) {
+            continue;
+        }
+        let schema = match reference
+            .strip_prefix('#')
+            .and_then(|pointer| specification.pointer_mut(pointer))
+        {
+            Some(schema) => schema,
+            None => continue,
+        };
+        visit(schema);
+        collect_refs(schema, &mut pending);
+    }
+}
+
+/// Collects the `$ref`s of a schema and its subschemas.
+fn collect_refs(schema: &Value, refs: &mut Vec<String>) {
+    match schema {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    (REF_FIELD, Value::String(reference)) => refs.push(reference.clone()),
+                    ("example" | "examples" | "default" | ENUM_FIELD | "const", _) => {}
+                    (_, value) => collect_refs(value, refs),
+                }
+            }
+        }
+        Value::Array(items) => items.iter().for_each(|item| collect_refs(item, refs)),
+        _ => {}
+    }
+}
+
+/// Rewrites a single OAS 3.0 Schema Object (and its subschemas) into a Draft 4 schema.
+///
+/// - `nullable: true` adds `null` to `type` (and to `enum`); without a `type` the schema is
+///   wrapped in `anyOf` with a `null` schema
+/// - `exclusiveMinimum`/`exclusiveMaximum` booleans are kept, as Draft 4 gives them the same
+///   meaning, but are dropped when the bound they modify is missing
+/// - `example`, `xml` and `externalDocs` are removed
+pub(crate) fn normalize_oas30_schema(schema: &mut Value) {
+    let map = match schema.as_object_mut() {
+        Some(map) => map,
+        None => return,
+    };
+    for_each_subschema(map, normalize_oas30_schema);
+
+    for keyword in ANNOTATION_KEYWORDS {
+        map.remove(keyword);
+    }
+    drop_orphaned_exclusive_bound(map, "exclusiveMinimum", "minimum");
+    drop_orphaned_exclusive_bound(map, "exclusiveMaximum", "maximum");
+
+    let nullable = match map.remove(NULLABLE_FIELD) {
+        Some(Value::Bool(nullable)) => nullable,
+        _ => false,
+    };
+    if nullable {
+        make_nullable(schema);
+    }
+}
+
+/// Rewrites a single OAS 3.0 Schema Object (and its subschemas) into a Draft 2020-12 schema.
+///
+/// - `nullable: true` adds `null` to `type` (and to `enum`), as in `normalize_oas30_schema`
+/// - `exclusiveMinimum`/`exclusiveMaximum` booleans take the value of the bound they modify
+/// - `example` becomes a single item `examples`
+/// - `format: binary` becomes `contentMediaType: application/octet-stream`
+pub(crate) fn upgrade_oas30_schema(schema: &mut Value) {
+    let map = match schema.as_object_mut() {
+        Some(map) => map,
+        None => return,
+    };
+    for_each_subschema(map, upgrade_oas30_schema);
+
+    if let Some(example) = map.remove(EXAMPLE_FIELD) {
+        map.entry(EXAMPLES_FIELD)
+            .or_insert_with(|| json!([example]));
+    }
+    upgrade_exclusive_bound(map, "exclusiveMinimum", "minimum");
+    upgrade_exclusive_bound(map, "exclusiveMaximum", "maximum");
+    if map
+        .get(FORMAT_FIELD)
+        .is_some_and(|format| format == BINARY_FORMAT)
+    {
+        map.remove(FORMAT_FIELD);
+        map.insert(
+            CONTENT_MEDIA_TYPE_FIELD.to_string(),
+            json!(OCTET_STREAM_MEDIA_TYPE),
+        );
+    }
+
+    let nullable = match map.remove(NULLABLE_FIELD) {
+        Some(Value::Bool(nullable)) => nullable,
+        _ => false,
+    };
+    if nullable {
+        make_nullable(schema);
+    }
+}
+
+fn for_each_subschema(map: &mut Map<String, Value>, visit: fn(&mut Value)) {
+    for keyword in SUBSCHEMA_KEYWORDS {
+        match map.get_mut(keyword) {
+            Some(subschema @ Value::Object(_)) => visit(subschema),
+            Some(Value::Array(subschemas)) => subschemas.iter_mut().for_each(visit),
+            _ => {}
+        }
+    }
+    for keyword in SUBSCHEMA_ARRAY_KEYWORDS {
+        if let Some(Value::Array(subschemas)) = map.get_mut(keyword) {
+            subschemas.iter_mut().for_each(visit);
+        }
+    }
+    for keyword in SUBSCHEMA_MAP_KEYWORDS {
+        if let Some(Value::Object(subschemas)) = map.get_mut(keyword) {
+            subschemas.values_mut().for_each(visit);
+        }
+    }
+}
+
+fn upgrade_exclusive_bound(map: &mut Map<String, Value>, exclusive: &str, bound: &str) {
+    match map.get(exclusive) {
+        Some(Value::Bool(true)) => match map.remove(bound) {
+            Some(bound) => {
+                map.insert(exclusive.to_string(), bound);
+            }
+            None => {
+                map.remove(exclusive);
+            }
+        },
+        Some(Value::Bool(false)) => {
+            map.remove(exclusive);
+        }
+        _ => {}
+    }
+}
+
+fn drop_orphaned_exclusive_bound(map: &mut Map<String, Value>, exclusive: &str, bound: &str) {
+    if map.get(exclusive).is_some_and(Value::is_boolean) && !map.contains_key(bound) {
+        map.remove(exclusive);
+    }
+}
+
+fn make_nullable(schema: &mut Value) {
+    let map = match schema.as_object_mut() {
+        Some(map) => map,
+        None => return,
+    };
+    match map.get_mut(TYPE_FIELD) {
+        Some(Value::String(schema_type)) => {
+            let schema_type = schema_type.clone();
+            map.insert(TYPE_FIELD.to_string(), json!([schema_type, NULL_TYPE]));
+        }
+        Some(Value::Array(types)) => {
+            if !types.iter().any(|t| t == NULL_TYPE) {
+                types.push(json!(NULL_TYPE));
+            }
+        }
+        _ => {
+            let inner = std::mem::take(map);
+            *schema = json!({ "anyOf": [Value::Object(inner), { TYPE_FIELD: NULL_TYPE }] });
+            return;
+        }
+    }
+    match map.get_mut(ENUM_FIELD) {
+        Some(Value::Array(values)) if !values.contains(&Value::Null) => values.push(Value::Null),
+        _ => {}
+    }
+}
+
+/// Resolves the draft the Schema Objects of an OAS 3.1 document default to.
+///
+/// # Arguments
+/// * `specification` - The OpenAPI document
+///
+/// # Returns
+/// * `Ok(Draft)` - The draft named by `jsonSchemaDialect`, or Draft 2020-12 when it is missing
+/// * `Err(String)` - If the dialect is not a known JSON Schema draft.
+pub(crate) fn resolve_oas31_draft(specification: &Value) -> Result<Draft, String> {
+    match specification.get(JSON_SCHEMA_DIALECT_FIELD) {
+        None => Ok(Draft::Draft202012),
+        Some(Value::String(dialect)) => dialect_draft(dialect),
+        Some(other) => Err(format!(
+            "'{}' must be a string, found {}",
+            JSON_SCHEMA_DIALECT_FIELD, other
+        )),
+    }
+}
+
+/// Returns the draft a schema declares with `$schema`, if it declares one.
+pub(crate) fn declared_draft(schema: &Value) -> Option<Result<Draft, String>> {
+    schema
+        .get(DIALECT_FIELD)
+        .and_then(Value::as_str)
+        .map(dialect_draft)
+}
+
+/// The keyword a draft uses to declare a schema's base URI.
+pub(crate) fn id_keyword(draft: Draft) -> &'static str {
+    match draft {
+        Draft::Draft4 => "id",
+        _ => "$id",
+    }
+}
+
+fn dialect_draft(dialect: &str) -> Result<Draft, String> {
+    if dialect.starts_with(OAS31_DIALECT_PREFIX) {
+        return Ok(Draft::Draft202012);
+    }
+    match Draft::default().detect(&json!({ DIALECT_FIELD: dialect })) {
+        Ok(draft) => Ok(draft),
+        Err(_) => Err(format!("Unsupported JSON Schema dialect '{}'", dialect)),
+    }
+}
+
+/// Prepares the resource copy of an OAS 3.1 document and returns the extra resources it needs.
+///
+/// The schema compiler only looks for `$id`, `$anchor` and `$dynamicAnchor` in schema keywords,
+/// so schemas declaring them are mirrored under the document's `$defs` to be registered under
+/// the root resource. Schemas with an `$id` are replaced by a `$ref` to their mirror, so that
+/// references to them enter their scope. Schemas declaring a `$schema` different from `draft` are moved into a
+/// resource of their own, compiled with their draft, and replaced by a `$ref` to it.
+///
+/// # Arguments
+/// * `document` - The copy of the specification the root resource is created from
+/// * `root_id` - The base URI of the root resource
+/// * `draft` - The draft of the root resource
+///
+/// # Returns
+/// * `Ok(Vec<(String, Resource)>)` - The resources to register next to the root resource
+/// * `Err(String)` - If a schema declares an unknown dialect.
+pub(crate) fn embed_oas31_schemas(
+    document: &mut Value,
+    root_id: &str,
+    draft: Draft,
+) -> Result<Vec<(String, Resource)>, String> {
+    let mut resources = vec![];
+    let mut mirrored = Map::new();
+    let mut error = None;
+    for_each_schema(document, &mut |schema| match declared_draft(schema) {
+        Some(Ok(declared)) if declared != draft => {
+            let uri = format!("{}-schema-{}", root_id, resources.len());
+            let mut contents = std::mem::replace(schema, json!({ REF_FIELD: uri }));
+            qualify_local_refs(&mut contents, root_id);
+            resources.push((uri, declared.create_resource(contents)));
+        }
+        Some(Err(e)) => error = Some(e),
+        _ => {
+            if declares_identifier(schema) {
+                let name = mirrored.len().to_string();
+                let reference = json!({ REF_FIELD: format!("#/{}/{}", DEFS_FIELD, name) });
+                // JSON pointers do not enter the scope of an '$id' outside schema keywords.
+                let contents = match schema.get(ID_FIELD) {
+                    Some(_) => std::mem::replace(schema, reference),
+                    None => schema.clone(),
+                };
+                mirrored.insert(name, contents);
+            }
+        }
+    });
+    if let Some(e) = error {
+        return Err(e);
+    }
+    if !mirrored.is_empty() {
+        document[DEFS_FIELD] = Value::Object(mirrored);
+    }
+    Ok(resources)
+}
+
+fn declares_identifier(schema: &Value) -> bool {
+    match schema {
+        Value::Object(map) => {
+            IDENTIFIER_KEYWORDS.iter().any(|keyword| map.contains_key(*keyword))
+                || map.values().any(declares_identifier)
+        }
+        Value::Array(items) => items.iter().any(declares_identifier),
+        _ => false,
+    }
+}
+
+/// Points the document-local `$ref`s of a schema moved out of the document back at the root.
+fn qualify_local_refs(schema: &mut Value, root_id: &str) {
+    match schema {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(reference) if key == REF_FIELD && reference.starts_with('#') => {
+                        *reference = format!("{}{}", root_id, reference);
+                    }
+                    _ => qualify_local_refs(value, root_id),
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| qualify_local_refs(item, root_id)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_nullable_type() {
+        let mut schema = json!({ "type": "string", "nullable": true, "enum": ["a", "b"] });
+        normalize_oas30_schema(&mut schema);
+        assert_eq!(
+            schema,
+            json!({ "type": ["string", "null"], "enum": ["a", "b", null] })
+        );
+
+        let mut schema = json!({ "type": "string", "nullable": false });
+        normalize_oas30_schema(&mut schema);
+        assert_eq!(schema, json!({ "type": "string" }));
+    }
+
+    #[test]
+    fn test_nullable_without_type() {
+        let mut schema = json!({
+            "nullable": true,
+            "allOf": [{ "$ref": "#/components/schemas/Pet" }]
+        });
+        normalize_oas30_schema(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "anyOf": [
+                    { "allOf": [{ "$ref": "#/components/schemas/Pet" }] },
+                    { "type": "null" }
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn test_nested_schemas_and_annotations() {
+        let mut schema = json!({
+            "type": "object",
+            "xml": { "name": "Pet" },
+            "properties": {
+                "name": { "type": "string", "example": "doggie", "nullable": true },
+                "tags": { "type": "array", "items": { "type": "integer", "nullable": true } },
+                "age": { "type": "integer", "exclusiveMinimum": true }
+            }
+        });
+        normalize_oas30_schema(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": ["string", "null"] },
+                    "tags": { "type": "array", "items": { "type": ["integer", "null"] } },
+                    "age": { "type": "integer" }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_all_subschema_keywords() {
+        let nullable = json!({ "type": "string", "nullable": true });
+        let normalized = json!({ "type": ["string", "null"] });
+        let mut schema = json!({
+            "items": [nullable.clone()],
+            "additionalItems": nullable.clone(),
+            "$defs": { "Name": nullable.clone() },
+            "dependencies": { "name": nullable.clone(), "age": ["name"] }
+        });
+        normalize_oas30_schema(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "items": [normalized.clone()],
+                "additionalItems": normalized.clone(),
+                "$defs": { "Name": normalized.clone() },
+                "dependencies": { "name": normalized, "age": ["name"] }
+            })
+        );
+    }
+
+    #[test]
+    fn test_normalize_document_skips_examples() {
+        let mut spec = json!({
+            "openapi": "3.0.2",
+            "paths": {
+                "/pets": {
+                    "get": {
+                        "parameters": [
+                            { "name": "limit", "in": "query", "schema": { "type": "integer", "nullable": true } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "content": {
+                                    "application/json": {
+                                        "example": { "schema": { "nullable": true } }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": { "Pet": { "type": "string", "nullable": true } }
+            }
+        });
+        normalize_oas30_document(&mut spec);
+        assert_eq!(
+            spec["paths"]["/pets"]["get"]["parameters"][0]["schema"],
+            json!({ "type": ["integer", "null"] })
+        );
+        assert_eq!(
+            spec["paths"]["/pets"]["get"]["responses"]["200"]["content"]["application/json"]["example"],
+            json!({ "schema": { "nullable": true } })
+        );
+        assert_eq!(
+            spec["components"]["schemas"]["Pet"],
+            json!({ "type": ["string", "null"] })
+        );
+    }
+
+    #[test]
+    fn test_upgrade_oas30_schema() {
+        let mut schema = json!({
+            "type": "object",
+            "example": { "age": 3 },
+            "properties": {
+                "name": { "type": "string", "enum": ["a", "b"], "nullable": true },
+                "age": { "type": "integer", "minimum": 0, "exclusiveMinimum": true },
+                "weight": { "type": "number", "maximum": 99, "exclusiveMaximum": false },
+                "photo": { "type": "string", "format": "binary" },
+                "owner": { "$ref": "#/components/schemas/Owner", "nullable": true }
+            }
+        });
+        upgrade_oas30_schema(&mut schema);
+        assert_eq!(
+            schema,
+            json!({
+                "type": "object",
+                "examples": [{ "age": 3 }],
+                "properties": {
+                    "name": { "type": ["string", "null"], "enum": ["a", "b", null] },
+                    "age": { "type": "integer", "exclusiveMinimum": 0 },
+                    "weight": { "type": "number", "maximum": 99 },
+                    "photo": { "type": "string", "contentMediaType": "application/octet-stream" },
+                    "owner": {
+                        "anyOf": [{ "$ref": "#/components/schemas/Owner" }, { "type": "null" }]
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_normalize_discriminators_where_validated() {
+        let discriminated = |name: &str| {
+            json!({
+                "oneOf": [
+                    { "$ref": format!("#/components/schemas/{}Cat", name) },
+                    { "$ref": format!("#/components/schemas/{}Dog", name) }
+                ],
+                "discriminator": { "propertyName": "petType" }
+            })
+        };
+        let mut spec = json!({
+            "openapi": "3.1.0",
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "parameters": [
+                            { "name": "filter", "in": "query", "schema": { "$ref": "#/components/schemas/Query" } }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "properties": {
+                                            "pet": { "$ref": "#/components/schemas/Pet" },
+                                            "shared": { "$ref": "#/components/schemas/Query" },
+                                            "negated": { "not": { "$ref": "#/components/schemas/Negated" } }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Pet": discriminated(""),
+                    "Cat": { "properties": { "kitten": { "$ref": "#/components/schemas/Kitten" } } },
+                    "Dog": { "type": "object" },
+                    "Kitten": discriminated("Kitten"),
+                    "Query": discriminated("Query"),
+                    "Negated": discriminated("Negated"),
+                    "Unused": discriminated("Unused")
+                }
+            }
+        });
+        normalize_discriminators(&mut spec);
+        let schemas = &spec["components"]["schemas"];
+        // Reached from the body, and through the subschema the discriminator selects
+        assert!(schemas["Pet"].get(ANY_OF_FIELD).is_some());
+        assert!(schemas["Kitten"].get(ANY_OF_FIELD).is_some());
+        // Also used by a parameter, under a 'not', or not used at all
+        assert!(schemas["Query"].get(ONE_OF_FIELD).is_some());
+        assert!(schemas["Negated"].get(ONE_OF_FIELD).is_some());
+        assert!(schemas["Unused"].get(ONE_OF_FIELD).is_some());
+    }
+
+    #[test]
+    fn test_resolve_oas31_draft() {
+        let spec = json!({ "openapi": "3.1.0" });
+        assert_eq!(resolve_oas31_draft(&spec).unwrap(), Draft::Draft202012);
+        let spec = json!({
+            "openapi": "3.1.0",
+            "jsonSchemaDialect": "https://spec.openapis.org/oas/3.1/dialect/base"
+        });
+        assert_eq!(resolve_oas31_draft(&spec).unwrap(), Draft::Draft202012);
+        let spec = json!({
+            "openapi": "3.1.0",
+            "jsonSchemaDialect": "http://json-schema.org/draft-07/schema#"
+        });
+        assert_eq!(resolve_oas31_draft(&spec).unwrap(), Draft::Draft7);
+        let spec = json!({ "openapi": "3.1.0", "jsonSchemaDialect": "urn:unknown" });
+        assert!(resolve_oas31_draft(&spec).is_err());
+    }
+
+    #[test]
+    fn test_embed_oas31_schemas() {
+        let mut spec = json!({
+            "openapi": "3.1.0",
+            "components": {
+                "schemas": {
+                    "Plain": { "type": "string" },
+                    "Tree": { "$dynamicAnchor": "node", "type": "object" },
+                    "Node": { "$id": "node", "type": "object" },
+                    "Legacy": {
+                        "$schema": "http://json-schema.org/draft-04/schema#",
+                        "properties": { "tree": { "$ref": "#/components/schemas/Tree" } }
+                    }
+                }
+            }
+        });
+        let resources = embed_oas31_schemas(&mut spec, "@@root", Draft::Draft202012).unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].0, "@@root-schema-0");
+        assert_eq!(resources[0].1.draft(), Draft::Draft4);
+        assert_eq!(
+            resources[0].1.contents()["properties"]["tree"],
+            json!({ "$ref": "@@root#/components/schemas/Tree" })
+        );
+        assert_eq!(
+            spec["components"]["schemas"]["Legacy"],
+            json!({ "$ref": "@@root-schema-0" })
+        );
+        assert_eq!(spec["components"]["schemas"]["Node"], json!({ "$ref": "#/$defs/0" }));
+        assert_eq!(
+            spec["$defs"],
+            json!({
+                "0": { "$id": "node", "type": "object" },
+                "1": { "$dynamicAnchor": "node", "type": "object" }
+            })
+        );
+    }
+}
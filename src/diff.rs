@@ -0,0 +1,1366 @@
+//! Breaking-change detection between two versions of a specification.
+//!
+//! `diff` matches the operations of both versions by path template and method, compares their
+//! parameters, request bodies and responses, and the schemas of those, and classifies every
+//! change it finds as breaking or not for clients of the old version. Schemas are compared by
+//! direction: narrowing what a request may hold breaks clients, while in a response it is
+//! widening what clients may receive that does.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oasert::diff::{diff, ChangeKind};
+//! use oasert::traverser::OpenApiTraverser;
+//! use serde_json::json;
+//!
+//! let specification = |responses| {
+//!     json!({
+//!         "openapi": "3.1.0",
+//!         "info": { "title": "Users", "version": "1.0.0" },
+//!         "paths": { "/users": { "get": { "responses": responses } } }
+//!     })
+//! };
+//! let old = OpenApiTraverser::new(specification(json!({
+//!     "200": { "description": "Success" },
+//!     "404": { "description": "Not Found" }
+//! })))
+//! .unwrap();
+//! let new = OpenApiTraverser::new(specification(json!({
+//!     "200": { "description": "Success" }
+//! })))
+//! .unwrap();
+//!
+//! let report = diff(&old, &new);
+//! assert!(report.is_breaking());
+//! assert_eq!(report.changes[0].kind, ChangeKind::ResponseRemoved);
+//! assert_eq!(report.changes[0].pointer, "/paths/~1users/get/responses/404");
+//! ```
+use crate::document::{escape, follow_refs, OPERATION_METHODS};
+use crate::traverser::OpenApiTraverser;
+use crate::types::Direction;
+use crate::{
+    CONTENT_FIELD, IN_FIELD, NAME_FIELD, PARAMETERS_FIELD, PATHS_FIELD, REQUEST_BODY_FIELD,
+    REQUIRED_FIELD, RESPONSES_FIELD, SCHEMA_FIELD, TYPE_FIELD,
+};
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeSet, HashSet};
+use std::fmt::{Display, Formatter};
+
+const PROPERTIES_FIELD: &str = "properties";
+const ADDITIONAL_PROPERTIES_FIELD: &str = "additionalProperties";
+const ITEMS_FIELD: &str = "items";
+const ENUM_FIELD: &str = "enum";
+const NULLABLE_FIELD: &str = "nullable";
+const PATTERN_FIELD: &str = "pattern";
+const UNIQUE_ITEMS_FIELD: &str = "uniqueItems";
+const PATH_LOCATION: &str = "path";
+const HEADER_LOCATION: &str = "header";
+
+/// Keywords that bound a value from above, lowering them tightens a schema.
+const UPPER_BOUND_KEYWORDS: [&str; 5] = [
+    "maxLength",
+    "maxItems",
+    "maxProperties",
+    "maximum",
+    "exclusiveMaximum",
+];
+
+/// Keywords that bound a value from below, raising them tightens a schema.
+const LOWER_BOUND_KEYWORDS: [&str; 5] = [
+    "minLength",
+    "minItems",
+    "minProperties",
+    "minimum",
+    "exclusiveMinimum",
+];
+
+/// OAS 3.0 boolean keywords that make the bound they name exclusive, turning them on tightens a
+/// schema.
+const EXCLUSIVE_FLAG_KEYWORDS: [(&str, &str); 2] =
+    [("exclusiveMinimum", "minimum"), ("exclusiveMaximum", "maximum")];
+
+/// Composition keywords, whose subschemas are compared member by member.
+const COMPOSITION_KEYWORDS: [&str; 3] = ["allOf", "anyOf", "oneOf"];
+
+/// What changed between the two versions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    OperationRemoved,
+    OperationAdded,
+    ParameterAdded,
+    ParameterRemoved,
+    /// A new required parameter, or an optional one that became required.
+    ParameterRequired,
+    ParameterOptional,
+    /// A request body that became required.
+    RequestBodyRequired,
+    MediaTypeRemoved,
+    MediaTypeAdded,
+    ResponseRemoved,
+    ResponseAdded,
+    TypeChanged,
+    EnumNarrowed,
+    EnumWidened,
+    /// A bound, `pattern`, `required` property or the like that accepts fewer values.
+    ConstraintTightened,
+    ConstraintLoosened,
+    PropertyRemoved,
+    PropertyAdded,
+}
+
+impl ChangeKind {
+    /// The id the change is reported by, e.g. `operation-removed`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::OperationRemoved => "operation-removed",
+            ChangeKind::OperationAdded => "operation-added",
+            ChangeKind::ParameterAdded => "parameter-added",
+            ChangeKind::ParameterRemoved => "parameter-removed",
+            ChangeKind::ParameterRequired => "parameter-required",
+            ChangeKind::ParameterOptional => "parameter-optional",
+            ChangeKind::RequestBodyRequired => "request-body-required",
+            ChangeKind::MediaTypeRemoved => "media-type-removed",
+            ChangeKind::MediaTypeAdded => "media-type-added",
+            ChangeKind::ResponseRemoved => "response-removed",
+            ChangeKind::ResponseAdded => "response-added",
+            ChangeKind::TypeChanged => "type-changed",
+            ChangeKind::EnumNarrowed => "enum-narrowed",
+            ChangeKind::EnumWidened => "enum-widened",
+            ChangeKind::ConstraintTightened => "constraint-tightened",
+            ChangeKind::ConstraintLoosened => "constraint-loosened",
+            ChangeKind::PropertyRemoved => "property-removed",
+            ChangeKind::PropertyAdded => "property-added",
+        }
+    }
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A change between the two versions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    /// Whether clients of the old version can break on the change.
+    pub breaking: bool,
+    /// The operation the change is found in, e.g. `GET /users/{userId}`.
+    pub operation: String,
+    /// The JSON Pointer of the changed node in the new version, or in the old version for
+    /// what was removed. Schemas are located where they are defined rather than referenced.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Change {
+    fn compatibility(&self) -> &'static str {
+        match self.breaking {
+            true => "breaking",
+            false => "non-breaking",
+        }
+    }
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<12} {} {} #{}: {}",
+            self.compatibility(),
+            self.kind,
+            self.operation,
+            self.pointer,
+            self.message
+        )
+    }
+}
+
+/// The changes between two versions, by operation in the order of the old version, followed by
+/// the operations the new version adds.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DiffReport {
+    pub changes: Vec<Change>,
+}
+
+impl DiffReport {
+    /// Whether any change breaks clients of the old version.
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|change| change.breaking)
+    }
+
+    pub fn breaking_changes(&self) -> impl Iterator<Item = &Change> {
+        self.changes.iter().filter(|change| change.breaking)
+    }
+
+    /// The report as JSON:
+    /// `{ "summary": { "breaking": 1, "non-breaking": 2 }, "changes": [{ "kind", "breaking", "operation", "pointer", "message" }] }`.
+    pub fn to_json(&self) -> Value {
+        let breaking = self.breaking_changes().count();
+        let mut summary = Map::new();
+        summary.insert("breaking".to_string(), json!(breaking));
+        summary.insert(
+            "non-breaking".to_string(),
+            json!(self.changes.len() - breaking),
+        );
+        let changes: Vec<Value> = self
+            .changes
+            .iter()
+            .map(|change| {
+                json!({
+                    "kind": change.kind.as_str(),
+                    "breaking": change.breaking,
+                    "operation": change.operation,
+                    "pointer": change.pointer,
+                    "message": change.message,
+                })
+            })
+            .collect();
+        json!({ "summary": summary, "changes": changes })
+    }
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        let breaking = self.breaking_changes().count();
+        write!(
+            f,
+            "{} breaking change(s), {} non-breaking change(s)",
+            breaking,
+            self.changes.len() - breaking
+        )
+    }
+}
+
+/// Compares two versions of a specification.
+///
+/// Operations are matched by path template and method, the names of path parameters aside
+/// (`/users/{id}` matches `/users/{userId}`). Parameters are matched by location and name, path
+/// parameters by their position in the template. Members of `allOf`, `anyOf` and `oneOf` are
+/// compared by position.
+///
+/// # Arguments
+/// * `old` - The version clients are written against
+/// * `new` - The version replacing it
+///
+/// # Returns
+/// * `DiffReport` - Every change found, breaking or not.
+pub fn diff(old: &OpenApiTraverser, new: &OpenApiTraverser) -> DiffReport {
+    let mut differ = Differ {
+        old: old.specification(),
+        new: new.specification(),
+        operation: String::new(),
+        visited: HashSet::new(),
+        changes: vec![],
+    };
+    let old_operations = operations(differ.old);
+    let new_operations = operations(differ.new);
+
+    for old_operation in &old_operations {
+        match new_operations
+            .iter()
+            .find(|new_operation| new_operation.key == old_operation.key)
+        {
+            Some(new_operation) => differ.compare_operation(old_operation, new_operation),
+            None => {
+                differ.operation = old_operation.describe();
+                differ.record(
+                    ChangeKind::OperationRemoved,
+                    true,
+                    old_operation.pointer.clone(),
+                    "Operation was removed",
+                );
+            }
+        }
+    }
+    for new_operation in &new_operations {
+        if !old_operations
+            .iter()
+            .any(|old_operation| old_operation.key == new_operation.key)
+        {
+            differ.operation = new_operation.describe();
+            differ.record(
+                ChangeKind::OperationAdded,
+                false,
+                new_operation.pointer.clone(),
+                "Operation was added",
+            );
+        }
+    }
+    DiffReport {
+        changes: differ.changes,
+    }
+}
+
+/// An operation of one of the versions.
+struct DiffOperation<'s> {
+    /// The path template with the names of its parameters left out, and the method.
+    key: (String, &'static str),
+    path: &'s str,
+    method: &'static str,
+    pointer: String,
+    operation: &'s Value,
+    path_item: &'s Value,
+}
+
+impl DiffOperation<'_> {
+    fn describe(&self) -> String {
+        format!("{} {}", self.method.to_ascii_uppercase(), self.path)
+    }
+
+    /// The names of the path parameters, in template order.
+    fn template_parameters(&self) -> Vec<&str> {
+        self.path
+            .split('{')
+            .skip(1)
+            .filter_map(|segment| segment.split_once('}'))
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// Every operation of a document, in document order.
+fn operations(document: &Value) -> Vec<DiffOperation<'_>> {
+    let paths = match document.get(PATHS_FIELD).and_then(Value::as_object) {
+        Some(paths) => paths,
+        None => return vec![],
+    };
+    let mut operations = vec![];
+    for (path, path_item) in paths {
+        let pointer = format!("/{}/{}", PATHS_FIELD, escape(path));
+        let (path_item, pointer) = follow_refs(document, path_item, pointer);
+        let template = strip_parameter_names(path);
+        for method in OPERATION_METHODS {
+            if let Some(operation) = path_item.get(method) {
+                operations.push(DiffOperation {
+                    key: (template.clone(), method),
+                    path,
+                    method,
+                    pointer: format!("{}/{}", pointer, method),
+                    operation,
+                    path_item,
+                });
+            }
+        }
+    }
+    operations
+}
+
+/// Compares the two versions, collecting the changes.
+struct Differ<'s> {
+    old: &'s Value,
+    new: &'s Value,
+    /// The operation being compared.
+    operation: String,
+    /// The pairs of schemas compared for the operation, so that recursive schemas end.
+    visited: HashSet<(String, String, Direction)>,
+    changes: Vec<Change>,
+}
+
+impl<'s> Differ<'s> {
+    fn record(
+        &mut self,
+        kind: ChangeKind,
+        breaking: bool,
+        pointer: String,
+        message: impl Into<String>,
+    ) {
+        self.changes.push(Change {
+            kind,
+            breaking,
+            operation: self.operation.clone(),
+            pointer,
+            message: message.into(),
+        });
+    }
+
+    /// Records a change to what a schema accepts. Accepting less breaks requests clients
+    /// send, accepting more breaks clients reading responses.
+    fn record_constraint(
+        &mut self,
+        tightened: bool,
+        direction: Direction,
+        pointer: String,
+        message: impl Into<String>,
+    ) {
+        let kind = match tightened {
+            true => ChangeKind::ConstraintTightened,
+            false => ChangeKind::ConstraintLoosened,
+        };
+        self.record(
+            kind,
+            tightened == (direction == Direction::Request),
+            pointer,
+            message,
+        );
+    }
+
+    fn compare_operation(&mut self, old: &DiffOperation<'s>, new: &DiffOperation<'s>) {
+        self.operation = new.describe();
+        self.visited.clear();
+        self.compare_parameters(old, new);
+
+        let old_body = old.operation.get(REQUEST_BODY_FIELD).map(|body| {
+            let pointer = format!("{}/{}", old.pointer, REQUEST_BODY_FIELD);
+            follow_refs(self.old, body, pointer)
+        });
+        let new_body = new.operation.get(REQUEST_BODY_FIELD).map(|body| {
+            let pointer = format!("{}/{}", new.pointer, REQUEST_BODY_FIELD);
+            follow_refs(self.new, body, pointer)
+        });
+        if let Some((new_body, new_pointer)) = &new_body {
+            let was_required = matches!(&old_body, Some((old_body, _)) if is_required(old_body));
+            if is_required(new_body) && !was_required {
+                self.record(
+                    ChangeKind::RequestBodyRequired,
+                    true,
+                    new_pointer.clone(),
+                    "Request body is now required",
+                );
+            }
+        }
+        if let (Some(old_body), Some(new_body)) = (old_body, new_body) {
+            self.compare_content(old_body, new_body, Direction::Request);
+        }
+
+        let old_responses = old
+            .operation
+            .get(RESPONSES_FIELD)
+            .and_then(Value::as_object);
+        let new_responses = new
+            .operation
+            .get(RESPONSES_FIELD)
+            .and_then(Value::as_object);
+        let (old_responses, new_responses) = match (old_responses, new_responses) {
+            (Some(old_responses), Some(new_responses)) => (old_responses, new_responses),
+            _ => return,
+        };
+        let old_pointer = format!("{}/{}", old.pointer, RESPONSES_FIELD);
+        let new_pointer = format!("{}/{}", new.pointer, RESPONSES_FIELD);
+        for (status, old_response) in old_responses {
+            let old_pointer = format!("{}/{}", old_pointer, escape(status));
+            match new_responses.get(status) {
+                Some(new_response) => {
+                    let new_pointer = format!("{}/{}", new_pointer, escape(status));
+                    let old_response = follow_refs(self.old, old_response, old_pointer);
+                    let new_response = follow_refs(self.new, new_response, new_pointer);
+                    self.compare_content(old_response, new_response, Direction::Response);
+                }
+                None => self.record(
+                    ChangeKind::ResponseRemoved,
+                    true,
+                    old_pointer,
+                    format!("Response '{}' was removed", status),
+                ),
+            }
+        }
+        for status in new_responses.keys() {
+            if !old_responses.contains_key(status) {
+                self.record(
+                    ChangeKind::ResponseAdded,
+                    false,
+                    format!("{}/{}", new_pointer, escape(status)),
+                    format!("Response '{}' was added", status),
+                );
+            }
+        }
+    }
+
+    fn compare_parameters(&mut self, old: &DiffOperation<'s>, new: &DiffOperation<'s>) {
+        let old_parameters = parameters(self.old, old);
+        let new_parameters = parameters(self.new, new);
+        for (key, new_pointer, new_parameter) in &new_parameters {
+            let name = describe_parameter(new_parameter);
+            let old_parameter = old_parameters.iter().find(|(old_key, _, _)| old_key == key);
+            let (old_pointer, old_parameter) = match old_parameter {
+                Some((_, old_pointer, old_parameter)) => (old_pointer, old_parameter),
+                None if is_required(new_parameter) => {
+                    self.record(
+                        ChangeKind::ParameterRequired,
+                        true,
+                        new_pointer.clone(),
+                        format!("Required {} was added", name),
+                    );
+                    continue;
+                }
+                None => {
+                    self.record(
+                        ChangeKind::ParameterAdded,
+                        false,
+                        new_pointer.clone(),
+                        format!("Optional {} was added", name),
+                    );
+                    continue;
+                }
+            };
+            match (is_required(old_parameter), is_required(new_parameter)) {
+                (false, true) => self.record(
+                    ChangeKind::ParameterRequired,
+                    true,
+                    new_pointer.clone(),
+                    format!("{} is now required", capitalize(&name)),
+                ),
+                (true, false) => self.record(
+                    ChangeKind::ParameterOptional,
+                    false,
+                    new_pointer.clone(),
+                    format!("{} is now optional", capitalize(&name)),
+                ),
+                _ => {}
+            }
+            if let (Some(old_schema), Some(new_schema)) = (
+                old_parameter.get(SCHEMA_FIELD),
+                new_parameter.get(SCHEMA_FIELD),
+            ) {
+                self.compare_schema(
+                    (old_schema, format!("{}/{}", old_pointer, SCHEMA_FIELD)),
+                    (new_schema, format!("{}/{}", new_pointer, SCHEMA_FIELD)),
+                    Direction::Request,
+                );
+            }
+            let old_media = (*old_parameter, old_pointer.clone());
+            let new_media = (*new_parameter, new_pointer.clone());
+            self.compare_content(old_media, new_media, Direction::Request);
+        }
+        for (key, old_pointer, old_parameter) in &old_parameters {
+            if !new_parameters.iter().any(|(new_key, _, _)| new_key == key) {
+                self.record(
+                    ChangeKind::ParameterRemoved,
+                    false,
+                    old_pointer.clone(),
+                    format!(
+                        "{} was removed",
+                        capitalize(&describe_parameter(old_parameter))
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Compares the `content` of a request body, response or parameter.
+    fn compare_content(
+        &mut self,
+        (old, old_pointer): (&'s Value, String),
+        (new, new_pointer): (&'s Value, String),
+        direction: Direction,
+    ) {
+        let (old_content, new_content) = match (
+            old.get(CONTENT_FIELD).and_then(Value::as_object),
+            new.get(CONTENT_FIELD).and_then(Value::as_object),
+        ) {
+            (Some(old_content), Some(new_content)) => (old_content, new_content),
+            _ => return,
+        };
+        let old_pointer = format!("{}/{}", old_pointer, CONTENT_FIELD);
+        let new_pointer = format!("{}/{}", new_pointer, CONTENT_FIELD);
+        for (media_type, old_media) in old_content {
+            let old_pointer = format!("{}/{}", old_pointer, escape(media_type));
+            let new_media = match new_content.get(media_type) {
+                Some(new_media) => new_media,
+                None => {
+                    self.record(
+                        ChangeKind::MediaTypeRemoved,
+                        true,
+                        old_pointer,
+                        format!("Media type '{}' was removed", media_type),
+                    );
+                    continue;
+                }
+            };
+            let new_pointer = format!("{}/{}", new_pointer, escape(media_type));
+            if let (Some(old_schema), Some(new_schema)) =
+                (old_media.get(SCHEMA_FIELD), new_media.get(SCHEMA_FIELD))
+            {
+                self.compare_schema(
+                    (old_schema, format!("{}/{}", old_pointer, SCHEMA_FIELD)),
+                    (new_schema, format!("{}/{}", new_pointer, SCHEMA_FIELD)),
+                    direction,
+                );
+            }
+        }
+        for media_type in new_content.keys() {
+            if !old_content.contains_key(media_type) {
+                self.record(
+                    ChangeKind::MediaTypeAdded,
+                    false,
+                    format!("{}/{}", new_pointer, escape(media_type)),
+                    format!("Media type '{}' was added", media_type),
+                );
+            }
+        }
+    }
+
+    fn compare_schema(
+        &mut self,
+        (old, old_pointer): (&'s Value, String),
+        (new, new_pointer): (&'s Value, String),
+        direction: Direction,
+    ) {
+        let (old, old_pointer) = follow_refs(self.old, old, old_pointer);
+        let (new, new_pointer) = follow_refs(self.new, new, new_pointer);
+        if !old.is_object() || !new.is_object() {
+            return;
+        }
+        let pair = (old_pointer.clone(), new_pointer.clone(), direction);
+        if !self.visited.insert(pair) {
+            return;
+        }
+
+        self.compare_types(old, new, &new_pointer, direction);
+        self.compare_enums(old, new, &new_pointer, direction);
+        self.compare_bounds(old, new, &new_pointer, direction);
+        self.compare_required(old, new, &new_pointer, direction);
+        self.compare_properties((old, &old_pointer), (new, &new_pointer), direction);
+
+        for keyword in [ITEMS_FIELD, ADDITIONAL_PROPERTIES_FIELD] {
+            if let (Some(old_schema), Some(new_schema)) = (old.get(keyword), new.get(keyword)) {
+                self.compare_schema(
+                    (old_schema, format!("{}/{}", old_pointer, keyword)),
+                    (new_schema, format!("{}/{}", new_pointer, keyword)),
+                    direction,
+                );
+            }
+        }
+        for keyword in COMPOSITION_KEYWORDS {
+            if let (Some(Value::Array(old_schemas)), Some(Value::Array(new_schemas))) =
+                (old.get(keyword), new.get(keyword))
+            {
+                for (index, (old_schema, new_schema)) in
+                    old_schemas.iter().zip(new_schemas).enumerate()
+                {
+                    self.compare_schema(
+                        (old_schema, format!("{}/{}/{}", old_pointer, keyword, index)),
+                        (new_schema, format!("{}/{}/{}", new_pointer, keyword, index)),
+                        direction,
+                    );
+                }
+            }
+        }
+    }
+
+    fn compare_types(&mut self, old: &Value, new: &Value, pointer: &str, direction: Direction) {
+        // A schema without a type accepts every type.
+        let (removed, added) = match (types(old), types(new)) {
+            (Some(old_types), Some(new_types)) => (
+                !old_types.iter().all(|name| covers(&new_types, name)),
+                !new_types.iter().all(|name| covers(&old_types, name)),
+            ),
+            (None, Some(_)) => (true, false),
+            (Some(_), None) => (false, true),
+            (None, None) => (false, false),
+        };
+        if !removed && !added {
+            return;
+        }
+        let breaking = match direction {
+            Direction::Request => removed,
+            Direction::Response => added,
+        };
+        let message = format!(
+            "Type changed from '{}' to '{}'",
+            describe_types(old),
+            describe_types(new)
+        );
+        self.record(
+            ChangeKind::TypeChanged,
+            breaking,
+            format!("{}/{}", pointer, TYPE_FIELD),
+            message,
+        );
+    }
+
+    fn compare_enums(&mut self, old: &Value, new: &Value, pointer: &str, direction: Direction) {
+        let pointer = format!("{}/{}", pointer, ENUM_FIELD);
+        match (old.get(ENUM_FIELD), new.get(ENUM_FIELD)) {
+            (Some(Value::Array(old_values)), Some(Value::Array(new_values))) => {
+                let removed = difference(old_values, new_values);
+                let added = difference(new_values, old_values);
+                if !removed.is_empty() {
+                    self.record(
+                        ChangeKind::EnumNarrowed,
+                        direction == Direction::Request,
+                        pointer.clone(),
+                        format!("Enum values were removed: {}", removed),
+                    );
+                }
+                if !added.is_empty() {
+                    self.record(
+                        ChangeKind::EnumWidened,
+                        direction == Direction::Response,
+                        pointer,
+                        format!("Enum values were added: {}", added),
+                    );
+                }
+            }
+            (None, Some(Value::Array(new_values))) => self.record(
+                ChangeKind::EnumNarrowed,
+                direction == Direction::Request,
+                pointer,
+                format!("Values were restricted to: {}", join(new_values.iter())),
+            ),
+            (Some(Value::Array(_)), None) => self.record(
+                ChangeKind::EnumWidened,
+                direction == Direction::Response,
+                pointer,
+                "Enum was removed",
+            ),
+            _ => {}
+        }
+    }
+
+    fn compare_bounds(&mut self, old: &Value, new: &Value, pointer: &str, direction: Direction) {
+        let bounds = UPPER_BOUND_KEYWORDS
+            .iter()
+            .map(|keyword| (*keyword, true))
+            .chain(LOWER_BOUND_KEYWORDS.iter().map(|keyword| (*keyword, false)));
+        for (keyword, upper) in bounds {
+            let old_bound = old.get(keyword).filter(|bound| bound.is_number());
+            let new_bound = new.get(keyword).filter(|bound| bound.is_number());
+            let (tightened, message) = match (old_bound, new_bound) {
+                (Some(old_bound), Some(new_bound)) => {
+                    let (old_value, new_value) = (as_f64(old_bound), as_f64(new_bound));
+                    if old_value == new_value {
+                        continue;
+                    }
+                    let lowered = new_value < old_value;
+                    let message = format!(
+                        "{} was {} from {} to {}",
+                        keyword,
+                        if lowered { "lowered" } else { "raised" },
+                        old_bound,
+                        new_bound
+                    );
+                    (lowered == upper, message)
+                }
+                (None, Some(new_bound)) => {
+                    (true, format!("{} of {} was added", keyword, new_bound))
+                }
+                (Some(old_bound), None) => {
+                    (false, format!("{} of {} was removed", keyword, old_bound))
+                }
+                (None, None) => continue,
+            };
+            let pointer = match new_bound {
+                Some(_) => format!("{}/{}", pointer, keyword),
+                None => pointer.to_string(),
+            };
+            self.record_constraint(tightened, direction, pointer, message);
+        }
+
+        // Adding or removing the bound itself is reported above.
+        for (keyword, bound) in EXCLUSIVE_FLAG_KEYWORDS {
+            if old.get(bound).is_none() || new.get(bound).is_none() {
+                continue;
+            }
+            let old_exclusive = old.get(keyword) == Some(&Value::Bool(true));
+            let new_exclusive = new.get(keyword) == Some(&Value::Bool(true));
+            if old_exclusive != new_exclusive {
+                let message = match new_exclusive {
+                    true => format!("{} is now exclusive", bound),
+                    false => format!("{} is no longer exclusive", bound),
+                };
+                let pointer = format!("{}/{}", pointer, keyword);
+                self.record_constraint(new_exclusive, direction, pointer, message);
+            }
+        }
+
+        match (old.get(PATTERN_FIELD), new.get(PATTERN_FIELD)) {
+            (old_pattern, Some(new_pattern)) if old_pattern != Some(new_pattern) => {
+                let message = match old_pattern {
+                    Some(old_pattern) => {
+                        format!("pattern changed from {} to {}", old_pattern, new_pattern)
+                    }
+                    None => format!("pattern {} was added", new_pattern),
+                };
+                // A different pattern may reject values the old one accepted.
+                let pointer = format!("{}/{}", pointer, PATTERN_FIELD);
+                self.record_constraint(true, direction, pointer, message);
+            }
+            (Some(old_pattern), None) => self.record_constraint(
+                false,
+                direction,
+                pointer.to_string(),
+                format!("pattern {} was removed", old_pattern),
+            ),
+            _ => {}
+        }
+
+        let old_unique = old.get(UNIQUE_ITEMS_FIELD) == Some(&Value::Bool(true));
+        let new_unique = new.get(UNIQUE_ITEMS_FIELD) == Some(&Value::Bool(true));
+        if old_unique != new_unique {
+            let message = match new_unique {
+                true => "Items must now be unique",
+                false => "Items no longer need to be unique",
+            };
+            self.record_constraint(new_unique, direction, pointer.to_string(), message);
+        }
+
+        let old_closed = old.get(ADDITIONAL_PROPERTIES_FIELD) == Some(&Value::Bool(false));
+        let new_closed = new.get(ADDITIONAL_PROPERTIES_FIELD) == Some(&Value::Bool(false));
+        if old_closed != new_closed {
+            let message = match new_closed {
+                true => "Additional properties are no longer allowed",
+                false => "Additional properties are now allowed",
+            };
+            self.record_constraint(new_closed, direction, pointer.to_string(), message);
+        }
+    }
+
+    fn compare_required(&mut self, old: &Value, new: &Value, pointer: &str, direction: Direction) {
+        let old_required = required(old);
+        let new_required = required(new);
+        for name in new_required.difference(&old_required) {
+            self.record_constraint(
+                true,
+                direction,
+                format!("{}/{}", pointer, REQUIRED_FIELD),
+                format!("Property '{}' is now required", name),
+            );
+        }
+        for name in old_required.difference(&new_required) {
+            self.record_constraint(
+                false,
+                direction,
+                pointer.to_string(),
+                format!("Property '{}' is no longer required", name),
+            );
+        }
+    }
+
+    fn compare_properties(
+        &mut self,
+        (old, old_pointer): (&'s Value, &str),
+        (new, new_pointer): (&'s Value, &str),
+        direction: Direction,
+    ) {
+        let (old_properties, new_properties) = match (
+            old.get(PROPERTIES_FIELD).and_then(Value::as_object),
+            new.get(PROPERTIES_FIELD).and_then(Value::as_object),
+        ) {
+            (Some(old_properties), Some(new_properties)) => (old_properties, new_properties),
+            _ => return,
+        };
+        let old_pointer = format!("{}/{}", old_pointer, PROPERTIES_FIELD);
+        let new_pointer = format!("{}/{}", new_pointer, PROPERTIES_FIELD);
+        // Clients may send a removed property to a schema that still allows others.
+        let closed = new.get(ADDITIONAL_PROPERTIES_FIELD) == Some(&Value::Bool(false));
+        for (name, old_property) in old_properties {
+            let old_pointer = format!("{}/{}", old_pointer, escape(name));
+            match new_properties.get(name) {
+                Some(new_property) => self.compare_schema(
+                    (old_property, old_pointer),
+                    (new_property, format!("{}/{}", new_pointer, escape(name))),
+                    direction,
+                ),
+                None => self.record(
+                    ChangeKind::PropertyRemoved,
+                    direction == Direction::Response || closed,
+                    old_pointer,
+                    format!("Property '{}' was removed", name),
+                ),
+            }
+        }
+        for name in new_properties.keys() {
+            if !old_properties.contains_key(name) {
+                self.record(
+                    ChangeKind::PropertyAdded,
+                    false,
+                    format!("{}/{}", new_pointer, escape(name)),
+                    format!("Property '{}' was added", name),
+                );
+            }
+        }
+    }
+}
+
+/// The parameters of an operation, its own and those of its path item it does not override,
+/// keyed by location and name. Path parameters are keyed by their position in the template.
+fn parameters<'s>(
+    document: &'s Value,
+    operation: &DiffOperation<'s>,
+) -> Vec<(String, String, &'s Value)> {
+    let path_pointer = operation
+        .pointer
+        .rsplit_once('/')
+        .map(|(path_pointer, _)| path_pointer.to_string())
+        .unwrap_or_default();
+    let template = operation.template_parameters();
+    let mut parameters: Vec<(String, String, &'s Value)> = vec![];
+    for (node, pointer) in [
+        (operation.operation, operation.pointer.clone()),
+        (operation.path_item, path_pointer),
+    ] {
+        let items = match node.get(PARAMETERS_FIELD).and_then(Value::as_array) {
+            Some(items) => items,
+            None => continue,
+        };
+        for (index, parameter) in items.iter().enumerate() {
+            let pointer = format!("{}/{}/{}", pointer, PARAMETERS_FIELD, index);
+            let (parameter, pointer) = follow_refs(document, parameter, pointer);
+            let location = parameter
+                .get(IN_FIELD)
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let name = parameter
+                .get(NAME_FIELD)
+                .and_then(Value::as_str)
+                .unwrap_or_default();
+            let key = match location {
+                PATH_LOCATION => match template.iter().position(|segment| *segment == name) {
+                    Some(position) => format!("{}:{}", location, position),
+                    None => format!("{}:{}", location, name),
+                },
+                HEADER_LOCATION => format!("{}:{}", location, name.to_ascii_lowercase()),
+                _ => format!("{}:{}", location, name),
+            };
+            if !parameters.iter().any(|(existing, _, _)| *existing == key) {
+                parameters.push((key, pointer, parameter));
+            }
+        }
+    }
+    parameters
+}
+
+fn describe_parameter(parameter: &Value) -> String {
+    format!(
+        "{} parameter '{}'",
+        parameter
+            .get(IN_FIELD)
+            .and_then(Value::as_str)
+            .unwrap_or_default(),
+        parameter
+            .get(NAME_FIELD)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    )
+}
+
+fn capitalize(text: &str) -> String {
+    let mut chars = text.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn is_required(node: &Value) -> bool {
+    node.get(REQUIRED_FIELD) == Some(&Value::Bool(true))
+}
+
+/// The names of the `required` properties of a schema.
+fn required(schema: &Value) -> BTreeSet<&str> {
+    match schema.get(REQUIRED_FIELD) {
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => BTreeSet::new(),
+    }
+}
+
+/// The types a schema allows, from a `type` name or array, and OAS 3.0 `nullable`.
+fn types(schema: &Value) -> Option<BTreeSet<&str>> {
+    let mut types: BTreeSet<&str> = match schema.get(TYPE_FIELD) {
+        Some(Value::String(name)) => BTreeSet::from([name.as_str()]),
+        Some(Value::Array(names)) => names.iter().filter_map(Value::as_str).collect(),
+        _ => return None,
+    };
+    if schema.get(NULLABLE_FIELD) == Some(&Value::Bool(true)) {
+        types.insert("null");
+    }
+    Some(types)
+}
+
+/// Whether the values of type `name` are among those of `types`, integers being numbers.
+fn covers(types: &BTreeSet<&str>, name: &str) -> bool {
+    types.contains(name) || (name == "integer" && types.contains("number"))
+}
+
+fn describe_types(schema: &Value) -> String {
+    match types(schema) {
+        Some(types) => types.into_iter().collect::<Vec<_>>().join("|"),
+        None => "any".to_string(),
+    }
+}
+
+/// The values of `values` missing from `others`, joined for a message.
+fn difference(values: &[Value], others: &[Value]) -> String {
+    join(values.iter().filter(|value| !others.contains(value)))
+}
+
+fn join<'v>(values: impl Iterator<Item = &'v Value>) -> String {
+    values.map(Value::to_string).collect::<Vec<_>>().join(", ")
+}
+
+fn as_f64(value: &Value) -> f64 {
+    value.as_f64().unwrap_or_default()
+}
+
+/// A path template with the names of its parameters left out, e.g. `/users/{}`.
+fn strip_parameter_names(path: &str) -> String {
+    let mut template = String::with_capacity(path.len());
+    let mut in_parameter = false;
+    for c in path.chars() {
+        match c {
+            '{' => {
+                in_parameter = true;
+                template.push(c);
+            }
+            '}' => {
+                in_parameter = false;
+                template.push(c);
+            }
+            _ if in_parameter => {}
+            _ => template.push(c),
+        }
+    }
+    template
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn specification(paths: Value, schemas: Value) -> OpenApiTraverser {
+        OpenApiTraverser::new(json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Pets", "version": "1.0.0" },
+            "paths": paths,
+            "components": { "schemas": schemas }
+        }))
+        .unwrap()
+    }
+
+    fn changes(report: &DiffReport) -> Vec<(&str, bool, &str)> {
+        report
+            .changes
+            .iter()
+            .map(|change| {
+                (
+                    change.kind.as_str(),
+                    change.breaking,
+                    change.pointer.as_str(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = specification(
+            json!({
+                "/pets/{id}": {
+                    "put": {
+                        "parameters": [
+                            { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } },
+                            { "name": "dryRun", "in": "query", "schema": { "type": "boolean" } }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                            }
+                        },
+                        "responses": {
+                            "200": {
+                                "description": "Updated",
+                                "content": {
+                                    "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                                }
+                            },
+                            "404": { "description": "Not Found" }
+                        }
+                    },
+                    "delete": {
+                        "parameters": [
+                            { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                        ],
+                        "responses": { "204": { "description": "Deleted" } }
+                    }
+                }
+            }),
+            json!({
+                "Pet": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "maxLength": 64 },
+                        "kind": { "type": "string", "enum": ["cat", "dog", "bird"] },
+                        "age": { "type": "integer" }
+                    }
+                }
+            }),
+        );
+        let new = specification(
+            json!({
+                "/pets/{petId}": {
+                    "put": {
+                        "parameters": [
+                            { "name": "petId", "in": "path", "required": true, "schema": { "type": "integer" } },
+                            { "name": "dryRun", "in": "query", "required": true, "schema": { "type": "boolean" } },
+                            { "name": "x-trace", "in": "header", "schema": { "type": "string" } }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                            }
+                        },
+                        "responses": {
+                            "200": {
+                                "description": "Updated",
+                                "content": {
+                                    "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                                }
+                            }
+                        }
+                    },
+                    "get": {
+                        "parameters": [
+                            { "name": "petId", "in": "path", "required": true, "schema": { "type": "integer" } }
+                        ],
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                }
+            }),
+            json!({
+                "Pet": {
+                    "type": "object",
+                    "properties": {
+                        "name": { "type": "string", "maxLength": 32 },
+                        "kind": { "type": "string", "enum": ["cat", "dog", "fish"] },
+                        "age": { "type": "string" }
+                    }
+                }
+            }),
+        );
+
+        let report = diff(&old, &new);
+        assert_eq!(
+            changes(&report),
+            vec![
+                (
+                    "parameter-required",
+                    true,
+                    "/paths/~1pets~1{petId}/put/parameters/1"
+                ),
+                (
+                    "parameter-added",
+                    false,
+                    "/paths/~1pets~1{petId}/put/parameters/2"
+                ),
+                // The request body
+                (
+                    "type-changed",
+                    true,
+                    "/components/schemas/Pet/properties/age/type"
+                ),
+                (
+                    "enum-narrowed",
+                    true,
+                    "/components/schemas/Pet/properties/kind/enum"
+                ),
+                (
+                    "enum-widened",
+                    false,
+                    "/components/schemas/Pet/properties/kind/enum"
+                ),
+                (
+                    "constraint-tightened",
+                    true,
+                    "/components/schemas/Pet/properties/name/maxLength"
+                ),
+                // The response body
+                (
+                    "type-changed",
+                    true,
+                    "/components/schemas/Pet/properties/age/type"
+                ),
+                (
+                    "enum-narrowed",
+                    false,
+                    "/components/schemas/Pet/properties/kind/enum"
+                ),
+                (
+                    "enum-widened",
+                    true,
+                    "/components/schemas/Pet/properties/kind/enum"
+                ),
+                (
+                    "constraint-tightened",
+                    false,
+                    "/components/schemas/Pet/properties/name/maxLength"
+                ),
+                (
+                    "response-removed",
+                    true,
+                    "/paths/~1pets~1{id}/put/responses/404"
+                ),
+                ("operation-removed", true, "/paths/~1pets~1{id}/delete"),
+                ("operation-added", false, "/paths/~1pets~1{petId}/get"),
+            ]
+        );
+        assert!(report.is_breaking());
+        assert_eq!(report.changes[0].operation, "PUT /pets/{petId}");
+        assert_eq!(
+            report.changes[0].message,
+            "Query parameter 'dryRun' is now required"
+        );
+        assert_eq!(
+            report.to_json()["summary"],
+            json!({ "breaking": 8, "non-breaking": 5 })
+        );
+        assert!(report
+            .to_string()
+            .ends_with("8 breaking change(s), 5 non-breaking change(s)"));
+
+        assert!(!diff(&new, &new).is_breaking());
+        assert!(diff(&new, &new).changes.is_empty());
+    }
+
+    #[test]
+    fn test_required_and_removed_properties() {
+        let paths = json!({
+            "/pets": {
+                "post": {
+                    "requestBody": {
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                        }
+                    },
+                    "responses": { "204": { "description": "Created" } }
+                }
+            }
+        });
+        let old = specification(
+            paths.clone(),
+            json!({
+                "Pet": {
+                    "type": "object",
+                    "required": ["name"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "nickname": { "type": "string" },
+                        "owner": { "$ref": "#/components/schemas/Pet" }
+                    }
+                }
+            }),
+        );
+        let new = specification(
+            paths,
+            json!({
+                "Pet": {
+                    "type": "object",
+                    "required": ["name", "owner"],
+                    "additionalProperties": false,
+                    "properties": {
+                        "name": { "type": "string" },
+                        "owner": { "$ref": "#/components/schemas/Pet" }
+                    }
+                }
+            }),
+        );
+
+        let report = diff(&old, &new);
+        assert_eq!(
+            changes(&report),
+            vec![
+                ("constraint-tightened", true, "/components/schemas/Pet"),
+                (
+                    "constraint-tightened",
+                    true,
+                    "/components/schemas/Pet/required"
+                ),
+                (
+                    "property-removed",
+                    true,
+                    "/components/schemas/Pet/properties/nickname"
+                ),
+            ]
+        );
+        assert_eq!(
+            report.changes[0].message,
+            "Additional properties are no longer allowed"
+        );
+    }
+
+    #[test]
+    fn test_oas30_exclusive_bounds() {
+        let specification = |age: Value, weight: Value| {
+            OpenApiTraverser::new(json!({
+                "openapi": "3.0.3",
+                "info": { "title": "Pets", "version": "1.0.0" },
+                "paths": {
+                    "/pets": {
+                        "post": {
+                            "requestBody": {
+                                "content": {
+                                    "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                                }
+                            },
+                            "responses": { "204": { "description": "Created" } }
+                        }
+                    }
+                },
+                "components": {
+                    "schemas": {
+                        "Pet": {
+                            "type": "object",
+                            "properties": { "age": age, "weight": weight }
+                        }
+                    }
+                }
+            }))
+            .unwrap()
+        };
+        let old = specification(
+            json!({ "type": "integer", "minimum": 0 }),
+            json!({ "type": "number", "maximum": 100, "exclusiveMaximum": true }),
+        );
+        let new = specification(
+            json!({ "type": "integer", "minimum": 0, "exclusiveMinimum": true }),
+            json!({ "type": "number", "maximum": 100, "exclusiveMaximum": false }),
+        );
+
+        let report = diff(&old, &new);
+        assert_eq!(
+            changes(&report),
+            vec![
+                (
+                    "constraint-tightened",
+                    true,
+                    "/components/schemas/Pet/properties/age/exclusiveMinimum"
+                ),
+                (
+                    "constraint-loosened",
+                    false,
+                    "/components/schemas/Pet/properties/weight/exclusiveMaximum"
+                ),
+            ]
+        );
+        assert_eq!(report.changes[0].message, "minimum is now exclusive");
+        assert_eq!(report.changes[1].message, "maximum is no longer exclusive");
+    }
+
+    #[test]
+    fn test_percent_encoded_references() {
+        let paths = json!({
+            "/pets": {
+                "post": {
+                    "requestBody": {
+                        "content": {
+                            "application/json": { "schema": { "$ref": "#/components/schemas/New%20Pet" } }
+                        }
+                    },
+                    "responses": { "204": { "description": "Created" } }
+                }
+            }
+        });
+        let old = specification(paths.clone(), json!({ "New Pet": { "type": "object" } }));
+        let new = specification(paths, json!({ "New Pet": { "type": "array" } }));
+
+        let report = diff(&old, &new);
+        assert_eq!(
+            changes(&report),
+            vec![("type-changed", true, "/components/schemas/New Pet/type")]
+        );
+    }
+}
@@ -0,0 +1,162 @@
+//! Helpers shared by the code that walks an OpenAPI document by itself, rather than through the
+//! routes of `OpenApiTraverser`: the specification checks, the linter, the differ, the bundler
+//! and the example, discriminator and access mode validators.
+use crate::{ENCODED_BACKSLASH, ENCODED_TILDE, PATH_SEPARATOR, REF_FIELD, TILDE};
+use serde_json::Value;
+
+/// The fields of a Path Item Object that hold an operation.
+pub(crate) const OPERATION_METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// The reference prefix of the schemas in `components`.
+const SCHEMAS_PREFIX: &str = "#/components/schemas/";
+
+/// Upper bound on chained `$ref`s, so that a reference cycle cannot loop forever.
+pub(crate) const MAX_REF_DEPTH: usize = 32;
+
+/// Keywords whose value is a single subschema. Before Draft 2020-12, `items` may also be an
+/// array of subschemas.
+pub(crate) const SUBSCHEMA_KEYWORDS: [&str; 10] = [
+    "items",
+    "additionalItems",
+    "additionalProperties",
+    "propertyNames",
+    "not",
+    "if",
+    "then",
+    "else",
+    "contains",
+    "unevaluatedProperties",
+];
+
+/// Keywords whose value is an array of subschemas.
+pub(crate) const SUBSCHEMA_ARRAY_KEYWORDS: [&str; 4] = ["allOf", "anyOf", "oneOf", "prefixItems"];
+
+/// Keywords whose value is a map of subschemas. The values of `dependencies` may also be arrays
+/// of property names.
+pub(crate) const SUBSCHEMA_MAP_KEYWORDS: [&str; 6] = [
+    "properties",
+    "patternProperties",
+    "$defs",
+    "definitions",
+    "dependentSchemas",
+    "dependencies",
+];
+
+/// Keywords whose value is an instance rather than a schema or a part of the document.
+pub(crate) const INSTANCE_KEYWORDS: [&str; 4] = ["example", "default", "enum", "const"];
+
+/// Escapes an object key for use as a JSON Pointer reference token.
+pub(crate) fn escape(key: &str) -> String {
+    key.replace(TILDE, ENCODED_TILDE)
+        .replace(PATH_SEPARATOR, ENCODED_BACKSLASH)
+}
+
+/// Turns the value of a discriminator `mapping` into a reference. A value is either a reference,
+/// or the name of a component schema.
+pub(crate) fn mapping_ref(target: &str) -> String {
+    match target.contains(PATH_SEPARATOR) || target.starts_with('#') {
+        true => target.to_string(),
+        false => format!("{}{}", SCHEMAS_PREFIX, target),
+    }
+}
+
+/// Resolves a JSON Pointer taken from a URI fragment, which may be percent-encoded.
+///
+/// # Arguments
+/// * `document` - The document the pointer is resolved in
+/// * `fragment` - The fragment without its `#`, e.g. `/components/schemas/My%20Type`
+pub(crate) fn resolve_fragment<'d>(document: &'d Value, fragment: &str) -> Option<&'d Value> {
+    locate(document, fragment).map(|(value, _)| value)
+}
+
+/// Resolves a document-local reference (`#/...`).
+pub(crate) fn resolve_reference<'d>(document: &'d Value, reference: &str) -> Option<&'d Value> {
+    resolve_fragment(document, reference.strip_prefix('#')?)
+}
+
+/// Resolves the document-local `$ref` of a node, if it has one.
+pub(crate) fn resolve_local_ref<'d>(document: &'d Value, node: &Value) -> Option<&'d Value> {
+    let reference = node.get(REF_FIELD).and_then(Value::as_str)?;
+    resolve_reference(document, reference)
+}
+
+/// Follows local `$ref`s, keeping track of the pointer of the node they lead to.
+///
+/// # Arguments
+/// * `document` - The document the references are resolved in
+/// * `node` - The node to start from
+/// * `pointer` - The JSON Pointer of `node`
+///
+/// # Returns
+/// The first node that is not a reference, or the reference that does not resolve (or is one
+/// too many), with its pointer.
+pub(crate) fn follow_refs<'d>(
+    document: &'d Value,
+    node: &'d Value,
+    pointer: String,
+) -> (&'d Value, String) {
+    let mut resolved = (node, pointer);
+    for _ in 0..MAX_REF_DEPTH {
+        let reference = match resolved.0.get(REF_FIELD).and_then(Value::as_str) {
+            Some(reference) => reference,
+            None => return resolved,
+        };
+        match reference
+            .strip_prefix('#')
+            .and_then(|fragment| locate(document, fragment))
+        {
+            Some(located) => resolved = located,
+            None => return resolved,
+        }
+    }
+    resolved
+}
+
+/// Resolves a fragment, returning the node with the JSON Pointer it was found at.
+fn locate<'d>(document: &'d Value, fragment: &str) -> Option<(&'d Value, String)> {
+    match document.pointer(fragment) {
+        Some(value) => Some((value, fragment.to_string())),
+        None => {
+            let decoded = percent_encoding::percent_decode_str(fragment)
+                .decode_utf8()
+                .ok()?;
+            Some((document.pointer(&decoded)?, decoded.into_owned()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_follow_refs() {
+        let document = json!({
+            "components": {
+                "schemas": {
+                    "My Type": { "$ref": "#/components/schemas/a~1b" },
+                    "a/b": { "type": "string" },
+                    "Loop": { "$ref": "#/components/schemas/Loop" }
+                }
+            }
+        });
+        let node = json!({ "$ref": "#/components/schemas/My%20Type" });
+        let (resolved, pointer) = follow_refs(&document, &node, "/start".to_string());
+        assert_eq!(resolved, &json!({ "type": "string" }));
+        assert_eq!(pointer, "/components/schemas/a~1b");
+
+        let node = json!({ "$ref": "#/components/schemas/Missing" });
+        let (resolved, pointer) = follow_refs(&document, &node, "/start".to_string());
+        assert_eq!(resolved, &node);
+        assert_eq!(pointer, "/start");
+
+        let node = json!({ "$ref": "#/components/schemas/Loop" });
+        let (resolved, _) = follow_refs(&document, &node, "/start".to_string());
+        assert!(resolved.get(REF_FIELD).is_some());
+
+        assert_eq!(escape("/a~b"), "~1a~0b");
+    }
+}
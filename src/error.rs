@@ -1,7 +1,5 @@
-use crate::traverser::TraverserError;
 use crate::types::primitive::OpenApiPrimitives;
-use crate::types::version::VersionError;
-use jsonschema::{ReferencingError, ValidationError as JsonSchemaValidationError};
+use crate::validator::builder::ValidatorBuilderError;
 use serde_json::Value;
 use std::fmt::{Display, Formatter};
 
@@ -124,33 +122,6 @@ pub enum ValidationErrorType {
 }
 
 impl ValidationErrorType {
-    pub(crate) fn traversal_failed<T>(traversal_error: TraverserError, message: &T) -> Self
-    where
-        T: ToString + ?Sized,
-    {
-        ValidationErrorType::TraversalFailed(traversal_error.to_string(), message.to_string())
-    }
-
-    pub(crate) fn schema_validation_failed<T>(
-        json_schema_error: JsonSchemaValidationError,
-        message: &T,
-    ) -> Self
-    where
-        T: ToString + ?Sized,
-    {
-        ValidationErrorType::SchemaValidationFailed(
-            json_schema_error.to_string(),
-            message.to_string(),
-        )
-    }
-
-    pub(crate) fn resource_load_error<T>(error: ReferencingError, message: &T) -> Self
-    where
-        T: ToString + ?Sized,
-    {
-        ValidationErrorType::LoadingResourceFailed(error.to_string(), message.to_string())
-    }
-
     pub(crate) fn assertion_failed<T>(message: &T) -> Self
     where
         T: ToString + ?Sized,
@@ -158,11 +129,19 @@ impl ValidationErrorType {
         ValidationErrorType::AssertionFailed(message.to_string())
     }
 
-    pub(crate) fn version_failed<T>(version_error: VersionError, message: &T) -> Self
+    pub(crate) fn builder_failed<T>(builder_error: ValidatorBuilderError, message: &T) -> Self
     where
         T: ToString + ?Sized,
     {
-        ValidationErrorType::VersionFailed(version_error.to_string(), message.to_string())
+        match builder_error {
+            ValidatorBuilderError::InvalidVersion(version_error) => {
+                ValidationErrorType::VersionFailed(version_error, message.to_string())
+            }
+            builder_error => ValidationErrorType::LoadingResourceFailed(
+                builder_error.to_string(),
+                message.to_string(),
+            ),
+        }
     }
 }
 
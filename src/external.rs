@@ -0,0 +1,432 @@
+//! Resolution of `$ref`s to other files of a multi-file specification.
+//!
+//! Every document reachable from the root document through a file reference (e.g.
+//! `./schemas/user.yaml#/User` or `common.json`), or through another URI served by a
+//! `ReferenceResolver` (e.g. `urn:schemas:money` or `https://schemas.internal/money.json`), is
+//! loaded once and embedded in the root document under `x-oasert-documents`. All references are then rewritten into local pointers,
+//! so that the traverser and the JSON Schema resource of the root document resolve them like any
+//! other `#/...` reference, cyclic file graphs included.
+use crate::loader::{self, SpecificationFormat};
+use crate::types::resolver::ReferenceResolver;
+use crate::REF_FIELD;
+use jsonschema::{Retrieve, Uri};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// The field of the root document the other documents are embedded under.
+pub(crate) const EXTERNAL_DOCUMENTS_FIELD: &str = "x-oasert-documents";
+
+const DISCRIMINATOR_FIELD: &str = "discriminator";
+const MAPPING_FIELD: &str = "mapping";
+const ID_FIELD: &str = "$id";
+
+/// Loads the documents referenced by a specification and embeds them in it.
+///
+/// # Arguments
+/// * `specification` - The root document
+/// * `root_path` - The file the root document was loaded from, relative references are
+///   resolved against its directory. `None` if it was not loaded from a file, relative references
+///   are then resolved against the sandbox, or else the current directory
+/// * `sandbox` - A directory that referenced files must be in, if any
+/// * `resolver` - The resolver of references to anything but files, if any
+///
+/// # Returns
+/// * `Ok(Vec<String>)` - The sources (file paths or URIs) of the embedded documents, by index
+/// * `Err(String)` - If a document cannot be read, parsed or resolved, or lies outside the sandbox.
+pub(crate) fn embed_external_documents(
+    specification: &mut Value,
+    root_path: Option<&Path>,
+    sandbox: Option<&Path>,
+    resolver: Option<&dyn ReferenceResolver>,
+) -> Result<Vec<String>, String> {
+    let sandbox = match sandbox {
+        Some(sandbox) => Some(canonicalize(sandbox)?),
+        None => None,
+    };
+    let root = match (root_path, &sandbox) {
+        (Some(root_path), _) => DocumentLocation::File(canonicalize(root_path)?),
+        (None, Some(sandbox)) => DocumentLocation::Memory(sandbox.clone()),
+        (None, None) => DocumentLocation::Memory(canonicalize(Path::new("."))?),
+    };
+    let mut graph = DocumentGraph {
+        root: root.clone(),
+        sandbox,
+        resolver,
+        indexes: HashMap::new(),
+        pending: vec![],
+    };
+
+    graph.rewrite_refs(specification, &root, None)?;
+    let mut documents = vec![];
+    while let Some((index, location)) = graph.pending.pop() {
+        let mut document = graph.load_document(&location)?;
+        graph.rewrite_refs(&mut document, &location, Some(index))?;
+        documents.push((index, document));
+    }
+    if documents.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut embedded = Map::new();
+    documents.sort_by_key(|(index, _)| *index);
+    for (index, document) in documents {
+        embedded.insert(index.to_string(), document);
+    }
+    let mut sources = vec![String::new(); graph.indexes.len()];
+    for (location, index) in graph.indexes {
+        sources[index] = location.to_string();
+    }
+    match specification.as_object_mut() {
+        Some(root) => {
+            root.insert(EXTERNAL_DOCUMENTS_FIELD.to_string(), Value::Object(embedded));
+            Ok(sources)
+        }
+        None => Err("The root document is not an object".to_string()),
+    }
+}
+
+/// Where a document was loaded from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum DocumentLocation {
+    /// A canonical file path.
+    File(PathBuf),
+    /// An absolute URI, without fragment.
+    Uri(String),
+    /// A root document that was not loaded from a file, with the canonical directory its relative
+    /// references are resolved against.
+    Memory(PathBuf),
+}
+
+impl Display for DocumentLocation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DocumentLocation::File(path) | DocumentLocation::Memory(path) => {
+                write!(f, "{}", path.display())
+            }
+            DocumentLocation::Uri(uri) => write!(f, "{}", uri),
+        }
+    }
+}
+
+/// The documents found so far, by location.
+struct DocumentGraph<'r> {
+    root: DocumentLocation,
+    sandbox: Option<PathBuf>,
+    resolver: Option<&'r dyn ReferenceResolver>,
+    indexes: HashMap<DocumentLocation, usize>,
+    pending: Vec<(usize, DocumentLocation)>,
+}
+
+impl DocumentGraph<'_> {
+    /// Rewrites every reference in a document into a pointer into the root document.
+    ///
+    /// # Arguments
+    /// * `value` - The document, or a part of it
+    /// * `location` - Where the document was loaded from
+    /// * `index` - The index the document is embedded under, `None` for the root document
+    fn rewrite_refs(
+        &mut self,
+        value: &mut Value,
+        location: &DocumentLocation,
+        index: Option<usize>,
+    ) -> Result<(), String> {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    match (key.as_str(), value) {
+                        (REF_FIELD, Value::String(reference)) => {
+                            *reference = self.rewrite_ref(reference, location, index)?;
+                        }
+                        (DISCRIMINATOR_FIELD, discriminator) => {
+                            self.rewrite_mapping(discriminator, location, index)?;
+                            self.rewrite_refs(discriminator, location, index)?;
+                        }
+                        ("example", _) => {}
+                        (_, value) if declares_id(value) => {}
+                        (_, value) => self.rewrite_refs(value, location, index)?,
+                    }
+                }
+                Ok(())
+            }
+            Value::Array(items) => {
+                for item in items.iter_mut().filter(|item| !declares_id(item)) {
+                    self.rewrite_refs(item, location, index)?;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Rewrites the `mapping` values of a discriminator that are references (rather than names).
+    fn rewrite_mapping(
+        &mut self,
+        discriminator: &mut Value,
+        location: &DocumentLocation,
+        index: Option<usize>,
+    ) -> Result<(), String> {
+        if let Some(Value::Object(mapping)) = discriminator.get_mut(MAPPING_FIELD) {
+            for target in mapping.values_mut() {
+                match target {
+                    Value::String(reference) if reference.contains('#') || reference.contains('/') => {
+                        *reference = self.rewrite_ref(reference, location, index)?;
+                    }
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rewrite_ref(
+        &mut self,
+        reference: &str,
+        location: &DocumentLocation,
+        index: Option<usize>,
+    ) -> Result<String, String> {
+        let (file, fragment) = match reference.split_once('#') {
+            Some((file, fragment)) => (file, fragment),
+            None => (reference, ""),
+        };
+        if !fragment.is_empty() && !fragment.starts_with('/') {
+            // Anchors ('#name') are resolved by JSON Schema, relative to the schema's base URI.
+            return Ok(reference.to_string());
+        }
+        let target = match file.is_empty() {
+            true => index,
+            false => self.find_document(file, location)?,
+        };
+        Ok(match target {
+            Some(target) => format!("#/{}/{}{}", EXTERNAL_DOCUMENTS_FIELD, target, fragment),
+            None => format!("#{}", fragment),
+        })
+    }
+
+    /// Finds the index of a referenced document, queueing it for loading when it is new.
+    ///
+    /// # Returns
+    /// * `Ok(Some(usize))` - The index of the document
+    /// * `Ok(None)` - If the document is the root document
+    /// * `Err(String)` - If a file cannot be found or lies outside the sandbox.
+    fn find_document(
+        &mut self,
+        reference: &str,
+        referrer: &DocumentLocation,
+    ) -> Result<Option<usize>, String> {
+        let location = match (uri_scheme(reference), referrer) {
+            (Some("file"), _) => {
+                let path = reference.trim_start_matches("file://");
+                DocumentLocation::File(self.file_path(Path::new("/"), path)?)
+            }
+            (Some(_), _) => DocumentLocation::Uri(reference.to_string()),
+            (None, DocumentLocation::File(referrer)) => {
+                let directory = referrer.parent().unwrap_or_else(|| Path::new("."));
+                DocumentLocation::File(self.file_path(directory, reference)?)
+            }
+            (None, DocumentLocation::Memory(directory)) => {
+                DocumentLocation::File(self.file_path(directory, reference)?)
+            }
+            (None, DocumentLocation::Uri(base)) => {
+                DocumentLocation::Uri(resolve_uri(base, reference))
+            }
+        };
+        if location == self.root {
+            return Ok(None);
+        }
+        if let Some(index) = self.indexes.get(&location) {
+            return Ok(Some(*index));
+        }
+        let index = self.indexes.len();
+        self.indexes.insert(location.clone(), index);
+        self.pending.push((index, location));
+        Ok(Some(index))
+    }
+
+    /// Resolves a referenced file against a directory, and checks it against the sandbox.
+    fn file_path(&self, directory: &Path, file: &str) -> Result<PathBuf, String> {
+        let file = match percent_encoding::percent_decode_str(file).decode_utf8() {
+            Ok(file) => file,
+            Err(e) => return Err(format!("Invalid reference '{}': {}", file, e)),
+        };
+        let path = canonicalize(&directory.join(file.as_ref()))?;
+        match &self.sandbox {
+            Some(sandbox) if !path.starts_with(sandbox) => Err(format!(
+                "Reference to '{}' escapes the sandbox '{}'",
+                path.display(),
+                sandbox.display()
+            )),
+            _ => Ok(path),
+        }
+    }
+
+    fn load_document(&self, location: &DocumentLocation) -> Result<Value, String> {
+        match location {
+            DocumentLocation::File(path) => load_file(path),
+            DocumentLocation::Uri(uri) => match self.resolver {
+                Some(resolver) => match resolver.resolve(uri) {
+                    Ok(document) => Ok(document),
+                    Err(e) => Err(format!("Could not resolve '{}': {}", uri, e)),
+                },
+                None => Err(format!(
+                    "Could not resolve '{}': no ReferenceResolver is registered",
+                    uri
+                )),
+            },
+            DocumentLocation::Memory(directory) => Err(format!(
+                "Could not load the in-memory document of '{}'",
+                directory.display()
+            )),
+        }
+    }
+}
+
+/// Whether a schema declares its own base URI. The references inside it are relative to that URI
+/// (e.g. a `$ref: tree` next to `$id: string-tree`), they are left to JSON Schema to resolve.
+fn declares_id(value: &Value) -> bool {
+    value.get(ID_FIELD).is_some_and(Value::is_string)
+}
+
+/// The scheme of an absolute URI. Single letters are not taken for a scheme, but for a drive.
+fn uri_scheme(reference: &str) -> Option<&str> {
+    let (scheme, _) = reference.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = scheme.len() > 1
+        && chars.next().is_some_and(|c| c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+/// Resolves a relative reference against an absolute, hierarchical URI (RFC 3986, section 5.2).
+fn resolve_uri(base: &str, reference: &str) -> String {
+    let (scheme, rest) = match base.split_once("://") {
+        Some(parts) => parts,
+        // URNs and other non-hierarchical URIs have nothing to resolve against.
+        None => return reference.to_string(),
+    };
+    let (authority, base_path) = match rest.find('/') {
+        Some(slash) => rest.split_at(slash),
+        None => (rest, "/"),
+    };
+    let path = match reference.starts_with('/') {
+        true => reference.to_string(),
+        false => format!("{}{}", &base_path[..=base_path.rfind('/').unwrap_or(0)], reference),
+    };
+    let mut segments: Vec<&str> = vec![];
+    for segment in path.split('/').skip(1) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("{}://{}/{}", scheme, authority, segments.join("/"))
+}
+
+/// Serves the documents of a `ReferenceResolver` to JSON Schema, for references that were not
+/// embedded (e.g. a relative `$ref` under a schema's own `$id`).
+pub(crate) struct ResolverRetriever(pub(crate) Arc<dyn ReferenceResolver>);
+
+impl Retrieve for ResolverRetriever {
+    fn retrieve(
+        &self,
+        uri: &Uri<String>,
+    ) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        self.0.resolve(uri.as_str())
+    }
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, String> {
+    match path.canonicalize() {
+        Ok(path) => Ok(path),
+        Err(e) => Err(format!("Could not resolve '{}': {}", path.display(), e)),
+    }
+}
+
+fn load_file(path: &Path) -> Result<Value, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => return Err(format!("Could not read '{}': {}", path.display(), e)),
+    };
+    match loader::parse_specification(&content, SpecificationFormat::from_path(path)) {
+        Ok(document) => Ok(document),
+        Err(e) => Err(format!("Could not parse '{}': {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    const ROOT: &str = "./test/multi-file/openapi.yaml";
+
+    #[test]
+    fn test_embed_documents() {
+        let mut spec = loader::parse_specification(
+            &std::fs::read_to_string(ROOT).unwrap(),
+            Some(SpecificationFormat::Yaml),
+        )
+        .unwrap();
+        embed_external_documents(&mut spec, Some(Path::new(ROOT)), None, None).unwrap();
+
+        let documents = spec[EXTERNAL_DOCUMENTS_FIELD].as_object().unwrap();
+        assert_eq!(documents.len(), 2);
+        let body = &spec["paths"]["/users"]["post"]["requestBody"]["content"]["application/json"];
+        assert_eq!(
+            body["schema"],
+            json!({ "$ref": "#/x-oasert-documents/0/User" })
+        );
+        // 'user.yaml' refers to 'common.json', which refers back to 'user.yaml'
+        let user = &documents["0"]["User"];
+        assert_eq!(
+            user["properties"]["address"],
+            json!({ "$ref": "#/x-oasert-documents/1/Address" })
+        );
+        assert_eq!(
+            documents["1"]["Address"]["properties"]["resident"],
+            json!({ "$ref": "#/x-oasert-documents/0/User" })
+        );
+        assert_eq!(
+            user["properties"]["id"],
+            json!({ "$ref": "#/x-oasert-documents/0/Id" })
+        );
+    }
+
+    #[test]
+    fn test_sandbox() {
+        let mut spec = json!({ "components": { "schemas": {
+            "Outside": { "$ref": "../openapi-v3.0.2.json#/components/schemas/Pet" }
+        } } });
+        let sandbox = Path::new("./test/multi-file");
+        let error = embed_external_documents(&mut spec, Some(Path::new(ROOT)), Some(sandbox), None);
+        assert!(error.unwrap_err().contains("escapes the sandbox"));
+
+        let mut spec = json!({ "$ref": "https://example.com/spec.json" });
+        assert!(embed_external_documents(&mut spec, Some(Path::new(ROOT)), None, None).is_err());
+    }
+
+    #[test]
+    fn test_resolve_uri() {
+        let base = "https://schemas.internal/lib/party.json";
+        assert_eq!(
+            resolve_uri(base, "iban.json"),
+            "https://schemas.internal/lib/iban.json"
+        );
+        assert_eq!(
+            resolve_uri(base, "../common/./iban.json"),
+            "https://schemas.internal/common/iban.json"
+        );
+        assert_eq!(
+            resolve_uri(base, "/iban.json"),
+            "https://schemas.internal/iban.json"
+        );
+        assert_eq!(uri_scheme("urn:schemas:money"), Some("urn"));
+        assert_eq!(uri_scheme("C:/specs/common.json"), None);
+        assert_eq!(uri_scheme("./common.json"), None);
+    }
+}
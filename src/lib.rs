@@ -1,13 +1,29 @@
+#[cfg(feature = "actix")]
+pub mod actix;
+pub mod bundle;
 pub mod cache;
 mod converter;
+mod dialect;
+pub mod diff;
+mod document;
 pub(crate) mod error;
+mod external;
+#[cfg(feature = "jwt")]
+pub mod jwt;
+pub mod lint;
+pub mod loader;
+pub mod meta_schema;
+pub mod overlay;
+pub mod swagger;
 pub mod traverser;
 pub mod types;
+pub mod upgrade;
 pub mod validator;
 
 const CONTENT_FIELD: &'static str = "content";
 const SCHEMA_FIELD: &'static str = "schema";
 const REQUEST_BODY_FIELD: &'static str = "requestBody";
+const RESPONSES_FIELD: &'static str = "responses";
 const PATHS_FIELD: &'static str = "paths";
 const PARAMETERS_FIELD: &'static str = "parameters";
 const REF_FIELD: &'static str = "$ref";
@@ -20,3 +36,7 @@ const NAME_FIELD: &'static str = "name";
 const OPENAPI_FIELD: &'static str = "openapi";
 const REQUIRED_FIELD: &'static str = "required";
 const IN_FIELD: &'static str = "in";
+const COMPONENTS_FIELD: &'static str = "components";
+const SECURITY_SCHEMES_FIELD: &'static str = "securitySchemes";
+const TYPE_FIELD: &'static str = "type";
+const ROOT_RESOURCE: &'static str = "@@root";
@@ -0,0 +1,679 @@
+//! Style and safety rules for OpenAPI documents, beyond what the meta-schema requires.
+//!
+//! A `Linter` runs a set of `LintRule`s over a specification, each at a configurable `Severity`,
+//! and collects what they find in a `LintReport`. The report prints one finding per line, or
+//! converts into JSON for CI.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oasert::lint::{Linter, Severity};
+//! use oasert::traverser::OpenApiTraverser;
+//! use serde_json::json;
+//!
+//! let specification = json!({
+//!     "openapi": "3.1.0",
+//!     "info": { "title": "Users", "version": "1.0.0" },
+//!     "paths": {
+//!         "/users": {
+//!             "get": { "responses": { "200": { "description": "Success" } } }
+//!         }
+//!     }
+//! });
+//! let traverser = OpenApiTraverser::new(specification).unwrap();
+//! let linter = Linter::recommended().severity("operation-security", Severity::Error);
+//! let report = linter.lint(&traverser);
+//! assert!(report.has_errors());
+//! assert_eq!(report.findings[0].rule, "operation-operation-id");
+//! ```
+mod rules;
+
+pub use rules::{
+    AdditionalPropertiesRule, CamelCasePathParametersRule, OperationIdRule, OperationSecurityRule,
+    StringMaxLengthRule, UniqueOperationIdRule,
+};
+
+use crate::document::{
+    self, escape, OPERATION_METHODS, SUBSCHEMA_ARRAY_KEYWORDS, SUBSCHEMA_KEYWORDS,
+    SUBSCHEMA_MAP_KEYWORDS,
+};
+use crate::traverser::OpenApiTraverser;
+use crate::{
+    CONTENT_FIELD, PARAMETERS_FIELD, PATHS_FIELD, REQUEST_BODY_FIELD, RESPONSES_FIELD, SCHEMA_FIELD,
+};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::Arc;
+
+const RULES_FIELD: &str = "rules";
+const EXTENDS_FIELD: &str = "extends";
+const RECOMMENDED_RULE_SET: &str = "recommended";
+const EMPTY_RULE_SET: &str = "none";
+
+/// How much a finding matters, from least to most.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Hint,
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Hint => "hint",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Severity {
+    type Err = LintConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "hint" => Ok(Severity::Hint),
+            "info" => Ok(Severity::Info),
+            "warn" | "warning" => Ok(Severity::Warning),
+            "error" => Ok(Severity::Error),
+            _ => Err(LintConfigError(format!(
+                "Unknown severity '{}', expected one of: off, hint, info, warning, error",
+                s
+            ))),
+        }
+    }
+}
+
+/// A linter configuration that could not be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfigError(pub String);
+
+impl Display for LintConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid lint configuration: {}", self.0)
+    }
+}
+
+impl std::error::Error for LintConfigError {}
+
+/// Something a rule found, at a JSON Pointer into the specification.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub pointer: String,
+    pub message: String,
+}
+
+impl LintIssue {
+    pub fn new(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+/// A style or safety rule.
+///
+/// Rules get the specification through a `LintContext`, which lists its operations, parameters
+/// and schemas with their JSON Pointers.
+pub trait LintRule: Send + Sync {
+    /// The id the rule is configured and reported by, e.g. `operation-operation-id`.
+    fn id(&self) -> &str;
+
+    /// The severity of the rule unless configured otherwise.
+    fn default_severity(&self) -> Severity;
+
+    /// # check
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The specification being linted
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<LintIssue>` - What the rule found, empty if the specification follows it.
+    fn check(&self, context: &LintContext) -> Vec<LintIssue>;
+}
+
+/// A rule finding, as reported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintFinding {
+    pub rule: String,
+    pub severity: Severity,
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Display for LintFinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:<7} {} #{}: {}",
+            self.severity, self.rule, self.pointer, self.message
+        )
+    }
+}
+
+/// The findings of a lint run, ordered by rule, then document order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LintReport {
+    pub findings: Vec<LintFinding>,
+}
+
+impl LintReport {
+    /// The number of findings of a severity.
+    pub fn count(&self, severity: Severity) -> usize {
+        self.findings
+            .iter()
+            .filter(|finding| finding.severity == severity)
+            .count()
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.count(Severity::Error) > 0
+    }
+
+    /// Whether any finding is at least as severe as `threshold`, e.g. to fail a CI job on
+    /// warnings.
+    pub fn fails(&self, threshold: Severity) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.severity >= threshold)
+    }
+
+    /// The report as JSON:
+    /// `{ "summary": { "error": 1, ... }, "findings": [{ "rule", "severity", "pointer", "message" }] }`.
+    pub fn to_json(&self) -> Value {
+        let mut summary = Map::new();
+        for severity in [
+            Severity::Error,
+            Severity::Warning,
+            Severity::Info,
+            Severity::Hint,
+        ] {
+            summary.insert(severity.to_string(), json!(self.count(severity)));
+        }
+        let findings: Vec<Value> = self
+            .findings
+            .iter()
+            .map(|finding| {
+                json!({
+                    "rule": finding.rule,
+                    "severity": finding.severity.as_str(),
+                    "pointer": finding.pointer,
+                    "message": finding.message,
+                })
+            })
+            .collect();
+        json!({ "summary": summary, "findings": findings })
+    }
+}
+
+impl Display for LintReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        write!(
+            f,
+            "{} error(s), {} warning(s), {} info, {} hint(s)",
+            self.count(Severity::Error),
+            self.count(Severity::Warning),
+            self.count(Severity::Info),
+            self.count(Severity::Hint)
+        )
+    }
+}
+
+/// Runs rules over specifications.
+#[derive(Clone, Default)]
+pub struct Linter {
+    rules: Vec<(Arc<dyn LintRule>, Option<Severity>)>,
+}
+
+impl Linter {
+    /// A linter without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A linter with every built-in rule, at its default severity.
+    pub fn recommended() -> Self {
+        Self::new()
+            .rule(OperationIdRule)
+            .rule(UniqueOperationIdRule)
+            .rule(CamelCasePathParametersRule)
+            .rule(AdditionalPropertiesRule)
+            .rule(StringMaxLengthRule)
+            .rule(OperationSecurityRule)
+    }
+
+    /// Builds a linter from a configuration, e.g. parsed from the YAML of a CI job:
+    ///
+    /// ```yaml
+    /// extends: recommended   # or 'none', to start without rules
+    /// rules:
+    ///   operation-security: error
+    ///   string-max-length: off
+    /// ```
+    ///
+    /// # Arguments
+    /// * `config` - The configuration
+    ///
+    /// # Returns
+    /// * `Ok(Linter)` - The configured linter
+    /// * `Err(LintConfigError)` - If a rule set, rule or severity is unknown.
+    pub fn from_config(config: &Value) -> Result<Self, LintConfigError> {
+        let mut linter = match config.get(EXTENDS_FIELD).map(Value::as_str) {
+            None | Some(Some(RECOMMENDED_RULE_SET)) => Self::recommended(),
+            Some(Some(EMPTY_RULE_SET)) => Self::new(),
+            Some(other) => {
+                return Err(LintConfigError(format!(
+                    "Unknown rule set {}, expected '{}' or '{}'",
+                    other.map(|name| format!("'{}'", name)).unwrap_or_default(),
+                    RECOMMENDED_RULE_SET,
+                    EMPTY_RULE_SET
+                )));
+            }
+        };
+        let rules = match config.get(RULES_FIELD) {
+            Some(Value::Object(rules)) => rules,
+            Some(_) => return Err(LintConfigError("'rules' must be an object".to_string())),
+            None => return Ok(linter),
+        };
+        for (id, severity) in rules {
+            if !linter.rules.iter().any(|(rule, _)| rule.id() == id) {
+                linter = match Self::recommended().find(id) {
+                    Some(rule) => linter.rules_from(rule),
+                    None => return Err(LintConfigError(format!("Unknown rule '{}'", id))),
+                };
+            }
+            linter = match severity.as_str() {
+                // YAML 1.2 reads 'off' as a string.
+                Some("off") => linter.disable(id),
+                Some(severity) => linter.severity(id, Severity::from_str(severity)?),
+                None => {
+                    return Err(LintConfigError(format!(
+                        "The severity of '{}' must be a string",
+                        id
+                    )));
+                }
+            };
+        }
+        Ok(linter)
+    }
+
+    /// Adds a rule, at its default severity.
+    pub fn rule(mut self, rule: impl LintRule + 'static) -> Self {
+        self.rules.push((Arc::new(rule), None));
+        self
+    }
+
+    /// Sets the severity of the rule with the given id.
+    pub fn severity(mut self, id: &str, severity: Severity) -> Self {
+        for (rule, configured) in self.rules.iter_mut() {
+            if rule.id() == id {
+                *configured = Some(severity);
+            }
+        }
+        self
+    }
+
+    /// Removes the rule with the given id.
+    pub fn disable(mut self, id: &str) -> Self {
+        self.rules.retain(|(rule, _)| rule.id() != id);
+        self
+    }
+
+    /// Runs every rule over the specification of a traverser.
+    pub fn lint(&self, traverser: &OpenApiTraverser) -> LintReport {
+        let context = LintContext::new(traverser);
+        let mut findings = vec![];
+        for (rule, severity) in &self.rules {
+            let severity = severity.unwrap_or_else(|| rule.default_severity());
+            for issue in rule.check(&context) {
+                findings.push(LintFinding {
+                    rule: rule.id().to_string(),
+                    severity,
+                    pointer: issue.pointer,
+                    message: issue.message,
+                });
+            }
+        }
+        LintReport { findings }
+    }
+
+    fn find(&self, id: &str) -> Option<Arc<dyn LintRule>> {
+        self.rules
+            .iter()
+            .find(|(rule, _)| rule.id() == id)
+            .map(|(rule, _)| rule.clone())
+    }
+
+    fn rules_from(mut self, rule: Arc<dyn LintRule>) -> Self {
+        self.rules.push((rule, None));
+        self
+    }
+}
+
+/// An operation of the specification.
+pub struct LintOperation<'s> {
+    pub path: &'s str,
+    pub method: &'static str,
+    /// The JSON Pointer of the Operation Object.
+    pub pointer: String,
+    pub operation: &'s Value,
+    /// The Path Item Object the operation is in.
+    pub path_item: &'s Value,
+}
+
+/// A schema of the specification, found from a request body, response body or parameter.
+pub struct LintSchema<'s> {
+    /// The JSON Pointer of the schema, where it is defined rather than where it is referenced.
+    pub pointer: String,
+    pub schema: &'s Value,
+    /// Whether the schema is a member of an `allOf`, and so only part of an object.
+    pub composed: bool,
+}
+
+/// The specification being linted.
+pub struct LintContext<'t> {
+    traverser: &'t OpenApiTraverser,
+}
+
+impl<'t> LintContext<'t> {
+    fn new(traverser: &'t OpenApiTraverser) -> Self {
+        Self { traverser }
+    }
+
+    pub fn specification(&self) -> &'t Value {
+        self.traverser.specification()
+    }
+
+    /// Every operation, in document order.
+    pub fn operations(&self) -> Vec<LintOperation<'t>> {
+        let paths = match self
+            .specification()
+            .get(PATHS_FIELD)
+            .and_then(Value::as_object)
+        {
+            Some(paths) => paths,
+            None => return vec![],
+        };
+        let mut operations = vec![];
+        for (path, path_item) in paths {
+            let path_pointer = format!("/{}/{}", PATHS_FIELD, escape(path));
+            let (path_item, path_pointer) = self.resolve(path_item, path_pointer);
+            for method in OPERATION_METHODS {
+                if let Some(operation) = path_item.get(method) {
+                    operations.push(LintOperation {
+                        path,
+                        method,
+                        pointer: format!("{}/{}", path_pointer, method),
+                        operation,
+                        path_item,
+                    });
+                }
+            }
+        }
+        operations
+    }
+
+    /// The parameters of an operation, its own and those of its path item, with their pointers.
+    pub fn parameters(&self, operation: &LintOperation<'t>) -> Vec<(String, &'t Value)> {
+        let path_pointer = operation
+            .pointer
+            .rsplit_once('/')
+            .map(|(path_pointer, _)| path_pointer.to_string())
+            .unwrap_or_default();
+        let mut parameters = vec![];
+        for (node, pointer) in [
+            (operation.path_item, path_pointer),
+            (operation.operation, operation.pointer.clone()),
+        ] {
+            if let Some(Value::Array(items)) = node.get(PARAMETERS_FIELD) {
+                for (index, parameter) in items.iter().enumerate() {
+                    let pointer = format!("{}/{}/{}", pointer, PARAMETERS_FIELD, index);
+                    parameters.push(self.resolve(parameter, pointer));
+                }
+            }
+        }
+        parameters
+            .into_iter()
+            .map(|(parameter, pointer)| (pointer, parameter))
+            .collect()
+    }
+
+    /// Every distinct schema reachable from request and response bodies, subschemas included.
+    pub fn body_schemas(&self) -> Vec<LintSchema<'t>> {
+        let mut roots = vec![];
+        for operation in self.operations() {
+            if let Some(request_body) = operation.operation.get(REQUEST_BODY_FIELD) {
+                let pointer = format!("{}/{}", operation.pointer, REQUEST_BODY_FIELD);
+                let (request_body, pointer) = self.resolve(request_body, pointer);
+                roots.extend(Self::media_schemas(request_body, &pointer));
+            }
+            if let Some(Value::Object(responses)) = operation.operation.get(RESPONSES_FIELD) {
+                for (status, response) in responses {
+                    let pointer = format!(
+                        "{}/{}/{}",
+                        operation.pointer,
+                        RESPONSES_FIELD,
+                        escape(status)
+                    );
+                    let (response, pointer) = self.resolve(response, pointer);
+                    roots.extend(Self::media_schemas(response, &pointer));
+                }
+            }
+        }
+        self.collect_schemas(roots)
+    }
+
+    /// Every distinct schema reachable from parameters, subschemas included.
+    pub fn parameter_schemas(&self) -> Vec<LintSchema<'t>> {
+        let mut roots = vec![];
+        for operation in self.operations() {
+            for (pointer, parameter) in self.parameters(&operation) {
+                if let Some(schema) = parameter.get(SCHEMA_FIELD) {
+                    roots.push((schema, format!("{}/{}", pointer, SCHEMA_FIELD)));
+                }
+                roots.extend(Self::media_schemas(parameter, &pointer));
+            }
+        }
+        self.collect_schemas(roots)
+    }
+
+    /// The schemas of the `content` of a request body, response or parameter.
+    fn media_schemas(node: &'t Value, pointer: &str) -> Vec<(&'t Value, String)> {
+        match node.get(CONTENT_FIELD) {
+            Some(Value::Object(content)) => content
+                .iter()
+                .filter_map(|(media_type, media)| {
+                    let schema = media.get(SCHEMA_FIELD)?;
+                    let pointer = format!(
+                        "{}/{}/{}/{}",
+                        pointer,
+                        CONTENT_FIELD,
+                        escape(media_type),
+                        SCHEMA_FIELD
+                    );
+                    Some((schema, pointer))
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    fn collect_schemas(&self, roots: Vec<(&'t Value, String)>) -> Vec<LintSchema<'t>> {
+        let mut schemas = vec![];
+        let mut seen = HashSet::new();
+        for (schema, pointer) in roots {
+            self.walk_schema(schema, pointer, false, &mut seen, &mut schemas);
+        }
+        schemas
+    }
+
+    fn walk_schema(
+        &self,
+        schema: &'t Value,
+        pointer: String,
+        composed: bool,
+        seen: &mut HashSet<String>,
+        schemas: &mut Vec<LintSchema<'t>>,
+    ) {
+        let (schema, pointer) = self.resolve(schema, pointer);
+        if !schema.is_object() || !seen.insert(pointer.clone()) {
+            return;
+        }
+        for keyword in SUBSCHEMA_KEYWORDS {
+            if let Some(subschema) = schema.get(keyword) {
+                let pointer = format!("{}/{}", pointer, escape(keyword));
+                self.walk_schema(subschema, pointer, false, seen, schemas);
+            }
+        }
+        for keyword in SUBSCHEMA_ARRAY_KEYWORDS {
+            if let Some(Value::Array(subschemas)) = schema.get(keyword) {
+                for (index, subschema) in subschemas.iter().enumerate() {
+                    let pointer = format!("{}/{}/{}", pointer, keyword, index);
+                    let composed = keyword == "allOf";
+                    self.walk_schema(subschema, pointer, composed, seen, schemas);
+                }
+            }
+        }
+        for keyword in SUBSCHEMA_MAP_KEYWORDS {
+            if let Some(Value::Object(subschemas)) = schema.get(keyword) {
+                for (name, subschema) in subschemas {
+                    let pointer = format!("{}/{}/{}", pointer, escape(keyword), escape(name));
+                    self.walk_schema(subschema, pointer, false, seen, schemas);
+                }
+            }
+        }
+        schemas.push(LintSchema {
+            pointer,
+            schema,
+            composed,
+        });
+    }
+
+    /// Follows local `$ref`s, keeping track of the pointer of the node they lead to.
+    fn resolve(&self, node: &'t Value, pointer: String) -> (&'t Value, String) {
+        document::follow_refs(self.specification(), node, pointer)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn traverser() -> OpenApiTraverser {
+        OpenApiTraverser::new(json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Users", "version": "1.0.0" },
+            "paths": {
+                "/users": {
+                    "get": {
+                        "operationId": "listUsers",
+                        "security": [],
+                        "responses": { "200": { "description": "Success" } }
+                    },
+                    "post": {
+                        "security": [],
+                        "responses": { "201": { "description": "Created" } }
+                    }
+                }
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_configure_rules() {
+        let config = json!({
+            "rules": {
+                "operation-operation-id": "error",
+                "string-max-length": "off"
+            }
+        });
+        let linter = Linter::from_config(&config).unwrap();
+        assert_eq!(linter.rules.len(), 5);
+        let report = linter.lint(&traverser());
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].severity, Severity::Error);
+        assert_eq!(report.findings[0].pointer, "/paths/~1users/post");
+        assert!(report.fails(Severity::Warning));
+
+        let config = json!({ "extends": "none", "rules": { "operation-operation-id": "info" } });
+        let report = Linter::from_config(&config).unwrap().lint(&traverser());
+        assert_eq!(report.count(Severity::Info), 1);
+        assert!(!report.fails(Severity::Warning));
+
+        let unknown = Linter::from_config(&json!({ "rules": { "no-such-rule": "error" } }));
+        assert!(unknown.is_err());
+        let unknown = Linter::from_config(&json!({ "rules": { "string-max-length": "fatal" } }));
+        assert!(unknown.is_err());
+    }
+
+    #[test]
+    fn test_report_output() {
+        let report = Linter::recommended().lint(&traverser());
+        assert_eq!(
+            report.to_json(),
+            json!({
+                "summary": { "error": 0, "warning": 1, "info": 0, "hint": 0 },
+                "findings": [{
+                    "rule": "operation-operation-id",
+                    "severity": "warning",
+                    "pointer": "/paths/~1users/post",
+                    "message": "Operation 'POST /users' has no operationId"
+                }]
+            })
+        );
+        assert_eq!(
+            report.to_string(),
+            "warning operation-operation-id #/paths/~1users/post: \
+             Operation 'POST /users' has no operationId\n\
+             0 error(s), 1 warning(s), 0 info, 0 hint(s)"
+        );
+    }
+
+    #[test]
+    fn test_percent_encoded_references() {
+        let traverser = OpenApiTraverser::new(json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Users", "version": "1.0.0" },
+            "paths": {
+                "/users": {
+                    "post": {
+                        "operationId": "createUser",
+                        "security": [],
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/User%20Name" }
+                                }
+                            }
+                        },
+                        "responses": { "201": { "description": "Created" } }
+                    }
+                }
+            },
+            "components": { "schemas": { "User Name": { "type": "string" } } }
+        }))
+        .unwrap();
+        let report = Linter::recommended().lint(&traverser);
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].rule, "string-max-length");
+        assert_eq!(report.findings[0].pointer, "/components/schemas/User Name");
+    }
+}
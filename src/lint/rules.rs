@@ -0,0 +1,356 @@
+//! The built-in lint rules.
+use crate::lint::{LintContext, LintIssue, LintOperation, LintRule, LintSchema, Severity};
+use crate::{IN_FIELD, NAME_FIELD, SECURITY_FIELD, TYPE_FIELD};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+
+const OPERATION_ID_FIELD: &str = "operationId";
+const PROPERTIES_FIELD: &str = "properties";
+const ADDITIONAL_PROPERTIES_FIELD: &str = "additionalProperties";
+const UNEVALUATED_PROPERTIES_FIELD: &str = "unevaluatedProperties";
+const MAX_LENGTH_FIELD: &str = "maxLength";
+const ENUM_FIELD: &str = "enum";
+const CONST_FIELD: &str = "const";
+const PATH_LOCATION: &str = "path";
+
+/// Every operation has an `operationId`.
+pub struct OperationIdRule;
+
+impl LintRule for OperationIdRule {
+    fn id(&self) -> &str {
+        "operation-operation-id"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<LintIssue> {
+        context
+            .operations()
+            .into_iter()
+            .filter(|operation| operation_id(operation).is_none())
+            .map(|operation| {
+                LintIssue::new(
+                    operation.pointer.clone(),
+                    format!("Operation '{}' has no operationId", describe(&operation)),
+                )
+            })
+            .collect()
+    }
+}
+
+/// No two operations share an `operationId`.
+pub struct UniqueOperationIdRule;
+
+impl LintRule for UniqueOperationIdRule {
+    fn id(&self) -> &str {
+        "operation-operation-id-unique"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<LintIssue> {
+        let mut first_use: HashMap<&str, String> = HashMap::new();
+        let mut issues = vec![];
+        for operation in context.operations() {
+            let id = match operation_id(&operation) {
+                Some(id) => id,
+                None => continue,
+            };
+            match first_use.get(id) {
+                Some(first) => issues.push(LintIssue::new(
+                    format!("{}/{}", operation.pointer, OPERATION_ID_FIELD),
+                    format!(
+                        "operationId '{}' of '{}' is already used by '{}'",
+                        id,
+                        describe(&operation),
+                        first
+                    ),
+                )),
+                None => {
+                    first_use.insert(id, describe(&operation));
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Path parameters are named in camelCase, e.g. `userId` rather than `user_id`.
+pub struct CamelCasePathParametersRule;
+
+impl LintRule for CamelCasePathParametersRule {
+    fn id(&self) -> &str {
+        "path-parameters-camel-case"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<LintIssue> {
+        let mut seen = HashSet::new();
+        let mut issues = vec![];
+        for operation in context.operations() {
+            for (pointer, parameter) in context.parameters(&operation) {
+                let name = match parameter.get(IN_FIELD).and_then(Value::as_str) {
+                    Some(PATH_LOCATION) => parameter.get(NAME_FIELD).and_then(Value::as_str),
+                    _ => None,
+                };
+                match name {
+                    // Parameters of a path item are shared by its operations, report them once.
+                    Some(name) if !is_camel_case(name) && seen.insert(pointer.clone()) => {
+                        issues.push(LintIssue::new(
+                            pointer,
+                            format!("Path parameter '{}' is not camelCase", name),
+                        ));
+                    }
+                    _ => {}
+                }
+            }
+        }
+        issues
+    }
+}
+
+/// Object schemas of request and response bodies set `additionalProperties` (or, in 3.1,
+/// `unevaluatedProperties`). Members of an `allOf` are exempt, the composed schema sets it.
+pub struct AdditionalPropertiesRule;
+
+impl LintRule for AdditionalPropertiesRule {
+    fn id(&self) -> &str {
+        "body-additional-properties"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<LintIssue> {
+        context
+            .body_schemas()
+            .into_iter()
+            .filter(|schema| !schema.composed && has_type(schema, "object"))
+            .filter(|schema| {
+                schema.schema.get(ADDITIONAL_PROPERTIES_FIELD).is_none()
+                    && schema.schema.get(UNEVALUATED_PROPERTIES_FIELD).is_none()
+            })
+            .map(|schema| {
+                LintIssue::new(
+                    schema.pointer,
+                    "Object schema does not set additionalProperties",
+                )
+            })
+            .collect()
+    }
+}
+
+/// String schemas of bodies and parameters set `maxLength`, unless they are an `enum` or `const`.
+pub struct StringMaxLengthRule;
+
+impl LintRule for StringMaxLengthRule {
+    fn id(&self) -> &str {
+        "string-max-length"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<LintIssue> {
+        let mut seen = HashSet::new();
+        context
+            .body_schemas()
+            .into_iter()
+            .chain(context.parameter_schemas())
+            .filter(|schema| seen.insert(schema.pointer.clone()))
+            .filter(|schema| has_type(schema, "string"))
+            .filter(|schema| {
+                [MAX_LENGTH_FIELD, ENUM_FIELD, CONST_FIELD]
+                    .iter()
+                    .all(|keyword| schema.schema.get(*keyword).is_none())
+            })
+            .map(|schema| LintIssue::new(schema.pointer, "String schema does not set maxLength"))
+            .collect()
+    }
+}
+
+/// Every operation declares its security requirements, or inherits those of the document. An
+/// empty `security` list declares an operation public on purpose.
+pub struct OperationSecurityRule;
+
+impl LintRule for OperationSecurityRule {
+    fn id(&self) -> &str {
+        "operation-security"
+    }
+
+    fn default_severity(&self) -> Severity {
+        Severity::Warning
+    }
+
+    fn check(&self, context: &LintContext) -> Vec<LintIssue> {
+        if context.specification().get(SECURITY_FIELD).is_some() {
+            return vec![];
+        }
+        context
+            .operations()
+            .into_iter()
+            .filter(|operation| operation.operation.get(SECURITY_FIELD).is_none())
+            .map(|operation| {
+                LintIssue::new(
+                    operation.pointer.clone(),
+                    format!(
+                        "Operation '{}' declares no security requirements",
+                        describe(&operation)
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+fn operation_id<'s>(operation: &LintOperation<'s>) -> Option<&'s str> {
+    operation
+        .operation
+        .get(OPERATION_ID_FIELD)
+        .and_then(Value::as_str)
+}
+
+fn describe(operation: &LintOperation) -> String {
+    format!(
+        "{} {}",
+        operation.method.to_ascii_uppercase(),
+        operation.path
+    )
+}
+
+/// Whether a schema is of a type, by its `type` (a name or a 3.1 array of names). Schemas with
+/// `properties` and no `type` are taken for objects.
+fn has_type(schema: &LintSchema, name: &str) -> bool {
+    match schema.schema.get(TYPE_FIELD) {
+        Some(Value::String(type_name)) => type_name == name,
+        Some(Value::Array(type_names)) => type_names.iter().any(|type_name| type_name == name),
+        _ => name == "object" && schema.schema.get(PROPERTIES_FIELD).is_some(),
+    }
+}
+
+fn is_camel_case(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_lowercase()) && chars.all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lint::{Linter, Severity};
+    use crate::traverser::OpenApiTraverser;
+    use serde_json::json;
+
+    #[test]
+    fn test_recommended_rules() {
+        let traverser = OpenApiTraverser::new(json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Users", "version": "1.0.0" },
+            "paths": {
+                "/users/{userId}": {
+                    "get": {
+                        "operationId": "getUser",
+                        "security": [{ "oauth": ["users:read"] }],
+                        "parameters": [
+                            { "name": "userId", "in": "path", "required": true, "schema": { "type": "integer" } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "Success",
+                                "content": {
+                                    "application/json": { "schema": { "$ref": "#/components/schemas/User" } }
+                                }
+                            }
+                        }
+                    },
+                    "put": {
+                        "operationId": "getUser",
+                        "parameters": [
+                            { "name": "userId", "in": "path", "required": true, "schema": { "type": "integer" } }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": { "schema": { "$ref": "#/components/schemas/User" } }
+                            }
+                        },
+                        "responses": { "204": { "description": "Updated" } }
+                    }
+                },
+                "/orders/{order_id}": {
+                    "delete": {
+                        "security": [],
+                        "parameters": [
+                            { "name": "order_id", "in": "path", "required": true, "schema": { "type": "integer" } },
+                            { "name": "reason", "in": "query", "schema": { "type": "string", "enum": ["spam"] } }
+                        ],
+                        "responses": { "204": { "description": "Deleted" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "User": {
+                        "allOf": [{ "$ref": "#/components/schemas/Named" }],
+                        "unevaluatedProperties": false,
+                        "properties": {
+                            "address": {
+                                "type": "object",
+                                "properties": { "street": { "type": "string", "maxLength": 64 } }
+                            }
+                        }
+                    },
+                    "Named": {
+                        "type": "object",
+                        "properties": { "name": { "type": ["string", "null"] } }
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let report = Linter::recommended().lint(&traverser);
+        let findings: Vec<(&str, &str)> = report
+            .findings
+            .iter()
+            .map(|finding| (finding.rule.as_str(), finding.pointer.as_str()))
+            .collect();
+        assert_eq!(
+            findings,
+            vec![
+                (
+                    "operation-operation-id",
+                    "/paths/~1orders~1{order_id}/delete"
+                ),
+                (
+                    "operation-operation-id-unique",
+                    "/paths/~1users~1{userId}/put/operationId"
+                ),
+                (
+                    "path-parameters-camel-case",
+                    "/paths/~1orders~1{order_id}/delete/parameters/0"
+                ),
+                (
+                    "body-additional-properties",
+                    "/components/schemas/User/properties/address"
+                ),
+                (
+                    "string-max-length",
+                    "/components/schemas/Named/properties/name"
+                ),
+                ("operation-security", "/paths/~1users~1{userId}/put"),
+            ]
+        );
+        assert_eq!(report.count(Severity::Error), 1);
+        assert_eq!(
+            report.findings[1].message,
+            "operationId 'getUser' of 'PUT /users/{userId}' is already used by 'GET /users/{userId}'"
+        );
+    }
+}
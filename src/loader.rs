@@ -0,0 +1,266 @@
+//! Parsing of OpenAPI documents written in JSON or YAML.
+//!
+//! YAML is read as YAML 1.2: `yes`, `no`, `on` and `off` are strings rather than booleans.
+//! Anchors and aliases are expanded, `<<` merge keys are applied, and non-string mapping keys
+//! (e.g. the `200:` of a response status) are converted to strings, as JSON requires.
+use serde_json::{Map, Number, Value};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+const MERGE_KEY: &str = "<<";
+
+/// The format an OpenAPI document is written in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpecificationFormat {
+    Json,
+    Yaml,
+}
+
+impl SpecificationFormat {
+    /// The format of a file, by its extension (`.json`, `.yaml` or `.yml`).
+    pub fn from_path(path: impl AsRef<Path>) -> Option<Self> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(SpecificationFormat::Json),
+            "yaml" | "yml" => Some(SpecificationFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// The format of a document, by its content: JSON documents start with `{` or `[`.
+    pub fn detect(content: &str) -> Self {
+        match content.trim_start_matches('\u{feff}').trim_start().chars().next() {
+            Some('{') | Some('[') => SpecificationFormat::Json,
+            _ => SpecificationFormat::Yaml,
+        }
+    }
+}
+
+impl Display for SpecificationFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpecificationFormat::Json => write!(f, "JSON"),
+            SpecificationFormat::Yaml => write!(f, "YAML"),
+        }
+    }
+}
+
+/// A document that could not be parsed, with the position of the problem when it is known.
+#[derive(Debug)]
+pub struct LoadError {
+    pub format: SpecificationFormat,
+    pub message: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+}
+
+impl LoadError {
+    fn new(format: SpecificationFormat, message: impl Into<String>) -> Self {
+        Self {
+            format,
+            message: message.into(),
+            line: None,
+            column: None,
+        }
+    }
+
+    fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+}
+
+impl Display for LoadError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => write!(
+                f,
+                "Invalid {} at line {}, column {}: {}",
+                self.format, line, column, self.message
+            ),
+            _ => write!(f, "Invalid {}: {}", self.format, self.message),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+/// Parses an OpenAPI document.
+///
+/// # Arguments
+/// * `content` - The document
+/// * `format` - The format of the document, detected from its content when `None`
+///
+/// # Returns
+/// * `Ok(Value)` - The document as JSON
+/// * `Err(LoadError)` - If the document is not valid JSON or YAML, or cannot be represented as JSON.
+pub fn parse_specification(
+    content: &str,
+    format: Option<SpecificationFormat>,
+) -> Result<Value, LoadError> {
+    let content = content.trim_start_matches('\u{feff}');
+    match format.unwrap_or_else(|| SpecificationFormat::detect(content)) {
+        SpecificationFormat::Json => parse_json(content),
+        SpecificationFormat::Yaml => parse_yaml(content),
+    }
+}
+
+fn parse_json(content: &str) -> Result<Value, LoadError> {
+    match serde_json::from_str(content) {
+        Ok(value) => Ok(value),
+        Err(e) => Err(LoadError::new(SpecificationFormat::Json, e.to_string())
+            .at(e.line(), e.column())),
+    }
+}
+
+fn parse_yaml(content: &str) -> Result<Value, LoadError> {
+    let mut value: serde_yaml::Value = match serde_yaml::from_str(content) {
+        Ok(value) => value,
+        Err(e) => {
+            let error = LoadError::new(SpecificationFormat::Yaml, e.to_string());
+            return Err(match e.location() {
+                Some(location) => error.at(location.line(), location.column()),
+                None => error,
+            });
+        }
+    };
+    if let Err(e) = value.apply_merge() {
+        return Err(LoadError::new(
+            SpecificationFormat::Yaml,
+            format!("Invalid merge key '{}': {}", MERGE_KEY, e),
+        ));
+    }
+    yaml_to_json(value, "")
+}
+
+/// Converts a YAML value into JSON. `pointer` locates the value, for error reporting.
+fn yaml_to_json(value: serde_yaml::Value, pointer: &str) -> Result<Value, LoadError> {
+    match value {
+        serde_yaml::Value::Null => Ok(Value::Null),
+        serde_yaml::Value::Bool(value) => Ok(Value::Bool(value)),
+        serde_yaml::Value::Number(number) => yaml_number_to_json(&number, pointer),
+        serde_yaml::Value::String(value) => Ok(Value::String(value)),
+        serde_yaml::Value::Sequence(items) => {
+            let mut converted = Vec::with_capacity(items.len());
+            for (index, item) in items.into_iter().enumerate() {
+                converted.push(yaml_to_json(item, &format!("{}/{}", pointer, index))?);
+            }
+            Ok(Value::Array(converted))
+        }
+        serde_yaml::Value::Mapping(mapping) => {
+            let mut converted = Map::new();
+            for (key, value) in mapping {
+                let key = yaml_key_to_string(key, pointer)?;
+                let value_pointer = format!(
+                    "{}/{}",
+                    pointer,
+                    key.replace('~', "~0").replace('/', "~1")
+                );
+                converted.insert(key, yaml_to_json(value, &value_pointer)?);
+            }
+            Ok(Value::Object(converted))
+        }
+        serde_yaml::Value::Tagged(tagged) => yaml_to_json(tagged.value, pointer),
+    }
+}
+
+fn yaml_number_to_json(number: &serde_yaml::Number, pointer: &str) -> Result<Value, LoadError> {
+    if let Some(value) = number.as_i64() {
+        return Ok(Value::Number(value.into()));
+    }
+    if let Some(value) = number.as_u64() {
+        return Ok(Value::Number(value.into()));
+    }
+    match number.as_f64().and_then(Number::from_f64) {
+        Some(value) => Ok(Value::Number(value)),
+        None => Err(LoadError::new(
+            SpecificationFormat::Yaml,
+            format!("'{}' at '{}' cannot be represented in JSON", number, pointer),
+        )),
+    }
+}
+
+/// Converts a mapping key into a JSON object key. Scalars keep their YAML spelling.
+fn yaml_key_to_string(key: serde_yaml::Value, pointer: &str) -> Result<String, LoadError> {
+    match key {
+        serde_yaml::Value::String(key) => Ok(key),
+        serde_yaml::Value::Number(key) => Ok(key.to_string()),
+        serde_yaml::Value::Bool(key) => Ok(key.to_string()),
+        serde_yaml::Value::Null => Ok("null".to_string()),
+        serde_yaml::Value::Tagged(tagged) => yaml_key_to_string(tagged.value, pointer),
+        serde_yaml::Value::Sequence(_) | serde_yaml::Value::Mapping(_) => Err(LoadError::new(
+            SpecificationFormat::Yaml,
+            format!("Mapping key at '{}' must be a scalar", pointer),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            SpecificationFormat::from_path("spec/openapi.YML"),
+            Some(SpecificationFormat::Yaml)
+        );
+        assert_eq!(SpecificationFormat::from_path("openapi.txt"), None);
+        assert_eq!(
+            SpecificationFormat::detect("\u{feff}  {\"openapi\": \"3.1.0\"}"),
+            SpecificationFormat::Json
+        );
+        assert_eq!(
+            SpecificationFormat::detect("openapi: 3.1.0"),
+            SpecificationFormat::Yaml
+        );
+    }
+
+    #[test]
+    fn test_yaml_pitfalls() {
+        let yaml = r#"
+openapi: 3.1.0
+x-defaults: &defaults
+  description: Success
+  x-cached: yes
+paths:
+  /lights:
+    put:
+      parameters:
+        - name: state
+          in: query
+          schema:
+            enum: [on, off]
+      responses:
+        200:
+          <<: *defaults
+        404: *defaults
+"#;
+        let spec = parse_specification(yaml, None).unwrap();
+        let responses = &spec["paths"]["/lights"]["put"]["responses"];
+        assert_eq!(
+            responses["200"],
+            json!({ "description": "Success", "x-cached": "yes" })
+        );
+        assert_eq!(responses["404"], responses["200"]);
+        assert_eq!(
+            spec["paths"]["/lights"]["put"]["parameters"][0]["schema"]["enum"],
+            json!(["on", "off"])
+        );
+        assert_eq!(spec["openapi"], json!("3.1.0"));
+    }
+
+    #[test]
+    fn test_error_location() {
+        let error = parse_specification("openapi: 3.1.0\npaths:\n  - a\n  b: c\n", None).unwrap_err();
+        assert_eq!(error.format, SpecificationFormat::Yaml);
+        assert_eq!(error.line, Some(4));
+        assert!(error.to_string().starts_with("Invalid YAML at line 4"));
+
+        let error = parse_specification("{\n  \"openapi\": 3.1.0,\n}", None).unwrap_err();
+        assert_eq!(error.format, SpecificationFormat::Json);
+        assert_eq!(error.line, Some(2));
+    }
+}
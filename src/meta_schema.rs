@@ -0,0 +1,578 @@
+//! Validation of an OpenAPI document itself, against the official OAS 3.0 and 3.1 meta-schemas.
+//!
+//! The meta-schemas are bundled with the crate, nothing is fetched. Besides the structure the
+//! meta-schemas describe, two rules they cannot express are checked: local `$ref`s must point at
+//! something, and every `{name}` of a path template must have a matching path parameter (and the
+//! other way around).
+use crate::document::{self, escape, resolve_fragment, OPERATION_METHODS};
+use crate::types::version::OpenApiVersion;
+use crate::{IN_FIELD, NAME_FIELD, OPENAPI_FIELD, PARAMETERS_FIELD, PATHS_FIELD, REF_FIELD};
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::{Draft, Validator as JsonValidator};
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// The OAS 3.0 meta-schema, `https://spec.openapis.org/oas/3.0/schema/2021-09-28`.
+const OAS_30_META_SCHEMA: &str = include_str!("../schemas/oas-3.0.json");
+
+/// The OAS 3.1 meta-schema, `https://spec.openapis.org/oas/3.1/schema/2022-10-07`. Schema Objects
+/// are only checked to be objects or booleans.
+const OAS_31_META_SCHEMA: &str = include_str!("../schemas/oas-3.1.json");
+
+const PATH_LOCATION: &str = "path";
+const ID_FIELD: &str = "$id";
+
+/// A problem with an OpenAPI document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpecificationError {
+    /// The JSON Pointer of the offending part of the document.
+    pub pointer: String,
+    pub message: String,
+}
+
+impl SpecificationError {
+    fn new(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for SpecificationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+impl std::error::Error for SpecificationError {}
+
+/// Validates an OpenAPI 3.0.x or 3.1.x document against its meta-schema, and checks its
+/// references and path templates.
+///
+/// # Arguments
+/// * `specification` - The document, with the version in its `openapi` field
+///
+/// # Returns
+/// * `Ok(())` - If the document is valid
+/// * `Err(Vec<SpecificationError>)` - Every problem found, in document order.
+///
+/// # Example
+///
+/// ```rust
+/// use oasert::meta_schema::validate_specification;
+/// use serde_json::json;
+///
+/// let specification = json!({
+///     "openapi": "3.1.0",
+///     "info": { "title": "Users", "version": "1.0.0" },
+///     "paths": {
+///         "/users/{id}": {
+///             "get": {
+///                 "parameters": [{ "name": "id", "schema": { "type": "integer" } }],
+///                 "responses": { "200": { "description": "Success" } }
+///             }
+///         }
+///     }
+/// });
+/// let errors = validate_specification(&specification).unwrap_err();
+/// assert!(errors.iter().any(|e| e.pointer == "/paths/~1users~1{id}/get/parameters/0"));
+/// ```
+pub fn validate_specification(specification: &Value) -> Result<(), Vec<SpecificationError>> {
+    let version = match specification.get(OPENAPI_FIELD).and_then(Value::as_str) {
+        Some(version) => version,
+        None => {
+            return Err(vec![SpecificationError::new(
+                "",
+                "The 'openapi' field is missing or not a string",
+            )]);
+        }
+    };
+    match OpenApiVersion::from_str(version) {
+        Ok(version) => check_specification(specification, &version),
+        Err(e) => Err(vec![SpecificationError::new(
+            format!("/{}", OPENAPI_FIELD),
+            e.to_string(),
+        )]),
+    }
+}
+
+/// Validates a document against the meta-schema of `version`, then checks its references and
+/// path templates.
+pub(crate) fn check_specification(
+    specification: &Value,
+    version: &OpenApiVersion,
+) -> Result<(), Vec<SpecificationError>> {
+    let mut errors = MetaSchema::get(version).validate(specification);
+    check_references(specification, specification, "", &mut errors);
+    check_path_templates(specification, &mut errors);
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// What a meta-schema error says about the document.
+enum Finding {
+    Error,
+    /// An alternative the document matches more than one way, because of an error in it.
+    MultipleValid,
+    /// Properties a parent did not expect, usually because they have errors themselves.
+    Unevaluated(Vec<String>),
+}
+
+/// A compiled meta-schema.
+struct MetaSchema {
+    document: Value,
+    draft: Draft,
+    validator: JsonValidator,
+}
+
+impl MetaSchema {
+    fn get(version: &OpenApiVersion) -> &'static MetaSchema {
+        static OAS_30: OnceLock<MetaSchema> = OnceLock::new();
+        static OAS_31: OnceLock<MetaSchema> = OnceLock::new();
+        match version {
+            OpenApiVersion::V30x => OAS_30.get_or_init(|| Self::compile(OAS_30_META_SCHEMA)),
+            OpenApiVersion::V31x => OAS_31.get_or_init(|| Self::compile(OAS_31_META_SCHEMA)),
+        }
+    }
+
+    fn compile(content: &str) -> Self {
+        // The bundled meta-schemas are known to be valid, a failure here is a packaging bug.
+        let document: Value =
+            serde_json::from_str(content).expect("The bundled meta-schema is not valid JSON");
+        let draft = match Draft::default().detect(&document) {
+            Ok(draft) => draft,
+            Err(e) => panic!("The bundled meta-schema has an unknown draft: {}", e),
+        };
+        let validator = Self::compile_schema(&document, draft);
+        Self {
+            document,
+            draft,
+            validator,
+        }
+    }
+
+    fn compile_schema(schema: &Value, draft: Draft) -> JsonValidator {
+        match jsonschema::options().with_draft(draft).build(schema) {
+            Ok(validator) => validator,
+            Err(e) => panic!("The bundled meta-schema does not compile: {}", e),
+        }
+    }
+
+    fn validate(&self, specification: &Value) -> Vec<SpecificationError> {
+        let mut branches = HashMap::new();
+        let found = self.validate_with(&self.validator, specification, "", &mut branches);
+
+        // A part of the document with errors is also unexpected to the parents that evaluate it,
+        // and an incomplete part may match several alternatives. These follow-up errors are dropped.
+        let has_errors_below = |pointer: &str| {
+            found.iter().any(|(error, kind)| {
+                !matches!(kind, Finding::Unevaluated(_)) && error.pointer.starts_with(pointer)
+            })
+        };
+        let mut errors = vec![];
+        for (error, kind) in &found {
+            match kind {
+                Finding::Error => errors.push(error.clone()),
+                Finding::MultipleValid => {
+                    let others = found.iter().any(|(other, kind)| {
+                        matches!(kind, Finding::Error) && other.pointer == error.pointer
+                    });
+                    if !others {
+                        errors.push(error.clone());
+                    }
+                }
+                Finding::Unevaluated(unexpected) => {
+                    let unexpected: Vec<String> = unexpected
+                        .iter()
+                        .filter(|name| {
+                            !has_errors_below(&format!("{}/{}", error.pointer, escape(name)))
+                        })
+                        .map(|name| format!("'{}'", name))
+                        .collect();
+                    if !unexpected.is_empty() {
+                        errors.push(SpecificationError::new(
+                            error.pointer.clone(),
+                            format!("Unexpected properties: {}", unexpected.join(", ")),
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+
+    /// Validates a part of the document, located by `prefix`.
+    fn validate_with(
+        &self,
+        validator: &JsonValidator,
+        instance: &Value,
+        prefix: &str,
+        branches: &mut HashMap<String, Option<JsonValidator>>,
+    ) -> Vec<(SpecificationError, Finding)> {
+        let mut errors = vec![];
+        for error in validator.iter_errors(instance) {
+            let pointer = format!("{}{}", prefix, error.instance_path);
+            let keyword = match &error.kind {
+                ValidationErrorKind::OneOfNotValid => "oneOf",
+                ValidationErrorKind::AnyOf => "anyOf",
+                ValidationErrorKind::OneOfMultipleValid => {
+                    let message =
+                        "Valid under more than one of the schemas listed in the 'oneOf' keyword";
+                    errors.push((
+                        SpecificationError::new(pointer, message),
+                        Finding::MultipleValid,
+                    ));
+                    continue;
+                }
+                ValidationErrorKind::UnevaluatedProperties { unexpected } => {
+                    let error = SpecificationError::new(pointer, error.to_string());
+                    errors.push((error, Finding::Unevaluated(unexpected.clone())));
+                    continue;
+                }
+                _ => {
+                    errors.push((
+                        SpecificationError::new(pointer, error.to_string()),
+                        Finding::Error,
+                    ));
+                    continue;
+                }
+            };
+            // 'Parameter or Reference' alternatives say little about what is wrong with a
+            // parameter. When there is a single candidate, the errors against it are reported.
+            let schema_path = error.schema_path.to_string();
+            let instance = error.instance.as_ref();
+            let refined = match self.branch(&schema_path, instance, branches) {
+                Some(branch) => self.validate_with(branch, instance, &pointer, &mut HashMap::new()),
+                None => vec![],
+            };
+            match refined.is_empty() {
+                true => {
+                    let message = format!(
+                        "Not valid under any of the schemas listed in the '{}' keyword",
+                        keyword
+                    );
+                    errors.push((SpecificationError::new(pointer, message), Finding::Error));
+                }
+                false => errors.extend(refined),
+            }
+        }
+        errors
+    }
+
+    /// The only subschema of a failed `oneOf`/`anyOf` an instance can have been meant for. That
+    /// is the one that is not a Reference Object, when the instance has no `$ref`.
+    fn branch<'b>(
+        &self,
+        schema_path: &str,
+        instance: &Value,
+        branches: &'b mut HashMap<String, Option<JsonValidator>>,
+    ) -> Option<&'b JsonValidator> {
+        if instance.get(REF_FIELD).is_some() {
+            return None;
+        }
+        let key = format!("{}|{}", schema_path, instance.is_object());
+        if !branches.contains_key(&key) {
+            let candidates: Vec<&Value> = match self.schema_at(schema_path) {
+                Some(Value::Array(candidates)) => candidates
+                    .iter()
+                    .filter(|candidate| !is_reference_schema(candidate))
+                    .collect(),
+                _ => vec![],
+            };
+            let compiled = match candidates.as_slice() {
+                [candidate] => Some(self.compile_branch(candidate)),
+                _ => None,
+            };
+            branches.insert(key.clone(), compiled);
+        }
+        branches.get(&key).and_then(Option::as_ref)
+    }
+
+    /// Finds the subschema of the meta-schema at a keyword location, following the `$ref`s
+    /// the location passes through.
+    fn schema_at(&self, schema_path: &str) -> Option<&Value> {
+        let mut node = &self.document;
+        for segment in schema_path.split('/').skip(1) {
+            if segment == REF_FIELD {
+                let reference = node.get(REF_FIELD)?.as_str()?;
+                node = self.document.pointer(reference.strip_prefix('#')?)?;
+                continue;
+            }
+            let segment = segment.replace("~1", "/").replace("~0", "~");
+            node = match node {
+                Value::Object(map) => map.get(&segment)?,
+                Value::Array(items) => items.get(segment.parse::<usize>().ok()?)?,
+                _ => return None,
+            };
+        }
+        Some(node)
+    }
+
+    /// Compiles a subschema of the meta-schema, keeping the definitions it may refer to.
+    fn compile_branch(&self, branch: &Value) -> JsonValidator {
+        let mut schema = self.document.clone();
+        if let Some(schema) = schema.as_object_mut() {
+            schema.retain(|key, _| {
+                matches!(
+                    key.as_str(),
+                    "$schema" | "id" | "$id" | "definitions" | "$defs"
+                )
+            });
+            schema.insert("allOf".to_string(), json!([branch]));
+        }
+        Self::compile_schema(&schema, self.draft)
+    }
+}
+
+fn is_reference_schema(schema: &Value) -> bool {
+    schema
+        .get(REF_FIELD)
+        .and_then(Value::as_str)
+        .is_some_and(|target| target.ends_with("/Reference") || target.ends_with("/reference"))
+}
+
+/// Reports the local `$ref`s of a document that do not point at anything.
+fn check_references(
+    document: &Value,
+    value: &Value,
+    pointer: &str,
+    errors: &mut Vec<SpecificationError>,
+) {
+    match value {
+        Value::Object(map) => {
+            if let Some(Value::String(reference)) = map.get(REF_FIELD) {
+                let resolved = match reference.strip_prefix('#') {
+                    Some(fragment) if fragment.starts_with('/') => {
+                        resolve_fragment(document, fragment).is_some()
+                    }
+                    // Anchors and references to other documents are left to JSON Schema.
+                    _ => true,
+                };
+                if !resolved {
+                    errors.push(SpecificationError::new(
+                        pointer,
+                        format!("Reference '{}' does not resolve", reference),
+                    ));
+                }
+            }
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("example" | "examples", _) => {}
+                    (_, value) if value.get(ID_FIELD).is_some_and(Value::is_string) => {}
+                    (key, value) => {
+                        let pointer = format!("{}/{}", pointer, escape(key));
+                        check_references(document, value, &pointer, errors);
+                    }
+                }
+            }
+        }
+        Value::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                check_references(document, item, &format!("{}/{}", pointer, index), errors);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Reports path template expressions without a path parameter, and path parameters without a
+/// template expression, for every operation.
+fn check_path_templates(specification: &Value, errors: &mut Vec<SpecificationError>) {
+    let paths = match specification.get(PATHS_FIELD).and_then(Value::as_object) {
+        Some(paths) => paths,
+        None => return,
+    };
+    for (path, path_item) in paths {
+        let path_pointer = format!("/{}/{}", PATHS_FIELD, escape(path));
+        let path_item = match dereference(specification, path_item) {
+            Some(path_item) => path_item,
+            None => continue,
+        };
+        let expressions = template_expressions(path);
+        let shared = path_parameters(specification, path_item);
+        for method in OPERATION_METHODS {
+            let operation = match path_item.get(method) {
+                Some(operation) => operation,
+                None => continue,
+            };
+            let operation_pointer = format!("{}/{}", path_pointer, method);
+            let mut declared: HashSet<&str> = shared.iter().map(|(name, _)| *name).collect();
+            let own = path_parameters(specification, operation);
+            declared.extend(own.iter().map(|(name, _)| *name));
+
+            for expression in &expressions {
+                if !declared.contains(expression) {
+                    errors.push(SpecificationError::new(
+                        operation_pointer.clone(),
+                        format!(
+                            "Path template expression '{{{}}}' of '{}' has no matching path parameter",
+                            expression, path
+                        ),
+                    ));
+                }
+            }
+            for (name, parameter_pointer) in &own {
+                if !expressions.contains(name) {
+                    errors.push(SpecificationError::new(
+                        format!("{}{}", operation_pointer, parameter_pointer),
+                        format!("Path parameter '{}' does not appear in '{}'", name, path),
+                    ));
+                }
+            }
+        }
+        for (name, parameter_pointer) in &shared {
+            if !expressions.contains(name) {
+                errors.push(SpecificationError::new(
+                    format!("{}{}", path_pointer, parameter_pointer),
+                    format!("Path parameter '{}' does not appear in '{}'", name, path),
+                ));
+            }
+        }
+    }
+}
+
+/// The names of the `{name}` expressions of a path template.
+fn template_expressions(path: &str) -> Vec<&str> {
+    path.split('{')
+        .skip(1)
+        .filter_map(|segment| segment.split_once('}'))
+        .map(|(name, _)| name)
+        .collect()
+}
+
+/// The names of the path parameters of a Path Item or Operation Object, with their pointer
+/// relative to it.
+fn path_parameters<'s>(specification: &'s Value, node: &'s Value) -> Vec<(&'s str, String)> {
+    let parameters = match node.get(PARAMETERS_FIELD).and_then(Value::as_array) {
+        Some(parameters) => parameters,
+        None => return vec![],
+    };
+    parameters
+        .iter()
+        .enumerate()
+        .filter_map(|(index, parameter)| {
+            let parameter = dereference(specification, parameter)?;
+            match parameter.get(IN_FIELD).and_then(Value::as_str) {
+                Some(PATH_LOCATION) => {
+                    let name = parameter.get(NAME_FIELD).and_then(Value::as_str)?;
+                    Some((name, format!("/{}/{}", PARAMETERS_FIELD, index)))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// Follows local `$ref`s until a node that is not a reference.
+fn dereference<'s>(specification: &'s Value, node: &'s Value) -> Option<&'s Value> {
+    match document::follow_refs(specification, node, String::new()) {
+        (node, _) if node.get(REF_FIELD).is_none() => Some(node),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pointers(errors: &[SpecificationError]) -> Vec<&str> {
+        errors.iter().map(|error| error.pointer.as_str()).collect()
+    }
+
+    #[test]
+    fn test_structural_errors() {
+        for openapi in ["3.0.3", "3.1.0"] {
+            let specification = json!({
+                "openapi": openapi,
+                "info": { "title": "Users", "version": "1.0.0" },
+                "paths": {
+                    "/users": {
+                        "post": {
+                            "parameters": [{ "name": "limit", "schema": { "type": "integer" } }],
+                            "requestBody": { "description": "A user" },
+                            "responses": { "201": { "description": "Created" } }
+                        }
+                    }
+                }
+            });
+            let errors = validate_specification(&specification).unwrap_err();
+            assert_eq!(
+                errors,
+                vec![
+                    SpecificationError::new(
+                        "/paths/~1users/post/parameters/0",
+                        "\"in\" is a required property"
+                    ),
+                    SpecificationError::new(
+                        "/paths/~1users/post/requestBody",
+                        "\"content\" is a required property"
+                    ),
+                ],
+                "{}",
+                openapi
+            );
+        }
+    }
+
+    #[test]
+    fn test_semantic_errors() {
+        let specification = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Users", "version": "1.0.0" },
+            "paths": {
+                "/users/{id}": {
+                    "parameters": [{ "$ref": "#/components/parameters/Limit" }],
+                    "get": {
+                        "parameters": [
+                            { "name": "name", "in": "path", "required": true, "schema": { "type": "string" } }
+                        ],
+                        "responses": {
+                            "200": {
+                                "description": "Success",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "$ref": "#/components/schemas/User" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "parameters": {
+                    "Limit": { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                }
+            }
+        });
+        // '{id}' is declared on the path item, 'name' is not in the template.
+        let errors = validate_specification(&specification).unwrap_err();
+        assert_eq!(
+            pointers(&errors),
+            vec![
+                "/paths/~1users~1{id}/get/responses/200/content/application~1json/schema",
+                "/paths/~1users~1{id}/get/parameters/0",
+            ]
+        );
+        assert_eq!(
+            errors[0].to_string(),
+            "#/paths/~1users~1{id}/get/responses/200/content/application~1json/schema: \
+             Reference '#/components/schemas/User' does not resolve"
+        );
+
+        let mut specification = specification;
+        specification["paths"]["/users/{id}"]["get"]["parameters"] = json!([]);
+        specification["components"]["schemas"] = json!({ "User": { "type": "object" } });
+        assert!(validate_specification(&specification).is_ok());
+
+        specification["paths"]["/users/{id}"]["parameters"] = json!([]);
+        let errors = validate_specification(&specification).unwrap_err();
+        assert_eq!(pointers(&errors), vec!["/paths/~1users~1{id}/get"]);
+    }
+}
@@ -0,0 +1,440 @@
+//! OpenAPI Overlays (Overlay Specification 1.0).
+//!
+//! An `Overlay` is an ordered list of actions, each selecting nodes of a specification with a
+//! JSONPath `target`. An action either merges its `update` into the selected nodes (objects are
+//! merged member by member, an array gets the update appended, anything else is replaced) or, with
+//! `remove: true`, removes them. This lets environment specific changes, such as stricter limits
+//! or hidden internal operations, live next to a shared specification.
+//!
+//! `OpenApiPayloadValidatorBuilder::overlay` applies overlays to the specification it loads,
+//! before anything is compiled from it.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oasert::overlay::Overlay;
+//! use serde_json::json;
+//!
+//! let overlay = Overlay::from_value(json!({
+//!     "overlay": "1.0.0",
+//!     "info": { "title": "Production", "version": "1.0.0" },
+//!     "actions": [
+//!         { "target": "$.paths.*[?@.x-internal == true]", "remove": true },
+//!         { "target": "$.components.schemas.Name", "update": { "maxLength": 64 } }
+//!     ]
+//! }))
+//! .unwrap();
+//! let mut specification = json!({
+//!     "openapi": "3.1.0",
+//!     "paths": {
+//!         "/users": { "get": {}, "delete": { "x-internal": true } }
+//!     },
+//!     "components": { "schemas": { "Name": { "type": "string" } } }
+//! });
+//! overlay.apply(&mut specification).unwrap();
+//! assert_eq!(specification["paths"]["/users"], json!({ "get": {} }));
+//! assert_eq!(specification["components"]["schemas"]["Name"]["maxLength"], 64);
+//! ```
+mod selector;
+
+use crate::loader::{self, SpecificationFormat};
+use selector::{Location, Selector, Step};
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::str::FromStr;
+
+const OVERLAY_FIELD: &str = "overlay";
+const ACTIONS_FIELD: &str = "actions";
+const TARGET_FIELD: &str = "target";
+const UPDATE_FIELD: &str = "update";
+const REMOVE_FIELD: &str = "remove";
+
+/// The major version of the Overlay Specification supported.
+const OVERLAY_VERSION_PREFIX: &str = "1.";
+
+/// An overlay that is invalid, or one of its actions that could not be applied.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OverlayError {
+    /// JSON Pointer to the cause in the overlay document, e.g. `/actions/2`
+    pub pointer: String,
+    pub message: String,
+}
+
+impl OverlayError {
+    fn new(pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        OverlayError {
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl Display for OverlayError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+impl std::error::Error for OverlayError {}
+
+/// An action of an overlay.
+#[derive(Debug, Clone)]
+struct Action {
+    target: String,
+    selector: Selector,
+    update: Option<Value>,
+    remove: bool,
+}
+
+/// A parsed overlay document.
+#[derive(Debug, Clone)]
+pub struct Overlay {
+    actions: Vec<Action>,
+}
+
+impl Overlay {
+    /// Parses an overlay document.
+    ///
+    /// # Arguments
+    /// * `document` - The overlay document
+    ///
+    /// # Returns
+    /// * `Ok(Overlay)` - The overlay
+    /// * `Err(OverlayError)` - If the document is not a 1.x overlay, or an action is invalid.
+    pub fn from_value(document: Value) -> Result<Self, OverlayError> {
+        match document.get(OVERLAY_FIELD).and_then(Value::as_str) {
+            Some(version) if version.starts_with(OVERLAY_VERSION_PREFIX) => {}
+            Some(version) => {
+                return Err(OverlayError::new(
+                    format!("/{}", OVERLAY_FIELD),
+                    format!("Unsupported overlay version '{}'", version),
+                ));
+            }
+            None => {
+                return Err(OverlayError::new(
+                    "",
+                    format!("'{}' must be a version string", OVERLAY_FIELD),
+                ));
+            }
+        }
+        let documents = match document.get(ACTIONS_FIELD) {
+            Some(Value::Array(actions)) if !actions.is_empty() => actions,
+            _ => {
+                return Err(OverlayError::new(
+                    "",
+                    format!("'{}' must be a non-empty array", ACTIONS_FIELD),
+                ));
+            }
+        };
+        let mut actions = vec![];
+        for (index, action) in documents.iter().enumerate() {
+            actions.push(Self::action(index, action)?);
+        }
+        Ok(Overlay { actions })
+    }
+
+    /// Reads and parses an overlay document from a JSON or YAML file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, OverlayError> {
+        let path = path.as_ref();
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => return Err(OverlayError::new("", e.to_string())),
+        };
+        match loader::parse_specification(&content, SpecificationFormat::from_path(path)) {
+            Ok(document) => Self::from_value(document),
+            Err(e) => Err(OverlayError::new("", e.to_string())),
+        }
+    }
+
+    fn action(index: usize, action: &Value) -> Result<Action, OverlayError> {
+        let pointer = format!("/{}/{}", ACTIONS_FIELD, index);
+        let target = match action.get(TARGET_FIELD).and_then(Value::as_str) {
+            Some(target) => target,
+            None => {
+                return Err(OverlayError::new(
+                    pointer,
+                    format!("'{}' must be a JSONPath string", TARGET_FIELD),
+                ));
+            }
+        };
+        let selector = match Selector::parse(target) {
+            Ok(selector) => selector,
+            Err(e) => {
+                return Err(OverlayError::new(
+                    format!("{}/{}", pointer, TARGET_FIELD),
+                    format!("Invalid JSONPath '{}': {}", target, e),
+                ));
+            }
+        };
+        let remove = match action.get(REMOVE_FIELD) {
+            None => false,
+            Some(Value::Bool(remove)) => *remove,
+            Some(_) => {
+                return Err(OverlayError::new(
+                    format!("{}/{}", pointer, REMOVE_FIELD),
+                    format!("'{}' must be a boolean", REMOVE_FIELD),
+                ));
+            }
+        };
+        let update = action.get(UPDATE_FIELD).cloned();
+        if update.is_none() && !remove {
+            return Err(OverlayError::new(
+                pointer,
+                format!(
+                    "Action has neither '{}' nor '{}: true'",
+                    UPDATE_FIELD, REMOVE_FIELD
+                ),
+            ));
+        }
+        Ok(Action {
+            target: target.to_string(),
+            selector,
+            update,
+            remove,
+        })
+    }
+
+    /// Applies the actions of the overlay to a specification, in order.
+    ///
+    /// Every action is applied, even after one fails, so that all problems are reported at once.
+    ///
+    /// # Arguments
+    /// * `specification` - The specification to change
+    ///
+    /// # Returns
+    /// * `Ok(())` - If every action was applied
+    /// * `Err(Vec<OverlayError>)` - The actions whose target matched nothing, or that tried to
+    ///   remove the document itself.
+    pub fn apply(&self, specification: &mut Value) -> Result<(), Vec<OverlayError>> {
+        let mut errors = vec![];
+        for (index, action) in self.actions.iter().enumerate() {
+            if let Err(message) = action.apply(specification) {
+                errors.push(OverlayError::new(
+                    format!("/{}/{}", ACTIONS_FIELD, index),
+                    message,
+                ));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}
+
+impl FromStr for Overlay {
+    type Err = OverlayError;
+
+    /// Parses an overlay document from JSON or YAML.
+    fn from_str(content: &str) -> Result<Self, Self::Err> {
+        match loader::parse_specification(content, None) {
+            Ok(document) => Self::from_value(document),
+            Err(e) => Err(OverlayError::new("", e.to_string())),
+        }
+    }
+}
+
+impl Action {
+    fn apply(&self, specification: &mut Value) -> Result<(), String> {
+        let mut locations = self.selector.select(specification);
+        if locations.is_empty() {
+            return Err(format!("Target '{}' matched nothing", self.target));
+        }
+        if self.remove {
+            if locations.iter().any(Vec::is_empty) {
+                return Err(format!(
+                    "Target '{}' selects the document, which cannot be removed",
+                    self.target
+                ));
+            }
+            // Later array items first, so that the indices of the others stay valid.
+            locations.sort();
+            locations.dedup();
+            for location in locations.iter().rev() {
+                remove(specification, location);
+            }
+            return Ok(());
+        }
+        let update = match &self.update {
+            Some(update) => update,
+            None => return Ok(()),
+        };
+        for location in &locations {
+            if let Some(target) = specification.pointer_mut(&selector::pointer(location)) {
+                match target {
+                    Value::Array(items) => items.push(update.clone()),
+                    target => merge(target, update),
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn remove(specification: &mut Value, location: &Location) {
+    let (last, parent) = match location.split_last() {
+        Some(split) => split,
+        None => return,
+    };
+    match (specification.pointer_mut(&selector::pointer(parent)), last) {
+        (Some(Value::Object(map)), Step::Key(key)) => {
+            map.remove(key);
+        }
+        (Some(Value::Array(items)), Step::Index(index)) if *index < items.len() => {
+            items.remove(*index);
+        }
+        _ => {}
+    }
+}
+
+/// Merges an update into a node: the members of an object update are merged into an object
+/// node, any other update replaces the node.
+fn merge(target: &mut Value, update: &Value) {
+    match (target, update) {
+        (Value::Object(target), Value::Object(update)) => merge_members(target, update),
+        (target, update) => *target = update.clone(),
+    }
+}
+
+fn merge_members(target: &mut Map<String, Value>, update: &Map<String, Value>) {
+    for (key, value) in update {
+        match target.get_mut(key) {
+            Some(existing) => merge(existing, value),
+            None => {
+                target.insert(key.clone(), value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn specification() -> Value {
+        json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Users", "version": "1.0.0" },
+            "paths": {
+                "/users": {
+                    "get": {
+                        "tags": ["users"],
+                        "parameters": [{ "name": "limit", "in": "query", "schema": { "type": "integer" } }]
+                    },
+                    "delete": { "tags": ["admin"], "x-internal": true }
+                },
+                "/admin": {
+                    "post": { "tags": ["admin"], "x-internal": true }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Name": { "type": "string", "maxLength": 256 }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_apply() {
+        let overlay: Overlay = r#"
+overlay: 1.0.0
+info:
+  title: Production
+  version: 1.0.0
+actions:
+  - target: $.paths.*[?@.x-internal == true]
+    description: Hide internal operations
+    remove: true
+  - target: $.components.schemas.Name
+    update:
+      maxLength: 64
+      pattern: ^[a-z]+$
+  - target: $..parameters[?@.name == 'limit'].schema
+    update:
+      maximum: 100
+  - target: $.paths['/users'].get.parameters
+    update:
+      name: offset
+      in: query
+      schema:
+        type: integer
+  - target: $.info.title
+    update: Users (production)
+"#
+        .parse()
+        .unwrap();
+        let mut specification = specification();
+        overlay.apply(&mut specification).unwrap();
+        assert_eq!(specification["info"]["title"], "Users (production)");
+        assert!(specification["paths"]["/users"].get("delete").is_none());
+        assert_eq!(specification["paths"]["/admin"], json!({}));
+        assert_eq!(
+            specification["components"]["schemas"]["Name"],
+            json!({ "type": "string", "maxLength": 64, "pattern": "^[a-z]+$" })
+        );
+        assert_eq!(
+            specification["paths"]["/users"]["get"]["parameters"],
+            json!([
+                { "name": "limit", "in": "query", "schema": { "type": "integer", "maximum": 100 } },
+                { "name": "offset", "in": "query", "schema": { "type": "integer" } }
+            ])
+        );
+    }
+
+    #[test]
+    fn test_unmatched_targets() {
+        let overlay = Overlay::from_value(json!({
+            "overlay": "1.0.0",
+            "info": { "title": "Broken", "version": "1.0.0" },
+            "actions": [
+                { "target": "$.paths['/missing']", "remove": true },
+                { "target": "$.components.schemas.Name", "update": { "maxLength": 64 } },
+                { "target": "$.paths.*[?@.x-hidden]", "update": { "deprecated": true } },
+                { "target": "$", "remove": true }
+            ]
+        }))
+        .unwrap();
+        let mut specification = specification();
+        let errors = overlay.apply(&mut specification).unwrap_err();
+        let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "#/actions/0: Target '$.paths['/missing']' matched nothing",
+                "#/actions/2: Target '$.paths.*[?@.x-hidden]' matched nothing",
+                "#/actions/3: Target '$' selects the document, which cannot be removed",
+            ]
+        );
+        // The other actions are still applied.
+        assert_eq!(
+            specification["components"]["schemas"]["Name"]["maxLength"],
+            64
+        );
+    }
+
+    #[test]
+    fn test_invalid_overlays() {
+        let error = |document: Value| Overlay::from_value(document).unwrap_err().to_string();
+        assert_eq!(
+            error(json!({ "overlay": "2.0.0", "actions": [] })),
+            "#/overlay: Unsupported overlay version '2.0.0'"
+        );
+        assert_eq!(
+            error(json!({ "overlay": "1.0.0", "actions": [] })),
+            "#: 'actions' must be a non-empty array"
+        );
+        assert_eq!(
+            error(json!({ "overlay": "1.0.0", "actions": [{ "target": "$.paths" }] })),
+            "#/actions/0: Action has neither 'update' nor 'remove: true'"
+        );
+        assert_eq!(
+            error(
+                json!({ "overlay": "1.0.0", "actions": [{ "target": "paths", "remove": true }] })
+            ),
+            "#/actions/0/target: Invalid JSONPath 'paths': Expected '$' at offset 0"
+        );
+    }
+}
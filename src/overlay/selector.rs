@@ -0,0 +1,722 @@
+//! The JSONPath (RFC 9535) expressions that overlay actions select their targets with.
+//!
+//! Supported are the root `$`, child segments (`.name`, `['name']`, `[0]`, `[-1]`, `.*`, `[*]`),
+//! descendant segments (`..name`, `..*`, `..[...]`), selector lists (`['get', 'post']`) and
+//! filters (`[?@.deprecated == true]`) with existence tests, comparisons (`==`, `!=`, `<`, `<=`,
+//! `>`, `>=`) against literals or other `@` paths, `!`, `&&`, `||` and parentheses. Member names
+//! written in shorthand may contain `-`, so that `@.x-internal` selects an extension. Function
+//! extensions such as `length()` are not supported.
+use crate::types::json_path::JsonPath;
+use serde_json::Value;
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+
+const ROOT_IDENTIFIER: char = '$';
+const CURRENT_NODE_IDENTIFIER: char = '@';
+
+/// A step from a node to one of its children.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Step {
+    Key(String),
+    Index(usize),
+}
+
+impl PartialOrd for Step {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Step {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Step::Key(a), Step::Key(b)) => a.cmp(b),
+            (Step::Index(a), Step::Index(b)) => a.cmp(b),
+            (Step::Key(_), Step::Index(_)) => Ordering::Less,
+            (Step::Index(_), Step::Key(_)) => Ordering::Greater,
+        }
+    }
+}
+
+/// The location of a selected node, as the steps from the root to it.
+pub(crate) type Location = Vec<Step>;
+
+/// Formats a location as a JSON Pointer.
+pub(crate) fn pointer(location: &[Step]) -> String {
+    let mut path = JsonPath::new();
+    for step in location {
+        match step {
+            Step::Key(key) => path.add(key),
+            Step::Index(index) => path.add(index.to_string()),
+        };
+    }
+    match location.is_empty() {
+        true => String::new(),
+        false => format!("/{}", path.format_path()),
+    }
+}
+
+/// A JSONPath expression that does not parse.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SelectorError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Display for SelectorError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+/// A parsed JSONPath query.
+#[derive(Debug, Clone)]
+pub(crate) struct Selector {
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Child(Vec<Selection>),
+    Descendant(Vec<Selection>),
+}
+
+#[derive(Debug, Clone)]
+enum Selection {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Filter(Expression),
+}
+
+#[derive(Debug, Clone)]
+enum Expression {
+    Or(Box<Expression>, Box<Expression>),
+    And(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Exists(Vec<Selection>),
+    Compare(Operand, Comparison, Operand),
+}
+
+#[derive(Debug, Clone)]
+enum Operand {
+    /// A path relative to the current node, of names and indices.
+    Path(Vec<Selection>),
+    Literal(Value),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl Selector {
+    /// Parses a JSONPath query.
+    ///
+    /// # Arguments
+    /// * `query` - The query, e.g. `$.paths['/pets'].*[?@.x-internal == true]`
+    ///
+    /// # Returns
+    /// * `Ok(Selector)` - The parsed query
+    /// * `Err(SelectorError)` - If the query is not valid, or uses what is not supported.
+    pub(crate) fn parse(query: &str) -> Result<Self, SelectorError> {
+        let mut parser = Parser {
+            chars: query.chars().collect(),
+            position: 0,
+        };
+        parser.expect(ROOT_IDENTIFIER)?;
+        let mut segments = vec![];
+        loop {
+            parser.skip_whitespace();
+            match parser.peek() {
+                None => break,
+                Some(_) => segments.push(parser.segment()?),
+            }
+        }
+        Ok(Selector { segments })
+    }
+
+    /// Selects the nodes of a document.
+    ///
+    /// # Arguments
+    /// * `document` - The document the query is run against
+    ///
+    /// # Returns
+    /// The locations of the selected nodes, in document order.
+    pub(crate) fn select(&self, document: &Value) -> Vec<Location> {
+        let mut nodes = vec![(vec![], document)];
+        for segment in &self.segments {
+            let mut selected = vec![];
+            for (location, value) in nodes {
+                match segment {
+                    Segment::Child(selections) => {
+                        select_children(&location, value, selections, &mut selected);
+                    }
+                    Segment::Descendant(selections) => {
+                        select_descendants(location, value, selections, &mut selected);
+                    }
+                }
+            }
+            nodes = selected;
+        }
+        nodes.into_iter().map(|(location, _)| location).collect()
+    }
+}
+
+fn select_children<'v>(
+    location: &Location,
+    value: &'v Value,
+    selections: &[Selection],
+    selected: &mut Vec<(Location, &'v Value)>,
+) {
+    let child = |step: Step| {
+        let mut child = location.clone();
+        child.push(step);
+        child
+    };
+    for selection in selections {
+        match (selection, value) {
+            (Selection::Name(name), Value::Object(map)) => {
+                if let Some(member) = map.get(name) {
+                    selected.push((child(Step::Key(name.clone())), member));
+                }
+            }
+            (Selection::Index(index), Value::Array(items)) => {
+                if let Some(index) = resolve_index(*index, items.len()) {
+                    selected.push((child(Step::Index(index)), &items[index]));
+                }
+            }
+            (Selection::Wildcard, Value::Object(map)) => {
+                for (key, member) in map {
+                    selected.push((child(Step::Key(key.clone())), member));
+                }
+            }
+            (Selection::Wildcard, Value::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    selected.push((child(Step::Index(index)), item));
+                }
+            }
+            (Selection::Filter(expression), Value::Object(map)) => {
+                for (key, member) in map {
+                    if expression.matches(member) {
+                        selected.push((child(Step::Key(key.clone())), member));
+                    }
+                }
+            }
+            (Selection::Filter(expression), Value::Array(items)) => {
+                for (index, item) in items.iter().enumerate() {
+                    if expression.matches(item) {
+                        selected.push((child(Step::Index(index)), item));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Applies the selections to a node and all of its descendants, in document order.
+fn select_descendants<'v>(
+    location: Location,
+    value: &'v Value,
+    selections: &[Selection],
+    selected: &mut Vec<(Location, &'v Value)>,
+) {
+    select_children(&location, value, selections, selected);
+    let mut children = vec![];
+    select_children(&location, value, &[Selection::Wildcard], &mut children);
+    for (location, child) in children {
+        select_descendants(location, child, selections, selected);
+    }
+}
+
+fn resolve_index(index: i64, length: usize) -> Option<usize> {
+    let index = match index < 0 {
+        true => length as i64 + index,
+        false => index,
+    };
+    match index >= 0 && (index as usize) < length {
+        true => Some(index as usize),
+        false => None,
+    }
+}
+
+impl Expression {
+    fn matches(&self, node: &Value) -> bool {
+        match self {
+            Expression::Or(left, right) => left.matches(node) || right.matches(node),
+            Expression::And(left, right) => left.matches(node) && right.matches(node),
+            Expression::Not(expression) => !expression.matches(node),
+            Expression::Exists(path) => resolve_path(node, path).is_some(),
+            Expression::Compare(left, comparison, right) => {
+                let left = left.resolve(node);
+                let right = right.resolve(node);
+                comparison.holds(left, right)
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn resolve<'v>(&'v self, node: &'v Value) -> Option<&'v Value> {
+        match self {
+            Operand::Path(path) => resolve_path(node, path),
+            Operand::Literal(value) => Some(value),
+        }
+    }
+}
+
+fn resolve_path<'v>(node: &'v Value, path: &[Selection]) -> Option<&'v Value> {
+    let mut current = node;
+    for selection in path {
+        current = match (selection, current) {
+            (Selection::Name(name), Value::Object(map)) => map.get(name)?,
+            (Selection::Index(index), Value::Array(items)) => {
+                &items[resolve_index(*index, items.len())?]
+            }
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+impl Comparison {
+    /// Compares two values. A path that selects nothing only equals another that selects nothing.
+    fn holds(self, left: Option<&Value>, right: Option<&Value>) -> bool {
+        let ordering = match (left, right) {
+            (Some(Value::Number(a)), Some(Value::Number(b))) => a.as_f64().partial_cmp(&b.as_f64()),
+            (Some(Value::String(a)), Some(Value::String(b))) => Some(a.cmp(b)),
+            _ => None,
+        };
+        let equal = match ordering {
+            Some(ordering) => ordering == Ordering::Equal,
+            None => left == right,
+        };
+        match self {
+            Comparison::Equal => equal,
+            Comparison::NotEqual => !equal,
+            Comparison::Less => ordering == Some(Ordering::Less),
+            Comparison::LessOrEqual => equal || ordering == Some(Ordering::Less),
+            Comparison::Greater => ordering == Some(Ordering::Greater),
+            Comparison::GreaterOrEqual => equal || ordering == Some(Ordering::Greater),
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+}
+
+impl Parser {
+    fn segment(&mut self) -> Result<Segment, SelectorError> {
+        match (self.peek(), self.peek_at(1)) {
+            (Some('.'), Some('.')) => {
+                self.position += 2;
+                match self.peek() {
+                    Some('[') => Ok(Segment::Descendant(self.bracket()?)),
+                    _ => Ok(Segment::Descendant(vec![self.shorthand()?])),
+                }
+            }
+            (Some('.'), _) => {
+                self.position += 1;
+                Ok(Segment::Child(vec![self.shorthand()?]))
+            }
+            (Some('['), _) => Ok(Segment::Child(self.bracket()?)),
+            _ => Err(self.error("Expected '.', '..' or '['")),
+        }
+    }
+
+    /// A wildcard or member name after `.` or `..`.
+    fn shorthand(&mut self) -> Result<Selection, SelectorError> {
+        if self.eat('*') {
+            return Ok(Selection::Wildcard);
+        }
+        let name = self.name();
+        match name.is_empty() {
+            true => Err(self.error("Expected a member name or '*'")),
+            false => Ok(Selection::Name(name)),
+        }
+    }
+
+    fn name(&mut self) -> String {
+        let start = self.position;
+        while let Some(c) = self.peek() {
+            let first = self.position == start;
+            match c.is_alphabetic()
+                || c == '_'
+                || !c.is_ascii()
+                || (!first && (c.is_ascii_digit() || c == '-'))
+            {
+                true => self.position += 1,
+                false => break,
+            }
+        }
+        self.chars[start..self.position].iter().collect()
+    }
+
+    /// A bracketed, comma separated list of selections.
+    fn bracket(&mut self) -> Result<Vec<Selection>, SelectorError> {
+        self.expect('[')?;
+        let mut selections = vec![];
+        loop {
+            self.skip_whitespace();
+            let selection = match self.peek() {
+                Some('*') => {
+                    self.position += 1;
+                    Selection::Wildcard
+                }
+                Some('?') => {
+                    self.position += 1;
+                    Selection::Filter(self.or()?)
+                }
+                Some('\'' | '"') => Selection::Name(self.string()?),
+                Some(c) if c == '-' || c.is_ascii_digit() => Selection::Index(self.integer()?),
+                _ => return Err(self.error("Expected a name, index, '*' or filter")),
+            };
+            selections.push(selection);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.position += 1,
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(selections);
+                }
+                _ => return Err(self.error("Expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression, SelectorError> {
+        let mut expression = self.and()?;
+        while self.eat_operator("||") {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression, SelectorError> {
+        let mut expression = self.unary()?;
+        while self.eat_operator("&&") {
+            expression = Expression::And(Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression, SelectorError> {
+        self.skip_whitespace();
+        if self.peek() == Some('!') && self.peek_at(1) != Some('=') {
+            self.position += 1;
+            return Ok(Expression::Not(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let expression = self.or()?;
+            self.skip_whitespace();
+            self.expect(')')?;
+            return Ok(expression);
+        }
+        let start = self.position;
+        let left = self.operand()?;
+        let comparison = match self.comparison() {
+            Some(comparison) => comparison,
+            None => {
+                return match left {
+                    Operand::Path(path) => Ok(Expression::Exists(path)),
+                    Operand::Literal(_) => Err(SelectorError {
+                        offset: start,
+                        message: "Expected a comparison".to_string(),
+                    }),
+                };
+            }
+        };
+        let right = self.operand()?;
+        Ok(Expression::Compare(left, comparison, right))
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        let operators = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        operators
+            .into_iter()
+            .find(|(operator, _)| self.eat_operator(operator))
+            .map(|(_, comparison)| comparison)
+    }
+
+    fn operand(&mut self) -> Result<Operand, SelectorError> {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(CURRENT_NODE_IDENTIFIER) => {
+                self.position += 1;
+                let mut path = vec![];
+                loop {
+                    match (self.peek(), self.peek_at(1)) {
+                        (Some('.'), Some('.')) => {
+                            return Err(
+                                self.error("Descendant segments are not supported in filters")
+                            );
+                        }
+                        (Some('.'), _) => {
+                            self.position += 1;
+                            match self.shorthand()? {
+                                Selection::Wildcard => {
+                                    return Err(
+                                        self.error("Wildcards are not supported in filters")
+                                    );
+                                }
+                                selection => path.push(selection),
+                            }
+                        }
+                        (Some('['), _) => {
+                            let start = self.position;
+                            for selection in self.bracket()? {
+                                match selection {
+                                    Selection::Name(_) | Selection::Index(_) => {
+                                        path.push(selection)
+                                    }
+                                    _ => {
+                                        return Err(SelectorError {
+                                            offset: start,
+                                            message: "Only names and indices are supported in filter paths".to_string(),
+                                        });
+                                    }
+                                }
+                            }
+                        }
+                        _ => return Ok(Operand::Path(path)),
+                    }
+                }
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(Value::String(self.string()?))),
+            Some(c) if c == '-' || c.is_ascii_digit() => Ok(Operand::Literal(self.number()?)),
+            _ => {
+                for (keyword, value) in [
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                    ("null", Value::Null),
+                ] {
+                    if self.eat_keyword(keyword) {
+                        return Ok(Operand::Literal(value));
+                    }
+                }
+                Err(self.error("Expected '@' or a literal"))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, SelectorError> {
+        let quote = match self.peek() {
+            Some(quote @ ('\'' | '"')) => quote,
+            _ => return Err(self.error("Expected a string")),
+        };
+        self.position += 1;
+        let mut string = String::new();
+        loop {
+            match self.peek() {
+                None => return Err(self.error("Unterminated string")),
+                Some(c) if c == quote => {
+                    self.position += 1;
+                    return Ok(string);
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    let escaped = match self.peek() {
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c @ ('/' | '\\' | '\'' | '"')) => c,
+                        _ => return Err(self.error("Unsupported escape sequence")),
+                    };
+                    string.push(escaped);
+                    self.position += 1;
+                }
+                Some(c) => {
+                    string.push(c);
+                    self.position += 1;
+                }
+            }
+        }
+    }
+
+    fn integer(&mut self) -> Result<i64, SelectorError> {
+        let start = self.position;
+        self.eat('-');
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+        let digits: String = self.chars[start..self.position].iter().collect();
+        match digits.parse() {
+            Ok(integer) => Ok(integer),
+            Err(_) => Err(SelectorError {
+                offset: start,
+                message: format!("Invalid index '{}'", digits),
+            }),
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, SelectorError> {
+        let start = self.position;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+        {
+            self.position += 1;
+        }
+        let digits: String = self.chars[start..self.position].iter().collect();
+        match serde_json::from_str::<serde_json::Number>(&digits) {
+            Ok(number) => Ok(Value::Number(number)),
+            Err(_) => Err(SelectorError {
+                offset: start,
+                message: format!("Invalid number '{}'", digits),
+            }),
+        }
+    }
+
+    fn eat_operator(&mut self, operator: &str) -> bool {
+        self.skip_whitespace();
+        let matches = operator
+            .chars()
+            .enumerate()
+            .all(|(offset, c)| self.peek_at(offset) == Some(c));
+        if matches {
+            self.position += operator.len();
+        }
+        matches
+    }
+
+    /// Eats a keyword literal, unless it is the start of a longer name.
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let length = keyword.chars().count();
+        let next = self.peek_at(length);
+        if next.is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            return false;
+        }
+        self.eat_operator(keyword)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        match self.peek() == Some(c) {
+            true => {
+                self.position += 1;
+                true
+            }
+            false => false,
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SelectorError> {
+        match self.eat(c) {
+            true => Ok(()),
+            false => Err(self.error(&format!("Expected '{}'", c))),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.position + offset).copied()
+    }
+
+    fn error(&self, message: &str) -> SelectorError {
+        SelectorError {
+            offset: self.position,
+            message: message.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn select(query: &str, document: &Value) -> Vec<String> {
+        Selector::parse(query)
+            .unwrap()
+            .select(document)
+            .iter()
+            .map(|location| pointer(location))
+            .collect()
+    }
+
+    #[test]
+    fn test_select() {
+        let document = json!({
+            "paths": {
+                "/pets": {
+                    "get": { "x-internal": true, "tags": ["pets"] },
+                    "post": { "x-internal": false, "tags": ["pets", "admin"] }
+                },
+                "/users": {
+                    "get": { "tags": ["users"], "parameters": [{ "name": "limit" }, { "name": "offset" }] }
+                }
+            }
+        });
+        assert_eq!(select("$", &document), vec![""]);
+        assert_eq!(
+            select("$.paths['/pets'].get", &document),
+            vec!["/paths/~1pets/get"]
+        );
+        assert_eq!(
+            select("$.paths.*[?@.x-internal == true]", &document),
+            vec!["/paths/~1pets/get"]
+        );
+        assert_eq!(
+            select("$.paths.*[?!@.x-internal]", &document),
+            vec!["/paths/~1users/get"]
+        );
+        assert_eq!(
+            select(
+                "$.paths.*[?@.tags[1] == 'admin' || @.tags[0] == \"users\"]",
+                &document
+            ),
+            vec!["/paths/~1pets/post", "/paths/~1users/get"]
+        );
+        assert_eq!(
+            select("$..parameters[-1].name", &document),
+            vec!["/paths/~1users/get/parameters/1/name"]
+        );
+        assert_eq!(
+            select("$.paths['/pets']['get', 'post'].tags[0]", &document),
+            vec!["/paths/~1pets/get/tags/0", "/paths/~1pets/post/tags/0"]
+        );
+        assert!(select("$.paths.missing", &document).is_empty());
+    }
+
+    #[test]
+    fn test_invalid_queries() {
+        for query in [
+            "paths",
+            "$.",
+            "$[",
+            "$['open",
+            "$[?@.a ==]",
+            "$[?@..a]",
+            "$.a b",
+        ] {
+            assert!(Selector::parse(query).is_err(), "{}", query);
+        }
+        let error = Selector::parse("$.paths[?length(@) > 1]").unwrap_err();
+        assert_eq!(error.to_string(), "Expected '@' or a literal at offset 9");
+    }
+}
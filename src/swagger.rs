@@ -0,0 +1,903 @@
+//! Conversion of Swagger 2.0 documents into OpenAPI 3.0.
+//!
+//! `convert` turns a `swagger: "2.0"` document into an equivalent OpenAPI 3.0.3 document, which
+//! `OpenApiPayloadValidatorBuilder` loads like any other:
+//!
+//! - `host`, `basePath` and `schemes` become `servers`
+//! - `definitions`, `parameters`, `responses` and `securityDefinitions` move into `components`,
+//!   and the `$ref`s to them are rewritten
+//! - `body` and `formData` parameters become a `requestBody`, with a media type for each of
+//!   the `consumes` types. Responses get one for each of the `produces` types
+//! - other parameters and response headers get a `schema` from their type fields, and a
+//!   `style` from their `collectionFormat`
+//! - `x-nullable`, string `discriminator`s and `type: file` are rewritten into their 3.0 form
+//!
+//! # Example
+//!
+//! ```rust
+//! use oasert::swagger;
+//! use oasert::validator::builder::OpenApiPayloadValidatorBuilder;
+//! use serde_json::json;
+//!
+//! let document = json!({
+//!     "swagger": "2.0",
+//!     "info": { "title": "Pets", "version": "1.0.0" },
+//!     "host": "pets.example.com",
+//!     "basePath": "/v1",
+//!     "paths": {
+//!         "/pets": {
+//!             "get": { "responses": { "200": { "description": "Success" } } }
+//!         }
+//!     }
+//! });
+//! let specification = swagger::convert(&document).unwrap();
+//! assert_eq!(specification["servers"][0]["url"], "https://pets.example.com/v1");
+//!
+//! let validator = OpenApiPayloadValidatorBuilder::new()
+//!     .load_from_value(specification)
+//!     .build()
+//!     .unwrap();
+//! assert!(validator.find_operation("/pets", "GET").is_ok());
+//! ```
+use crate::{
+    COMPONENTS_FIELD, CONTENT_FIELD, IN_FIELD, NAME_FIELD, OPENAPI_FIELD, PARAMETERS_FIELD,
+    PATHS_FIELD, REF_FIELD, REQUEST_BODY_FIELD, REQUIRED_FIELD, RESPONSES_FIELD, SCHEMA_FIELD,
+    SECURITY_SCHEMES_FIELD, TYPE_FIELD,
+};
+use serde_json::{json, Map, Value};
+use std::fmt::{Display, Formatter};
+
+const SWAGGER_FIELD: &str = "swagger";
+const SWAGGER_VERSION: &str = "2.0";
+const OPENAPI_VERSION: &str = "3.0.3";
+const HOST_FIELD: &str = "host";
+const BASE_PATH_FIELD: &str = "basePath";
+const SCHEMES_FIELD: &str = "schemes";
+const CONSUMES_FIELD: &str = "consumes";
+const PRODUCES_FIELD: &str = "produces";
+const DEFINITIONS_FIELD: &str = "definitions";
+const SECURITY_DEFINITIONS_FIELD: &str = "securityDefinitions";
+const HEADERS_FIELD: &str = "headers";
+const EXAMPLES_FIELD: &str = "examples";
+const ITEMS_FIELD: &str = "items";
+const PROPERTIES_FIELD: &str = "properties";
+const DESCRIPTION_FIELD: &str = "description";
+const FORMAT_FIELD: &str = "format";
+const COLLECTION_FORMAT_FIELD: &str = "collectionFormat";
+const DISCRIMINATOR_FIELD: &str = "discriminator";
+const FLOW_FIELD: &str = "flow";
+const SCOPES_FIELD: &str = "scopes";
+const BODY_LOCATION: &str = "body";
+const FORM_DATA_LOCATION: &str = "formData";
+const QUERY_LOCATION: &str = "query";
+const FILE_TYPE: &str = "file";
+const JSON_MEDIA_TYPE: &str = "application/json";
+const URL_ENCODED_MEDIA_TYPE: &str = "application/x-www-form-urlencoded";
+const MULTIPART_MEDIA_TYPE: &str = "multipart/form-data";
+const EXTENSION_PREFIX: &str = "x-";
+
+/// The methods of a Swagger 2.0 Path Item Object. Unlike `document::OPERATION_METHODS`, which
+/// lists those of OpenAPI 3.x, it has no `trace`.
+const OPERATION_METHODS: [&str; 7] = ["get", "put", "post", "delete", "options", "head", "patch"];
+
+/// Fields of the root document, operations and path items that mean the same in both versions.
+const KEPT_FIELDS: [&str; 10] = [
+    "info",
+    "tags",
+    "externalDocs",
+    "security",
+    "summary",
+    "description",
+    "operationId",
+    "deprecated",
+    "$ref",
+    "callbacks",
+];
+
+/// Fields of a non-body parameter that are kept on the parameter, the others describe its schema.
+const PARAMETER_FIELDS: [&str; 5] = [
+    NAME_FIELD,
+    IN_FIELD,
+    DESCRIPTION_FIELD,
+    REQUIRED_FIELD,
+    "allowEmptyValue",
+];
+
+/// Fields of a non-body parameter, header or `items` that describe its schema.
+const SCHEMA_FIELDS: [&str; 16] = [
+    TYPE_FIELD,
+    FORMAT_FIELD,
+    ITEMS_FIELD,
+    "default",
+    "maximum",
+    "exclusiveMaximum",
+    "minimum",
+    "exclusiveMinimum",
+    "maxLength",
+    "minLength",
+    "pattern",
+    "maxItems",
+    "minItems",
+    "uniqueItems",
+    "enum",
+    "multipleOf",
+];
+
+/// Where the components of a Swagger 2.0 document moved to.
+const REF_PREFIXES: [(&str, &str); 3] = [
+    ("#/definitions/", "#/components/schemas/"),
+    ("#/parameters/", "#/components/parameters/"),
+    ("#/responses/", "#/components/responses/"),
+];
+
+/// The document cannot be converted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversionError(pub String);
+
+impl Display for ConversionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Conversion Failure: {}", self.0)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+/// Whether a document is a Swagger 2.0 document.
+pub fn is_swagger(document: &Value) -> bool {
+    document.get(SWAGGER_FIELD).and_then(Value::as_str) == Some(SWAGGER_VERSION)
+}
+
+/// Converts a Swagger 2.0 document into an OpenAPI 3.0.3 document.
+///
+/// # Arguments
+/// * `document` - The Swagger 2.0 document
+///
+/// # Returns
+/// * `Ok(Value)` - The OpenAPI 3.0.3 document
+/// * `Err(ConversionError)` - If the document is not a Swagger 2.0 document.
+pub fn convert(document: &Value) -> Result<Value, ConversionError> {
+    let root = match document {
+        Value::Object(root) if is_swagger(document) => root,
+        _ => {
+            return Err(ConversionError(format!(
+                "Not a Swagger {} document, '{}' is {}",
+                SWAGGER_VERSION,
+                SWAGGER_FIELD,
+                document.get(SWAGGER_FIELD).unwrap_or(&Value::Null)
+            )));
+        }
+    };
+    let converter = Converter {
+        document,
+        consumes: media_types(document.get(CONSUMES_FIELD)),
+        produces: media_types(document.get(PRODUCES_FIELD)),
+    };
+
+    let mut converted = Map::new();
+    converted.insert(OPENAPI_FIELD.to_string(), json!(OPENAPI_VERSION));
+    for (key, value) in root {
+        if KEPT_FIELDS.contains(&key.as_str()) || key.starts_with(EXTENSION_PREFIX) {
+            converted.insert(key.clone(), value.clone());
+        }
+    }
+    converted.insert("servers".to_string(), converter.servers());
+
+    let mut paths = Map::new();
+    if let Some(Value::Object(path_items)) = root.get(PATHS_FIELD) {
+        for (path, path_item) in path_items {
+            let path_item = match path.starts_with(EXTENSION_PREFIX) {
+                true => path_item.clone(),
+                false => converter.path_item(path_item),
+            };
+            paths.insert(path.clone(), path_item);
+        }
+    }
+    converted.insert(PATHS_FIELD.to_string(), Value::Object(paths));
+
+    let components = converter.components();
+    if !components.is_empty() {
+        converted.insert(COMPONENTS_FIELD.to_string(), Value::Object(components));
+    }
+
+    let mut converted = Value::Object(converted);
+    rewrite_refs(&mut converted);
+    Ok(converted)
+}
+
+struct Converter<'d> {
+    document: &'d Value,
+    consumes: Vec<String>,
+    produces: Vec<String>,
+}
+
+impl Converter<'_> {
+    /// One server per scheme, from `host` and `basePath`. Without a `host`, the server URL is
+    /// relative to where the document is served from.
+    fn servers(&self) -> Value {
+        let base_path = self
+            .document
+            .get(BASE_PATH_FIELD)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .trim_end_matches('/');
+        let host = match self.document.get(HOST_FIELD).and_then(Value::as_str) {
+            Some(host) => host,
+            None => {
+                let url = match base_path.is_empty() {
+                    true => "/",
+                    false => base_path,
+                };
+                return json!([{ "url": url }]);
+            }
+        };
+        let schemes = match self.document.get(SCHEMES_FIELD).and_then(Value::as_array) {
+            Some(schemes) if !schemes.is_empty() => {
+                schemes.iter().filter_map(Value::as_str).collect()
+            }
+            _ => vec!["https"],
+        };
+        Value::Array(
+            schemes
+                .into_iter()
+                .map(|scheme| json!({ "url": format!("{}://{}{}", scheme, host, base_path) }))
+                .collect(),
+        )
+    }
+
+    fn components(&self) -> Map<String, Value> {
+        let mut components = Map::new();
+        if let Some(Value::Object(definitions)) = self.document.get(DEFINITIONS_FIELD) {
+            let schemas = definitions
+                .iter()
+                .map(|(name, schema)| (name.clone(), convert_schema(schema)))
+                .collect();
+            components.insert("schemas".to_string(), Value::Object(schemas));
+        }
+
+        // Body parameters become request bodies, form parameters are inlined where used.
+        if let Some(Value::Object(parameters)) = self.document.get(PARAMETERS_FIELD) {
+            let mut converted = Map::new();
+            let mut request_bodies = Map::new();
+            for (name, parameter) in parameters {
+                match location(parameter) {
+                    BODY_LOCATION => {
+                        let request_body = self.body(parameter, &self.consumes);
+                        request_bodies.insert(name.clone(), request_body);
+                    }
+                    FORM_DATA_LOCATION => {}
+                    _ => {
+                        converted.insert(name.clone(), convert_parameter(parameter));
+                    }
+                }
+            }
+            if !converted.is_empty() {
+                components.insert(PARAMETERS_FIELD.to_string(), Value::Object(converted));
+            }
+            if !request_bodies.is_empty() {
+                components.insert("requestBodies".to_string(), Value::Object(request_bodies));
+            }
+        }
+
+        if let Some(Value::Object(responses)) = self.document.get(RESPONSES_FIELD) {
+            let responses = responses
+                .iter()
+                .map(|(name, response)| (name.clone(), self.response(response, &self.produces)))
+                .collect();
+            components.insert(RESPONSES_FIELD.to_string(), Value::Object(responses));
+        }
+
+        if let Some(Value::Object(schemes)) = self.document.get(SECURITY_DEFINITIONS_FIELD) {
+            let schemes = schemes
+                .iter()
+                .map(|(name, scheme)| (name.clone(), convert_security_scheme(scheme)))
+                .collect();
+            components.insert(SECURITY_SCHEMES_FIELD.to_string(), Value::Object(schemes));
+        }
+        components
+    }
+
+    fn path_item(&self, path_item: &Value) -> Value {
+        let path_item = match path_item {
+            Value::Object(path_item) => path_item,
+            _ => return path_item.clone(),
+        };
+        // Body and form parameters of the path item go into the request body of its operations.
+        let shared = self.parameters(path_item.get(PARAMETERS_FIELD));
+        let mut converted = Map::new();
+        for (key, value) in path_item {
+            match key.as_str() {
+                PARAMETERS_FIELD if !shared.parameters.is_empty() => {
+                    converted.insert(key.clone(), Value::Array(shared.parameters.clone()));
+                }
+                PARAMETERS_FIELD => {}
+                method if OPERATION_METHODS.contains(&method) => {
+                    converted.insert(key.clone(), self.operation(value, &shared));
+                }
+                _ => {
+                    converted.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        Value::Object(converted)
+    }
+
+    fn operation(&self, operation: &Value, shared: &Parameters) -> Value {
+        let operation = match operation {
+            Value::Object(operation) => operation,
+            _ => return operation.clone(),
+        };
+        let consumes = match operation.get(CONSUMES_FIELD) {
+            Some(consumes) => media_types(Some(consumes)),
+            None => self.consumes.clone(),
+        };
+        let produces = match operation.get(PRODUCES_FIELD) {
+            Some(produces) => media_types(Some(produces)),
+            None => self.produces.clone(),
+        };
+
+        let mut converted = Map::new();
+        for (key, value) in operation {
+            if KEPT_FIELDS.contains(&key.as_str()) || key.starts_with(EXTENSION_PREFIX) {
+                converted.insert(key.clone(), value.clone());
+            }
+        }
+
+        let own = self.parameters(operation.get(PARAMETERS_FIELD));
+        if !own.parameters.is_empty() {
+            converted.insert(
+                PARAMETERS_FIELD.to_string(),
+                Value::Array(own.parameters.clone()),
+            );
+        }
+        let body = own.body.as_ref().or(shared.body.as_ref());
+        let mut form = shared.form.clone();
+        for (name, property, required) in &own.form {
+            form.retain(|(shared_name, _, _)| shared_name != name);
+            form.push((name.clone(), property.clone(), *required));
+        }
+        let request_body = match (body, form.is_empty()) {
+            (Some(Body::Parameter(parameter)), _) => Some(self.body(parameter, &consumes)),
+            (Some(Body::Reference(reference)), _) => Some(json!({ REF_FIELD: reference })),
+            (None, false) => Some(form_body(&form, &consumes)),
+            (None, true) => None,
+        };
+        if let Some(request_body) = request_body {
+            converted.insert(REQUEST_BODY_FIELD.to_string(), request_body);
+        }
+
+        if let Some(Value::Object(responses)) = operation.get(RESPONSES_FIELD) {
+            let responses = responses
+                .iter()
+                .map(|(status, response)| (status.clone(), self.response(response, &produces)))
+                .collect();
+            converted.insert(RESPONSES_FIELD.to_string(), Value::Object(responses));
+        }
+        Value::Object(converted)
+    }
+
+    /// Splits parameters into the parameters that stay, the body and the form fields.
+    fn parameters(&self, parameters: Option<&Value>) -> Parameters {
+        let mut split = Parameters::default();
+        let parameters = match parameters.and_then(Value::as_array) {
+            Some(parameters) => parameters,
+            None => return split,
+        };
+        for parameter in parameters {
+            let reference = parameter.get(REF_FIELD).and_then(Value::as_str);
+            let resolved = match reference {
+                Some(reference) => match self.resolve(reference) {
+                    Some(resolved) => resolved,
+                    None => {
+                        split.parameters.push(parameter.clone());
+                        continue;
+                    }
+                },
+                None => parameter,
+            };
+            match (location(resolved), reference) {
+                (BODY_LOCATION, Some(reference)) => {
+                    let name = reference.rsplit('/').next().unwrap_or_default();
+                    split.body = Some(Body::Reference(format!(
+                        "#/{}/requestBodies/{}",
+                        COMPONENTS_FIELD, name
+                    )));
+                }
+                (BODY_LOCATION, None) => split.body = Some(Body::Parameter(resolved.clone())),
+                (FORM_DATA_LOCATION, _) => {
+                    let name = resolved
+                        .get(NAME_FIELD)
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    let mut property = parameter_schema(resolved);
+                    if let Some(description) = resolved.get(DESCRIPTION_FIELD) {
+                        property[DESCRIPTION_FIELD] = description.clone();
+                    }
+                    split
+                        .form
+                        .push((name.to_string(), property, is_required(resolved)));
+                }
+                (_, Some(_)) => split.parameters.push(parameter.clone()),
+                (_, None) => split.parameters.push(convert_parameter(parameter)),
+            }
+        }
+        split
+    }
+
+    /// A request body from a body parameter, with a media type for each of `consumes`.
+    fn body(&self, parameter: &Value, consumes: &[String]) -> Value {
+        let schema = convert_schema(parameter.get(SCHEMA_FIELD).unwrap_or(&json!({})));
+        let mut request_body = Map::new();
+        if let Some(description) = parameter.get(DESCRIPTION_FIELD) {
+            request_body.insert(DESCRIPTION_FIELD.to_string(), description.clone());
+        }
+        request_body.insert(CONTENT_FIELD.to_string(), content(&schema, consumes));
+        if is_required(parameter) {
+            request_body.insert(REQUIRED_FIELD.to_string(), Value::Bool(true));
+        }
+        Value::Object(request_body)
+    }
+
+    /// A response, with a media type for each of `produces` if it has a schema, and headers
+    /// with a schema from their type fields.
+    fn response(&self, response: &Value, produces: &[String]) -> Value {
+        let response = match response {
+            Value::Object(response) if !response.contains_key(REF_FIELD) => response,
+            _ => return response.clone(),
+        };
+        let mut converted = Map::new();
+        for (key, value) in response {
+            match (key.as_str(), value) {
+                (SCHEMA_FIELD | EXAMPLES_FIELD, _) => {}
+                (HEADERS_FIELD, Value::Object(headers)) => {
+                    let headers = headers
+                        .iter()
+                        .map(|(name, header)| (name.clone(), convert_header(header)))
+                        .collect();
+                    converted.insert(key.clone(), Value::Object(headers));
+                }
+                _ => {
+                    converted.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        let examples = response.get(EXAMPLES_FIELD).and_then(Value::as_object);
+        let schema = response.get(SCHEMA_FIELD).map(convert_schema);
+        let mut media_types: Vec<String> = produces.to_vec();
+        if let Some(examples) = examples {
+            media_types.extend(
+                examples
+                    .keys()
+                    .filter(|media_type| !produces.contains(media_type))
+                    .cloned(),
+            );
+        }
+        let mut content = Map::new();
+        for media_type in media_types {
+            let mut media = Map::new();
+            if let Some(schema) = &schema {
+                media.insert(SCHEMA_FIELD.to_string(), schema.clone());
+            }
+            if let Some(example) = examples.and_then(|examples| examples.get(&media_type)) {
+                media.insert("example".to_string(), example.clone());
+            }
+            if !media.is_empty() {
+                content.insert(media_type, Value::Object(media));
+            }
+        }
+        if !content.is_empty() {
+            converted.insert(CONTENT_FIELD.to_string(), Value::Object(content));
+        }
+        Value::Object(converted)
+    }
+
+    /// Resolves a reference to a global parameter.
+    fn resolve(&self, reference: &str) -> Option<&Value> {
+        let name = reference.strip_prefix("#/parameters/")?;
+        self.document.get(PARAMETERS_FIELD)?.get(name)
+    }
+}
+
+/// The parameters of an operation or path item, split up.
+#[derive(Default)]
+struct Parameters {
+    /// The parameters other than `body` and `formData`, converted.
+    parameters: Vec<Value>,
+    body: Option<Body>,
+    /// The `formData` parameters, as properties: name, schema and whether required.
+    form: Vec<(String, Value, bool)>,
+}
+
+enum Body {
+    Parameter(Value),
+    /// A reference to a global body parameter, which is converted into a request body.
+    Reference(String),
+}
+
+/// A request body of form fields. Forms with a file field are `multipart/form-data`.
+fn form_body(form: &[(String, Value, bool)], consumes: &[String]) -> Value {
+    let mut properties = Map::new();
+    let mut required = vec![];
+    let mut has_file = false;
+    for (name, property, is_required) in form {
+        has_file |= property.get(FORMAT_FIELD).and_then(Value::as_str) == Some("binary");
+        properties.insert(name.clone(), property.clone());
+        if *is_required {
+            required.push(Value::String(name.clone()));
+        }
+    }
+    let mut schema = json!({ TYPE_FIELD: "object", PROPERTIES_FIELD: properties });
+    if !required.is_empty() {
+        schema[REQUIRED_FIELD] = Value::Array(required);
+    }
+    let mut media_types: Vec<String> = consumes
+        .iter()
+        .filter(|media_type| {
+            media_type.as_str() == URL_ENCODED_MEDIA_TYPE
+                || media_type.as_str() == MULTIPART_MEDIA_TYPE
+        })
+        .cloned()
+        .collect();
+    if media_types.is_empty() {
+        let media_type = match has_file {
+            true => MULTIPART_MEDIA_TYPE,
+            false => URL_ENCODED_MEDIA_TYPE,
+        };
+        media_types.push(media_type.to_string());
+    }
+    json!({ CONTENT_FIELD: content(&schema, &media_types) })
+}
+
+fn content(schema: &Value, media_types: &[String]) -> Value {
+    Value::Object(
+        media_types
+            .iter()
+            .map(|media_type| (media_type.clone(), json!({ SCHEMA_FIELD: schema })))
+            .collect(),
+    )
+}
+
+/// Converts a query, header or path parameter.
+fn convert_parameter(parameter: &Value) -> Value {
+    let map = match parameter {
+        Value::Object(map) if !map.contains_key(REF_FIELD) => map,
+        _ => return parameter.clone(),
+    };
+    let mut converted = Map::new();
+    for (key, value) in map {
+        if PARAMETER_FIELDS.contains(&key.as_str()) || key.starts_with(EXTENSION_PREFIX) {
+            converted.insert(key.clone(), value.clone());
+        }
+    }
+    converted.insert(SCHEMA_FIELD.to_string(), parameter_schema(parameter));
+    let location = location(parameter);
+    let style = match parameter
+        .get(COLLECTION_FORMAT_FIELD)
+        .and_then(Value::as_str)
+    {
+        Some("multi") => Some(("form", true)),
+        Some("ssv") => Some(("spaceDelimited", false)),
+        Some("pipes") => Some(("pipeDelimited", false)),
+        // Arrays are comma separated unless exploded, which is the default of query parameters.
+        _ if location == QUERY_LOCATION && parameter.get(TYPE_FIELD) == Some(&json!("array")) => {
+            Some(("form", false))
+        }
+        _ => None,
+    };
+    if let Some((style, explode)) = style {
+        converted.insert("style".to_string(), json!(style));
+        converted.insert("explode".to_string(), json!(explode));
+    }
+    Value::Object(converted)
+}
+
+fn convert_header(header: &Value) -> Value {
+    let mut converted = Map::new();
+    if let Some(description) = header.get(DESCRIPTION_FIELD) {
+        converted.insert(DESCRIPTION_FIELD.to_string(), description.clone());
+    }
+    converted.insert(SCHEMA_FIELD.to_string(), parameter_schema(header));
+    Value::Object(converted)
+}
+
+/// The schema described by the type fields of a parameter, header or `items`.
+fn parameter_schema(parameter: &Value) -> Value {
+    let mut schema = Map::new();
+    if let Value::Object(map) = parameter {
+        for (key, value) in map {
+            match key.as_str() {
+                ITEMS_FIELD => {
+                    schema.insert(key.clone(), parameter_schema(value));
+                }
+                key if SCHEMA_FIELDS.contains(&key) => {
+                    schema.insert(key.to_string(), value.clone());
+                }
+                _ => {}
+            }
+        }
+    }
+    convert_schema(&Value::Object(schema))
+}
+
+/// Converts a Schema Object, and its subschemas, into its 3.0 form.
+fn convert_schema(schema: &Value) -> Value {
+    let map = match schema {
+        Value::Object(map) => map,
+        Value::Array(items) => return Value::Array(items.iter().map(convert_schema).collect()),
+        _ => return schema.clone(),
+    };
+    let mut converted = Map::new();
+    for (key, value) in map {
+        match (key.as_str(), value) {
+            ("x-nullable", nullable) => {
+                converted.insert("nullable".to_string(), nullable.clone());
+            }
+            (DISCRIMINATOR_FIELD, Value::String(property_name)) => {
+                converted.insert(key.clone(), json!({ "propertyName": property_name }));
+            }
+            (TYPE_FIELD, Value::String(type_name)) if type_name == FILE_TYPE => {
+                converted.insert(key.clone(), json!("string"));
+                converted.insert(FORMAT_FIELD.to_string(), json!("binary"));
+            }
+            (PROPERTIES_FIELD, Value::Object(properties)) => {
+                let properties = properties
+                    .iter()
+                    .map(|(name, property)| (name.clone(), convert_schema(property)))
+                    .collect();
+                converted.insert(key.clone(), Value::Object(properties));
+            }
+            (ITEMS_FIELD | "additionalProperties" | "allOf" | "anyOf" | "oneOf" | "not", _) => {
+                converted.insert(key.clone(), convert_schema(value));
+            }
+            _ => {
+                converted.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Value::Object(converted)
+}
+
+fn convert_security_scheme(scheme: &Value) -> Value {
+    let field = |name: &str| scheme.get(name).cloned().unwrap_or(Value::Null);
+    let mut converted = match scheme.get(TYPE_FIELD).and_then(Value::as_str) {
+        Some("basic") => json!({ TYPE_FIELD: "http", "scheme": "basic" }),
+        Some("apiKey") => json!({
+            TYPE_FIELD: "apiKey",
+            NAME_FIELD: field(NAME_FIELD),
+            IN_FIELD: field(IN_FIELD),
+        }),
+        Some("oauth2") => {
+            let scopes = scheme
+                .get(SCOPES_FIELD)
+                .cloned()
+                .unwrap_or_else(|| json!({}));
+            let (flow, urls): (&str, &[&str]) = match scheme.get(FLOW_FIELD).and_then(Value::as_str)
+            {
+                Some("implicit") => ("implicit", &["authorizationUrl"]),
+                Some("password") => ("password", &["tokenUrl"]),
+                Some("application") => ("clientCredentials", &["tokenUrl"]),
+                _ => ("authorizationCode", &["authorizationUrl", "tokenUrl"]),
+            };
+            let mut settings = Map::new();
+            for url in urls {
+                settings.insert(url.to_string(), field(url));
+            }
+            settings.insert(SCOPES_FIELD.to_string(), scopes);
+            json!({ TYPE_FIELD: "oauth2", "flows": { flow: settings } })
+        }
+        _ => scheme.clone(),
+    };
+    if let (Value::Object(converted), Value::Object(scheme)) = (&mut converted, scheme) {
+        for (key, value) in scheme {
+            if key == DESCRIPTION_FIELD || key.starts_with(EXTENSION_PREFIX) {
+                converted.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    converted
+}
+
+/// Rewrites the local references to definitions, parameters and responses into references to
+/// the components they moved to.
+fn rewrite_refs(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value) {
+                    (REF_FIELD, Value::String(reference)) => {
+                        let renamed = REF_PREFIXES.iter().find_map(|(from, to)| {
+                            Some(format!("{}{}", to, reference.strip_prefix(from)?))
+                        });
+                        if let Some(renamed) = renamed {
+                            *reference = renamed;
+                        }
+                    }
+                    // Examples are literal values, anything that looks like a reference in them is data.
+                    ("example", _) => {}
+                    (_, value) => rewrite_refs(value),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(rewrite_refs),
+        _ => {}
+    }
+}
+
+fn location(parameter: &Value) -> &str {
+    parameter
+        .get(IN_FIELD)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+}
+
+fn is_required(parameter: &Value) -> bool {
+    parameter.get(REQUIRED_FIELD) == Some(&Value::Bool(true))
+}
+
+/// The media types of `consumes` or `produces`, `application/json` if there are none.
+fn media_types(media_types: Option<&Value>) -> Vec<String> {
+    let media_types: Vec<String> = match media_types {
+        Some(Value::Array(media_types)) => media_types
+            .iter()
+            .filter_map(Value::as_str)
+            .map(String::from)
+            .collect(),
+        _ => vec![],
+    };
+    match media_types.is_empty() {
+        true => vec![JSON_MEDIA_TYPE.to_string()],
+        false => media_types,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::loader;
+    use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+    use http::Request;
+
+    const SPEC: &str = "./test/swagger-v2.0-pets.yaml";
+
+    fn document() -> Value {
+        let content = std::fs::read_to_string(SPEC).unwrap();
+        loader::parse_specification(&content, None).unwrap()
+    }
+
+    #[test]
+    fn test_convert() {
+        let converted = convert(&document()).unwrap();
+        assert_eq!(converted["openapi"], "3.0.3");
+        assert!(converted.get("swagger").is_none());
+        assert_eq!(
+            converted["servers"],
+            json!([
+                { "url": "https://pets.example.com/v1" },
+                { "url": "http://pets.example.com/v1" }
+            ])
+        );
+
+        let pets = &converted["paths"]["/pets"];
+        assert_eq!(
+            pets["get"]["parameters"][0],
+            json!({
+                "name": "tags",
+                "in": "query",
+                "schema": { "type": "array", "items": { "type": "string" } },
+                "style": "form",
+                "explode": true
+            })
+        );
+        assert_eq!(
+            pets["get"]["responses"]["200"]["content"],
+            json!({
+                "application/json": {
+                    "schema": { "type": "array", "items": { "$ref": "#/components/schemas/Pet" } }
+                }
+            })
+        );
+        assert_eq!(
+            pets["get"]["responses"]["200"]["headers"]["X-Total"],
+            json!({ "schema": { "type": "integer" } })
+        );
+        assert_eq!(
+            pets["post"]["requestBody"],
+            json!({
+                "content": {
+                    "application/json": { "schema": { "$ref": "#/components/schemas/Pet" } },
+                    "application/xml": { "schema": { "$ref": "#/components/schemas/Pet" } }
+                },
+                "required": true
+            })
+        );
+        assert_eq!(
+            pets["post"]["responses"]["default"],
+            json!({ "$ref": "#/components/responses/Error" })
+        );
+
+        let photo = &converted["paths"]["/pets/{petId}/photo"]["post"];
+        assert_eq!(
+            photo["parameters"],
+            json!([{ "$ref": "#/components/parameters/PetId" }])
+        );
+        assert_eq!(
+            photo["requestBody"]["content"]["multipart/form-data"]["schema"],
+            json!({
+                "type": "object",
+                "properties": {
+                    "caption": { "type": "string", "maxLength": 140 },
+                    "file": { "type": "string", "format": "binary", "description": "The photo" }
+                },
+                "required": ["file"]
+            })
+        );
+
+        let components = &converted["components"];
+        assert_eq!(
+            components["schemas"]["Pet"]["properties"]["tag"]["nullable"],
+            true
+        );
+        assert_eq!(
+            components["schemas"]["Pet"]["discriminator"],
+            json!({ "propertyName": "kind" })
+        );
+        assert_eq!(
+            components["parameters"]["PetId"]["schema"],
+            json!({ "type": "integer", "minimum": 1 })
+        );
+        assert_eq!(
+            components["securitySchemes"],
+            json!({
+                "basic": { "type": "http", "scheme": "basic" },
+                "oauth": {
+                    "type": "oauth2",
+                    "flows": {
+                        "authorizationCode": {
+                            "authorizationUrl": "https://auth.example.com/authorize",
+                            "tokenUrl": "https://auth.example.com/token",
+                            "scopes": { "pets:write": "Modify pets" }
+                        }
+                    }
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_converted_validation() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(convert(&document()).unwrap())
+            .validate_specification(true)
+            .build()
+            .unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/pets")
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+        let pet = json!({ "name": "Rex", "kind": "dog", "tag": null });
+        assert!(validator.validate_request(&request(pet), None).is_ok());
+        let pet = json!({ "kind": "dog" });
+        assert!(validator.validate_request(&request(pet), None).is_err());
+        assert!(validator.find_operation("/pets/1/photo", "POST").is_ok());
+    }
+
+    #[test]
+    fn test_not_swagger() {
+        let error = convert(&json!({ "openapi": "3.0.3" })).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Conversion Failure: Not a Swagger 2.0 document, 'swagger' is null"
+        );
+
+        match OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(document())
+            .build()
+        {
+            Ok(_) => panic!("Swagger 2.0 documents must be converted first"),
+            Err(e) => assert!(e.to_string().contains("oasert::swagger::convert")),
+        }
+    }
+}
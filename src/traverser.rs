@@ -1,8 +1,9 @@
+use crate::types::filter::OperationFilter;
 use crate::types::json_path::JsonPath;
 use crate::types::operation::Operation;
 use crate::types::primitive::OpenApiPrimitives;
 use crate::{NAME_FIELD, PARAMETERS_FIELD, PATHS_FIELD, PATH_SEPARATOR, REF_FIELD, SCHEMA_FIELD};
-use dashmap::{DashMap, Entry};
+use dashmap::DashMap;
 use serde_json::{Map, Value};
 use std::collections::{HashMap, HashSet};
 use std::fmt::{Display, Formatter};
@@ -35,6 +36,9 @@ pub enum TraverserError {
 
     /// A specified path does not exist in the specification.
     PathNotFound(String),
+
+    /// An operation exists in the specification, but is excluded by the `OperationFilter`.
+    OperationNotServed(String),
 }
 
 impl TraverserError {
@@ -86,6 +90,15 @@ impl TraverserError {
     pub(crate) fn path_not_found(message: impl Into<String>) -> Self {
         Self::PathNotFound(message.into())
     }
+
+    /// Creates a new `OperationNotServed` error.
+    ///
+    /// # Parameters
+    /// - `message`: The method and path of the excluded operation
+    #[inline]
+    pub(crate) fn operation_not_served(message: impl Into<String>) -> Self {
+        Self::OperationNotServed(message.into())
+    }
 }
 
 impl Display for TraverserError {
@@ -106,6 +119,9 @@ impl Display for TraverserError {
             TraverserError::PathNotFound(field) => {
                 write!(f, "Path not found: {}", field)
             }
+            TraverserError::OperationNotServed(field) => {
+                write!(f, "Operation not served here: {}", field)
+            }
         }
     }
 }
@@ -185,6 +201,9 @@ pub struct OpenApiTraverser {
     resolved_references: DashMap<String, Arc<Value>>,
     resolved_operations: DashMap<(String, String), Arc<Operation>>,
     path_router: PathNode,
+    /// The operations excluded by the `OperationFilter`, only routed to tell them apart from
+    /// paths that do not exist.
+    excluded_router: PathNode,
 }
 
 impl OpenApiTraverser {
@@ -218,14 +237,31 @@ impl OpenApiTraverser {
     /// This constructor automatically crawls all paths in the specification to build
     /// an internal routing tree for efficient operation lookup.
     pub fn new<'a>(specification: Value) -> Result<Self, TraverserError> {
+        Self::with_filter(specification, &OperationFilter::default())
+    }
+
+    /// Creates a new OpenAPI traverser that only routes the operations a filter serves.
+    ///
+    /// # Parameters
+    /// - `specification`: The complete OpenAPI specification as a JSON Value
+    /// - `filter`: Decides which operations are served
+    ///
+    /// # Returns
+    /// A new `OpenApiTraverser` instance, whose `get_operation_from_path_and_method` returns
+    /// `OperationNotServed` for the operations the filter excludes.
+    pub fn with_filter(
+        specification: Value,
+        filter: &OperationFilter,
+    ) -> Result<Self, TraverserError> {
         let mut traverser = Self {
             specification,
             resolved_references: DashMap::new(),
             resolved_operations: DashMap::new(),
             path_router: PathNode::new(),
+            excluded_router: PathNode::new(),
         };
 
-        if let Err(_) = traverser.crawl_paths() {
+        if let Err(_) = traverser.crawl_paths(filter) {
             return Err(TraverserError::invalid_structure(
                 "Failed to build router paths from provided specification.",
             ));
@@ -233,11 +269,14 @@ impl OpenApiTraverser {
         Ok(traverser)
     }
 
-    /// Crawls all paths in the specification to build the internal routing tree.
+    /// Crawls all paths in the specification to build the internal routing trees.
+    ///
+    /// # Parameters
+    /// - `filter`: Decides which operations are routed, the others go to the excluded router
     ///
     /// # Returns
     /// `Ok(())` on successful crawling, or a `TraverserError` if parsing fails.
-    fn crawl_paths(&mut self) -> TraverseResult {
+    fn crawl_paths(&mut self, filter: &OperationFilter) -> TraverseResult<'_> {
         let spec_paths = Self::get_as_object(&self.specification, PATHS_FIELD)?;
 
         for (spec_path, spec_methods) in spec_paths {
@@ -246,7 +285,10 @@ impl OpenApiTraverser {
                 let spec_path_segments = Self::split_path_segments(spec_path);
 
                 // Start at the root of the router
-                let mut current_node = &mut self.path_router;
+                let mut current_node = match filter.is_served(spec_path, spec_operation) {
+                    true => &mut self.path_router,
+                    false => &mut self.excluded_router,
+                };
 
                 // Build the path tree
                 for segment in spec_path_segments {
@@ -255,12 +297,27 @@ impl OpenApiTraverser {
                             Self::get_as_array(spec_operation, PARAMETERS_FIELD)?;
                         let param_name = Self::extract_parameter_name(segment);
 
-                        let param_schema = operation_params.iter().find(|param| {
-                            if let Ok(name) = Self::get_as_str(param, NAME_FIELD) {
-                                return name == param_name;
-                            }
-                            false
-                        });
+                        // Parameters can be references to components/parameters.
+                        let specification = &self.specification;
+                        let param_schema = operation_params
+                            .iter()
+                            .filter_map(|param| {
+                                match param.get(REF_FIELD).and_then(Value::as_str) {
+                                    Some(reference) => Self::get_reference_path(
+                                        specification,
+                                        reference,
+                                        &mut HashSet::new(),
+                                    )
+                                    .ok(),
+                                    None => Some(param),
+                                }
+                            })
+                            .find(|param| {
+                                if let Ok(name) = Self::get_as_str(param, NAME_FIELD) {
+                                    return name == param_name;
+                                }
+                                false
+                            });
                         let param_schema = match param_schema {
                             None => continue,
                             Some(found) => match found.get(SCHEMA_FIELD) {
@@ -331,15 +388,14 @@ impl OpenApiTraverser {
         K: Eq + Hash,
         F: FnOnce() -> Result<Arc<V>, TraverserError>,
     {
-        let entry = cache.entry(key);
-        match entry {
-            Entry::Occupied(e) => Ok(e.get().clone()),
-            Entry::Vacant(e) => {
-                let result = resolver()?;
-                e.insert(result.clone());
-                Ok(result)
-            }
+        if let Some(cached) = cache.get(&key) {
+            return Ok(cached.clone());
         }
+        // The resolver is called without holding a lock on the cache, as resolving a reference
+        // can resolve (and cache) the references it points to.
+        let result = resolver()?;
+        cache.insert(key, result.clone());
+        Ok(result)
     }
 
     /// Checks if a path segment is a parameter placeholder.
@@ -415,7 +471,8 @@ impl OpenApiTraverser {
     /// # Behavior
     /// This method performs path matching with parameter validation, including type
     /// conversion and schema validation for path parameters. Results are cached for
-    /// performance. Returns `PathNotFound` error if no matching operation exists.
+    /// performance. Returns `PathNotFound` error if no matching operation exists, and
+    /// `OperationNotServed` if the matching operation is excluded by the `OperationFilter`.
     pub fn get_operation_from_path_and_method<'a>(
         &self,
         request_path: &'a str,
@@ -430,8 +487,20 @@ impl OpenApiTraverser {
             || {
                 let result =
                     self.find_matching_operation(&segments, 0, &self.path_router, request_method);
-                match result {
-                    Some(operation) => Ok(operation),
+                if let Some(operation) = result {
+                    return Ok(operation);
+                }
+                match self.find_matching_operation(
+                    &segments,
+                    0,
+                    &self.excluded_router,
+                    request_method,
+                ) {
+                    Some(_) => Err(TraverserError::operation_not_served(format!(
+                        "{} {}",
+                        request_method.to_uppercase(),
+                        request_path
+                    ))),
                     None => Err(TraverserError::path_not_found(request_path)),
                 }
             },
@@ -627,11 +696,12 @@ impl OpenApiTraverser {
                 String::from(ref_string),
                 || {
                     let mut seen_references = HashSet::new();
-                    let result = self.get_reference_path(ref_string, &mut seen_references)?;
-                    Ok(match result {
-                        SearchResult::Arc(val) => val,
-                        SearchResult::Ref(val) => Arc::new(val.clone()),
-                    })
+                    let result = Self::get_reference_path(
+                        &self.specification,
+                        ref_string,
+                        &mut seen_references,
+                    )?;
+                    Ok(Arc::new(result.clone()))
                 },
             )?;
             return Ok(SearchResult::Arc(result));
@@ -641,20 +711,21 @@ impl OpenApiTraverser {
 
     /// Resolves a reference path with circular reference detection.
     ///
+    /// A reference to another reference is followed until it leads to a node that is not one.
+    ///
     /// # Parameters
+    /// - `specification`: The document the reference is resolved in
     /// - `ref_string`: The reference string to resolve (e.g., "#/components/schemas/User")
-    /// - `seen_references`: Set of already seen references for cycle detection
+    /// - `seen_references`: Set of already seen references for cycle detection. The followed
+    ///   references are added to it, so that a caller expanding them can detect recursion.
     ///
     /// # Returns
     /// The resolved JSON node or an error if the reference is invalid or circular.
-    fn get_reference_path<'n, 's>(
-        &'n self,
+    pub(crate) fn get_reference_path<'n>(
+        specification: &'n Value,
         ref_string: &'n str,
         seen_references: &mut HashSet<&'n str>,
-    ) -> TraverseSearchResult<'s>
-    where
-        'n: 's,
-    {
+    ) -> Result<&'n Value, TraverserError> {
         if seen_references.contains(ref_string) {
             return Err(TraverserError::cyclic_reference(ref_string));
         }
@@ -666,11 +737,14 @@ impl OpenApiTraverser {
             .collect::<Vec<&str>>()
             .join("/");
         complete_path.push_str(&path);
-        let current_schema = match &self.specification.pointer(&complete_path) {
+        let current_schema = match specification.pointer(&complete_path) {
             None => return Err(TraverserError::missing_field(ref_string)),
-            Some(v) => self.resolve_possible_ref(v)?,
+            Some(v) => v,
         };
-        Ok(current_schema)
+        match Self::get_as_str(current_schema, REF_FIELD) {
+            Ok(ref_string) => Self::get_reference_path(specification, ref_string, seen_references),
+            Err(_) => Ok(current_schema),
+        }
     }
 
     /// Generic helper for extracting typed values from JSON nodes.
@@ -835,6 +909,7 @@ impl OpenApiTraverser {
 #[cfg(test)]
 mod tests {
     use crate::traverser::{OpenApiTraverser, TraverserError};
+    use crate::types::filter::{OperationFilter, OperationMatcher};
     use serde_json::json;
     use std::sync::Arc;
 
@@ -993,6 +1068,40 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_get_operation_from_path_and_method_with_filter() {
+        let spec = json!({
+            "paths": {
+                "/pets/{id}": {
+                    "get": {
+                        "tags": ["pets"],
+                        "parameters": [
+                            { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                        ]
+                    },
+                    "delete": {
+                        "tags": ["admin"],
+                        "parameters": [
+                            { "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }
+                        ]
+                    }
+                }
+            }
+        });
+        let filter = OperationFilter::new().exclude(OperationMatcher::Tag("admin".to_string()));
+        let traverser = OpenApiTraverser::with_filter(spec, &filter).unwrap();
+        assert!(traverser
+            .get_operation_from_path_and_method("/pets/1", "GET")
+            .is_ok());
+        let result = traverser.get_operation_from_path_and_method("/pets/1", "DELETE");
+        assert!(
+            matches!(result, Err(TraverserError::OperationNotServed(operation)) if operation == "DELETE /pets/1")
+        );
+        // The excluded operation still needs a valid path parameter.
+        let result = traverser.get_operation_from_path_and_method("/pets/one", "DELETE");
+        assert!(matches!(result, Err(TraverserError::PathNotFound(_))));
+    }
+
     #[test]
     fn test_require_object_with_valid_object() {
         let object_value = json!({"name": "test", "age": 30});
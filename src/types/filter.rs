@@ -0,0 +1,120 @@
+use crate::PATH_SEPARATOR;
+use serde_json::Value;
+
+const TAGS_FIELD: &str = "tags";
+const OPERATION_ID_FIELD: &str = "operationId";
+
+/// Selects operations by a property of theirs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperationMatcher {
+    /// Operations listing the tag in `tags`.
+    Tag(String),
+    /// The operation with the `operationId`.
+    OperationId(String),
+    /// Operations whose path template starts with the prefix, on a segment boundary: `/users`
+    /// selects `/users` and `/users/{id}`, but not `/usersettings`.
+    PathPrefix(String),
+    /// Operations with the `x-` extension set to the value, e.g. `x-service: billing`.
+    Extension(String, Value),
+}
+
+impl OperationMatcher {
+    fn matches(&self, path: &str, operation: &Value) -> bool {
+        match self {
+            OperationMatcher::Tag(tag) => operation
+                .get(TAGS_FIELD)
+                .and_then(Value::as_array)
+                .is_some_and(|tags| tags.iter().any(|t| t == tag)),
+            OperationMatcher::OperationId(id) => {
+                operation.get(OPERATION_ID_FIELD).and_then(Value::as_str) == Some(id)
+            }
+            OperationMatcher::PathPrefix(prefix) => match path.strip_prefix(prefix.as_str()) {
+                Some(rest) => {
+                    rest.is_empty()
+                        || rest.starts_with(PATH_SEPARATOR)
+                        || prefix.ends_with(PATH_SEPARATOR)
+                }
+                None => false,
+            },
+            OperationMatcher::Extension(name, value) => operation.get(name) == Some(value),
+        }
+    }
+}
+
+/// Decides which operations of a specification are served, for deployments that serve only a
+/// part of it.
+///
+/// An operation is served when it matches one of the `include` matchers (or there are none) and
+/// none of the `exclude` matchers.
+#[derive(Debug, Clone, Default)]
+pub struct OperationFilter {
+    include: Vec<OperationMatcher>,
+    exclude: Vec<OperationMatcher>,
+}
+
+impl OperationFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Serves the operations matching `matcher`, and any other included operation.
+    pub fn include(mut self, matcher: OperationMatcher) -> Self {
+        self.include.push(matcher);
+        self
+    }
+
+    /// Does not serve the operations matching `matcher`, even when they are included.
+    pub fn exclude(mut self, matcher: OperationMatcher) -> Self {
+        self.exclude.push(matcher);
+        self
+    }
+
+    /// Whether an operation is served.
+    ///
+    /// # Arguments
+    /// * `path` - The path template of the operation, e.g. `/users/{id}`
+    /// * `operation` - The Operation Object
+    pub fn is_served(&self, path: &str, operation: &Value) -> bool {
+        let included = self.include.is_empty()
+            || self
+                .include
+                .iter()
+                .any(|matcher| matcher.matches(path, operation));
+        included
+            && !self
+                .exclude
+                .iter()
+                .any(|matcher| matcher.matches(path, operation))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_is_served() {
+        let operation =
+            json!({ "operationId": "getUser", "tags": ["users"], "x-service": "accounts" });
+        assert!(OperationFilter::new().is_served("/users/{id}", &operation));
+
+        let filter = OperationFilter::new().include(OperationMatcher::Tag("users".to_string()));
+        assert!(filter.is_served("/users/{id}", &operation));
+        assert!(!filter.is_served("/users/{id}", &json!({ "tags": ["admin"] })));
+
+        let filter = OperationFilter::new()
+            .include(OperationMatcher::PathPrefix("/users".to_string()))
+            .exclude(OperationMatcher::OperationId("getUser".to_string()));
+        assert!(!filter.is_served("/users/{id}", &operation));
+        assert!(filter.is_served("/users", &json!({})));
+        assert!(!filter.is_served("/usersettings", &json!({})));
+
+        let filter = OperationFilter::new().exclude(OperationMatcher::Extension(
+            "x-service".to_string(),
+            json!("accounts"),
+        ));
+        assert!(!filter.is_served("/users/{id}", &operation));
+        assert!(filter.is_served("/users/{id}", &json!({ "x-service": "billing" })));
+    }
+}
@@ -1,6 +1,9 @@
+pub mod filter;
 pub mod json_path;
 pub mod operation;
 pub mod primitive;
+pub mod resolver;
+pub mod security;
 pub mod version;
 
 use crate::converter::RequestBody;
@@ -8,7 +11,7 @@ use http::{HeaderMap, Method};
 use serde_json::Value;
 use std::fmt::{Display, Formatter};
 
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone)]
 pub enum ParameterLocation {
     Header,
     Query,
@@ -29,3 +32,32 @@ impl Display for ParameterLocation {
 }
 
 
+
+/// The direction a payload travels in, which decides whether `readOnly` or `writeOnly`
+/// properties are allowed in it.
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+pub enum Direction {
+    Request,
+    Response,
+}
+
+impl Display for Direction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let str = String::from(match self {
+            Direction::Request => "request",
+            Direction::Response => "response",
+        });
+        write!(f, "{}", str)
+    }
+}
+
+/// What to do with `readOnly` properties sent in a request, or `writeOnly` properties returned
+/// in a response.
+#[derive(PartialEq, Debug, Clone, Copy, Default)]
+pub enum AccessModePolicy {
+    /// The payload fails validation.
+    #[default]
+    Reject,
+    /// The properties are ignored while validating, and removed by the `strip_*` methods.
+    Strip,
+}
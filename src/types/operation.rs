@@ -69,3 +69,55 @@ impl RequestBody {
         self.content.get(content_type)
     }
 }
+
+impl Operation {
+    /// The operation object as defined in the specification.
+    pub fn data(&self) -> &Value {
+        &self.data
+    }
+
+    /// The `operationId` of the operation, if it has one.
+    pub fn operation_id(&self) -> Option<&str> {
+        self.data.get("operationId").and_then(Value::as_str)
+    }
+
+    /// The `tags` of the operation.
+    pub fn tags(&self) -> Vec<&str> {
+        match self.data.get("tags").and_then(Value::as_array) {
+            Some(tags) => tags.iter().filter_map(Value::as_str).collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the path template (e.g. `/pets/{petId}`) this operation is defined under.
+    pub(crate) fn path_template(&self) -> Option<String> {
+        self.path.0.get(1).map(|segment| {
+            segment
+                .replace(crate::ENCODED_BACKSLASH, crate::PATH_SEPARATOR)
+                .replace(crate::ENCODED_TILDE, crate::TILDE)
+        })
+    }
+
+    /// Extracts the values of templated path segments from a concrete request path.
+    pub(crate) fn extract_path_parameters(&self, request_path: &str) -> HashMap<String, String> {
+        let mut params = HashMap::new();
+        let template = match self.path_template() {
+            None => return params,
+            Some(template) => template,
+        };
+        let template_segments = template.split(crate::PATH_SEPARATOR).filter(|s| !s.is_empty());
+        let request_segments = request_path
+            .split(crate::PATH_SEPARATOR)
+            .filter(|s| !s.is_empty());
+        for (template_segment, request_segment) in template_segments.zip(request_segments) {
+            if template_segment.starts_with('{') && template_segment.ends_with('}') {
+                let name = &template_segment[1..template_segment.len() - 1];
+                let value = percent_encoding::percent_decode_str(request_segment)
+                    .decode_utf8_lossy()
+                    .to_string();
+                params.insert(name.to_string(), value);
+            }
+        }
+        params
+    }
+}
@@ -1,5 +1,5 @@
 use crate::error::ValidationErrorType;
-use serde_json::{json, Value};
+use serde_json::{json, Number, Value};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
@@ -34,13 +34,16 @@ impl FromStr for OpenApiPrimitives {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_lowercase().as_str() {
             "null" => Ok(OpenApiPrimitives::Null),
-            "bool" => Ok(OpenApiPrimitives::Bool),
+            "bool" | "boolean" => Ok(OpenApiPrimitives::Bool),
             "integer" => Ok(OpenApiPrimitives::Integer),
             "number" => Ok(OpenApiPrimitives::Number),
             "string" => Ok(OpenApiPrimitives::String),
             "array" => Ok(OpenApiPrimitives::Array),
             "object" => Ok(OpenApiPrimitives::Object),
-            &_ => todo!(),
+            other => Err(ValidationErrorType::assertion_failed(&format!(
+                "Unknown type '{}'",
+                other
+            ))),
         }
     }
 }
@@ -63,30 +66,145 @@ impl OpenApiPrimitives {
         None
     }
 
+    /// Converts a raw string (a parameter value) into the JSON value the schema describes.
+    ///
+    /// - `const`/`enum` values are matched first, by their string form
+    /// - a `type` array converts to the first of its types the input converts to, `null` last
+    /// - without `type`, the subschemas of `oneOf`/`anyOf`/`allOf` are tried in order, preferring
+    ///   a conversion the subschema accepts
+    /// - a schema with none of the above keeps the input as a string
+    ///
+    /// # Arguments
+    /// * `schema` - The schema of the value
+    /// * `input` - The raw value
+    ///
+    /// # Returns
+    /// * `Ok(Value)` - The converted value
+    /// * `Err(PrimitiveError)` - If the input cannot be converted to any type of the schema.
     pub fn convert_string_to_schema_type(
         schema: &Value,
         input: &str,
     ) -> Result<Value, PrimitiveError> {
-        let type_field = match schema
-            .get("type")
-            .and_then(|type_value| type_value.as_str())
-        {
-            None => {
-                return Err(PrimitiveError::invalid_schema_error(
-                    "Could not find 'type' field in schema.",
-                ));
+        if let Some(value) = Self::find_listed_value(schema, input) {
+            return Ok(value);
+        }
+        match schema.get("type") {
+            Some(Value::String(type_field)) => {
+                Self::convert_to_named_type(schema, type_field, input)
             }
-            Some(v) => v,
-        };
+            Some(Value::Array(types)) => Self::convert_to_any_type(schema, types, input),
+            Some(other) => Err(PrimitiveError::invalid_schema_error(format!(
+                "Invalid type field in schema: '{}'",
+                other
+            ))),
+            None => Self::convert_to_subschema_type(schema, input),
+        }
+    }
+
+    fn convert_to_named_type(
+        schema: &Value,
+        type_field: &str,
+        input: &str,
+    ) -> Result<Value, PrimitiveError> {
         let openapi_type = OpenApiPrimitives::from_str(type_field).map_err(|_| {
             PrimitiveError::invalid_schema_error(format!(
                 "Invalid type field in schema: '{}'",
                 type_field
             ))
         })?;
+        if openapi_type == OpenApiPrimitives::Array {
+            return Self::convert_delimited_to_array(schema, input);
+        }
         openapi_type.convert_value_to_type(input)
     }
 
+    fn convert_to_any_type(
+        schema: &Value,
+        types: &[Value],
+        input: &str,
+    ) -> Result<Value, PrimitiveError> {
+        let mut last_error = None;
+        for type_field in types.iter().filter_map(Value::as_str) {
+            if type_field == "null" {
+                continue;
+            }
+            match Self::convert_to_named_type(schema, type_field, input) {
+                Ok(value) => return Ok(value),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        if types.iter().any(|type_field| type_field == "null") && input == "null" {
+            return Ok(Value::Null);
+        }
+        match last_error {
+            Some(e) => Err(e),
+            None => Err(PrimitiveError::invalid_schema_error(
+                "Could not find a type in the 'type' field of schema.",
+            )),
+        }
+    }
+
+    fn convert_to_subschema_type(schema: &Value, input: &str) -> Result<Value, PrimitiveError> {
+        let mut fallback = None;
+        for keyword in ["oneOf", "anyOf", "allOf"] {
+            let subschemas = match schema.get(keyword).and_then(Value::as_array) {
+                Some(subschemas) => subschemas,
+                None => continue,
+            };
+            for subschema in subschemas {
+                let value = match Self::convert_string_to_schema_type(subschema, input) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+                // Subschemas with a '$ref' cannot be compiled on their own, these only serve as fallback.
+                if jsonschema::validator_for(subschema).is_ok_and(|v| v.is_valid(&value)) {
+                    return Ok(value);
+                }
+                fallback.get_or_insert(value);
+            }
+        }
+        match fallback {
+            Some(value) => Ok(value),
+            None => Ok(Value::String(input.to_string())),
+        }
+    }
+
+    /// Finds the `const` or `enum` value of a schema whose string form is the input.
+    fn find_listed_value(schema: &Value, input: &str) -> Option<Value> {
+        let listed = match (schema.get("const"), schema.get("enum")) {
+            (Some(value), _) => std::slice::from_ref(value),
+            (None, Some(Value::Array(values))) => values.as_slice(),
+            _ => return None,
+        };
+        listed
+            .iter()
+            .find(|value| match value {
+                Value::String(value) => value == input,
+                Value::Number(value) => input.parse::<Number>().is_ok_and(|n| n == *value),
+                Value::Bool(value) => input.parse::<bool>().is_ok_and(|b| b == *value),
+                Value::Null => input == "null",
+                Value::Object(_) | Value::Array(_) => false,
+            })
+            .cloned()
+    }
+
+    /// Converts a comma-delimited string (the OpenAPI `form`/`simple` style) into an array,
+    /// converting each element using the `items` schema when one is present.
+    fn convert_delimited_to_array(schema: &Value, input: &str) -> Result<Value, PrimitiveError> {
+        if input.is_empty() {
+            return Ok(Value::Array(vec![]));
+        }
+        let mut converted = vec![];
+        for item in input.split(',') {
+            let item = match schema.get("items") {
+                Some(items_schema) => Self::convert_string_to_schema_type(items_schema, item)?,
+                _ => Value::String(item.to_string()),
+            };
+            converted.push(item);
+        }
+        Ok(Value::Array(converted))
+    }
+
     pub fn convert_value_to_type(&self, input: &str) -> Result<Value, PrimitiveError> {
         match self {
             OpenApiPrimitives::Null => Ok(json!(Value::Null)),
@@ -158,3 +276,44 @@ impl Display for PrimitiveError {
 }
 
 impl std::error::Error for PrimitiveError {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_convert_type_array() {
+        let schema = json!({ "type": ["integer", "null"] });
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "42").unwrap();
+        assert_eq!(converted, json!(42));
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "null").unwrap();
+        assert_eq!(converted, Value::Null);
+        assert!(OpenApiPrimitives::convert_string_to_schema_type(&schema, "many").is_err());
+
+        let schema = json!({ "type": ["boolean", "string"] });
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "true").unwrap();
+        assert_eq!(converted, json!(true));
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "yes").unwrap();
+        assert_eq!(converted, json!("yes"));
+    }
+
+    #[test]
+    fn test_convert_without_type() {
+        let schema = json!({ "oneOf": [{ "type": "integer", "minimum": 10 }, { "type": "string" }] });
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "20").unwrap();
+        assert_eq!(converted, json!(20));
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "5").unwrap();
+        assert_eq!(converted, json!("5"));
+
+        let schema = json!({ "enum": [1, 2, "three"] });
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "2").unwrap();
+        assert_eq!(converted, json!(2));
+        let schema = json!({ "const": false });
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "false").unwrap();
+        assert_eq!(converted, json!(false));
+
+        let schema = json!({ "$ref": "#/components/schemas/Id" });
+        let converted = OpenApiPrimitives::convert_string_to_schema_type(&schema, "abc").unwrap();
+        assert_eq!(converted, json!("abc"));
+    }
+}
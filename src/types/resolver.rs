@@ -0,0 +1,72 @@
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Serves the documents of `$ref` URIs that are not files, such as URNs or the `https` ids of a
+/// shared schema library.
+///
+/// A resolver is registered on the `OpenApiPayloadValidatorBuilder`. The documents it serves are
+/// embedded in the specification when it is loaded, so that both the traversal of the
+/// specification and the compiled JSON schemas resolve references into them. Relative references
+/// inside a served document are resolved against its URI, and served by the resolver as well.
+///
+/// # Example
+///
+/// ```rust
+/// use oasert::types::resolver::{InMemoryResolver, ReferenceResolver};
+/// use serde_json::json;
+///
+/// let resolver = InMemoryResolver::new().with_document(
+///     "urn:schemas:money",
+///     json!({ "Money": { "type": "string", "pattern": "^[0-9]+\\.[0-9]{2}$" } }),
+/// );
+/// assert!(resolver.resolve("urn:schemas:money").is_ok());
+/// assert!(resolver.resolve("urn:schemas:unknown").is_err());
+/// ```
+pub trait ReferenceResolver: Send + Sync {
+    /// # resolve
+    ///
+    /// # Arguments
+    ///
+    /// * `uri` - The absolute URI of the document, without fragment
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Value)` - The document
+    /// * `Err(Box<dyn Error>)` - If the resolver does not serve the URI.
+    fn resolve(&self, uri: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// A `ReferenceResolver` serving documents from memory, by URI.
+#[derive(Debug, Clone, Default)]
+pub struct InMemoryResolver {
+    documents: HashMap<String, Value>,
+}
+
+impl InMemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a document, served for `uri` (an empty fragment, i.e. a trailing `#`, is ignored).
+    pub fn with_document(mut self, uri: impl Into<String>, document: Value) -> Self {
+        self.insert(uri, document);
+        self
+    }
+
+    /// Adds a document, served for `uri` (an empty fragment, i.e. a trailing `#`, is ignored).
+    pub fn insert(&mut self, uri: impl Into<String>, document: Value) {
+        let uri = uri.into();
+        let uri = uri.strip_suffix('#').map(String::from).unwrap_or(uri);
+        self.documents.insert(uri, document);
+    }
+}
+
+impl ReferenceResolver for InMemoryResolver {
+    fn resolve(&self, uri: &str) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
+        let uri = uri.strip_suffix('#').unwrap_or(uri);
+        match self.documents.get(uri) {
+            Some(document) => Ok(document.clone()),
+            None => Err(format!("No document is registered for '{}'", uri).into()),
+        }
+    }
+}
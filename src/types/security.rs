@@ -0,0 +1,180 @@
+use crate::types::ParameterLocation;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Scopes granted to a request, keyed by the name of the security scheme that granted them.
+pub type SchemeScopes = HashMap<String, Vec<String>>;
+
+/// The type of a Security Scheme Object defined in `components/securitySchemes`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SecuritySchemeType {
+    /// An API key sent in a header, query parameter or cookie.
+    ApiKey {
+        name: String,
+        location: ParameterLocation,
+    },
+
+    /// An HTTP authentication scheme sent in the `Authorization` header (e.g. `basic`, `bearer`).
+    Http {
+        scheme: String,
+        bearer_format: Option<String>,
+    },
+
+    /// OAuth 2.0, presented as a bearer token.
+    OAuth2,
+
+    /// OpenID Connect, presented as a bearer token.
+    OpenIdConnect,
+
+    /// Mutual TLS, which is established by the transport and not visible in the request.
+    MutualTls,
+}
+
+impl Display for SecuritySchemeType {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecuritySchemeType::ApiKey { name, location } => {
+                write!(f, "apiKey '{}' in {}", name, location)
+            }
+            SecuritySchemeType::Http { scheme, .. } => write!(f, "http {}", scheme),
+            SecuritySchemeType::OAuth2 => write!(f, "oauth2"),
+            SecuritySchemeType::OpenIdConnect => write!(f, "openIdConnect"),
+            SecuritySchemeType::MutualTls => write!(f, "mutualTLS"),
+        }
+    }
+}
+
+/// A credential extracted from a request for a specific security scheme.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credential {
+    /// The raw value of an API key.
+    ApiKey(String),
+
+    /// A decoded HTTP basic credential.
+    Basic { username: String, password: String },
+
+    /// A bearer token (HTTP bearer, OAuth 2.0 or OpenID Connect).
+    Bearer(String),
+
+    /// The value of an `Authorization` header for any other HTTP scheme.
+    Authorization { scheme: String, value: String },
+
+    /// No credential is carried by the request (mutual TLS).
+    Transport,
+}
+
+/// A security scheme, from the requirement that was satisfied, and its credential.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityMatch {
+    pub(crate) scheme_name: String,
+    pub(crate) scheme_type: SecuritySchemeType,
+    pub(crate) credential: Credential,
+    pub(crate) scopes: Vec<String>,
+    pub(crate) claims: Option<Value>,
+}
+
+impl SecurityMatch {
+    /// The name of the matched scheme, as declared in `components/securitySchemes`.
+    pub fn scheme_name(&self) -> &str {
+        &self.scheme_name
+    }
+
+    /// The type of the matched scheme.
+    pub fn scheme_type(&self) -> &SecuritySchemeType {
+        &self.scheme_type
+    }
+
+    /// The credential extracted from the request for the matched scheme.
+    pub fn credential(&self) -> &Credential {
+        &self.credential
+    }
+
+    /// The scopes granted by the credential, when it was verified (e.g. by a `JwtVerifier`).
+    pub fn scopes(&self) -> &Vec<String> {
+        &self.scopes
+    }
+
+    /// The claims of the credential, when it was a verified token.
+    pub fn claims(&self) -> Option<&Value> {
+        self.claims.as_ref()
+    }
+}
+
+/// The reason a `SecurityHandler` rejected a credential.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityRejection {
+    reason: String,
+}
+
+impl SecurityRejection {
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+        }
+    }
+
+    pub fn reason(&self) -> &str {
+        &self.reason
+    }
+}
+
+impl Display for SecurityRejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Rejected: {}", self.reason)
+    }
+}
+
+impl std::error::Error for SecurityRejection {}
+
+/// Verifies the credential extracted for a security scheme.
+///
+/// Handlers are registered per `securitySchemes` name on the `OpenApiPayloadValidatorBuilder`.
+/// When a request presents a credential for that scheme, the handler decides whether it is
+/// accepted and which scopes it grants. The granted scopes are then checked against the
+/// scopes listed for the scheme in the operation's security requirement.
+///
+/// # Example
+///
+/// ```rust
+/// use oasert::types::security::{Credential, SecurityHandler, SecurityRejection};
+/// use std::collections::HashMap;
+///
+/// struct ApiKeyStore {
+///     keys: HashMap<String, Vec<String>>,
+/// }
+///
+/// impl SecurityHandler for ApiKeyStore {
+///     fn verify(
+///         &self,
+///         _scheme_name: &str,
+///         credential: &Credential,
+///     ) -> Result<Vec<String>, SecurityRejection> {
+///         match credential {
+///             Credential::ApiKey(key) => match self.keys.get(key) {
+///                 Some(scopes) => Ok(scopes.clone()),
+///                 None => Err(SecurityRejection::new("unknown API key")),
+///             },
+///             _ => Err(SecurityRejection::new("expected an API key")),
+///         }
+///     }
+/// }
+/// ```
+pub trait SecurityHandler: Send + Sync {
+    /// # verify
+    ///
+    /// # Arguments
+    ///
+    /// * `scheme_name` - The name of the scheme in `components/securitySchemes`
+    /// * `credential` - The credential extracted from the request for that scheme
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` - The scopes granted by the credential
+    /// * `Err(SecurityRejection)` - If the credential is not accepted.
+    fn verify(
+        &self,
+        scheme_name: &str,
+        credential: &Credential,
+    ) -> Result<Vec<String>, SecurityRejection>;
+}
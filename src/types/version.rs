@@ -1,4 +1,6 @@
-use jsonschema::Draft;
+use crate::dialect;
+use jsonschema::{Draft, Resource};
+use serde_json::Value;
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
@@ -28,11 +30,65 @@ impl OpenApiVersion {
             OpenApiVersion::V31x => Draft::Draft202012,
         }
     }
+
+    /// Returns the draft the schemas of a specification are compiled with.
+    ///
+    /// For 3.1 this is the draft named by the root `jsonSchemaDialect`, when it is set.
+    pub(crate) fn resolve_draft(&self, specification: &Value) -> Result<Draft, VersionError> {
+        match self {
+            OpenApiVersion::V30x => Ok(self.get_draft()),
+            OpenApiVersion::V31x => dialect::resolve_oas31_draft(specification)
+                .map_err(|e| VersionError::unsupported_dialect(&e)),
+        }
+    }
+
+    /// Rewrites the schemas of a specification into the JSON Schema draft returned by `get_draft`.
+    pub(crate) fn normalize_specification(&self, specification: &mut Value) {
+        match self {
+            OpenApiVersion::V30x => dialect::normalize_oas30_document(specification),
+            OpenApiVersion::V31x => {}
+        }
+    }
+
+    /// Creates the schema resources of a specification, the root resource first.
+    ///
+    /// The resources are registered with the `oneOf` of discriminated schemas relaxed, see
+    /// `dialect::normalize_discriminators`; the specification itself is left as is.
+    ///
+    /// # Arguments
+    /// * `specification` - The normalized specification
+    /// * `root_id` - The base URI the specification is registered under
+    /// * `draft` - The draft returned by `resolve_draft`
+    ///
+    /// # Returns
+    /// * `Ok(Vec<Resource>)` - The root resource and the resources of schemas in other dialects
+    /// * `Err(VersionError)` - If a schema declares an unsupported dialect.
+    pub(crate) fn create_resources(
+        &self,
+        specification: &Value,
+        root_id: &str,
+        draft: Draft,
+    ) -> Result<Vec<(String, Resource)>, VersionError> {
+        let mut document = specification.clone();
+        dialect::normalize_discriminators(&mut document);
+        let mut resources = match self {
+            OpenApiVersion::V30x => vec![],
+            OpenApiVersion::V31x => {
+                match dialect::embed_oas31_schemas(&mut document, root_id, draft) {
+                    Ok(resources) => resources,
+                    Err(e) => return Err(VersionError::unsupported_dialect(&e)),
+                }
+            }
+        };
+        resources.insert(0, (root_id.to_string(), draft.create_resource(document)));
+        Ok(resources)
+    }
 }
 
 #[derive(Debug)]
 pub enum VersionError {
     UnsupportedVersion(String),
+    UnsupportedDialect(String),
 }
 
 impl VersionError {
@@ -42,6 +98,13 @@ impl VersionError {
     {
         VersionError::UnsupportedVersion(version.to_string())
     }
+
+    pub(crate) fn unsupported_dialect<T>(message: &T) -> Self
+    where
+        T: ToString + ?Sized,
+    {
+        VersionError::UnsupportedDialect(message.to_string())
+    }
 }
 
 impl Display for VersionError {
@@ -50,6 +113,9 @@ impl Display for VersionError {
             VersionError::UnsupportedVersion(version) => {
                 write!(f, "Unsupported version: {}", version)
             }
+            VersionError::UnsupportedDialect(message) => {
+                write!(f, "Unsupported dialect: {}", message)
+            }
         }
     }
 }
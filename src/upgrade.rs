@@ -0,0 +1,171 @@
+//! Upgrade of OpenAPI 3.0 documents to OpenAPI 3.1.
+//!
+//! `upgrade` rewrites a 3.0.x document into an equivalent 3.1.0 document, whose Schema Objects
+//! are plain Draft 2020-12 schemas:
+//!
+//! - `nullable: true` becomes a type union with `null` (or an `anyOf` with a `null` schema when
+//!   the schema has no `type`)
+//! - boolean `exclusiveMinimum`/`exclusiveMaximum` become numeric bounds
+//! - `example` becomes a single item `examples`
+//! - `format: binary` becomes `contentMediaType: application/octet-stream`
+//!
+//! `OpenApiPayloadValidatorBuilder::upgrade_to_oas31` applies the same upgrade to the
+//! specification it loads, so that only Draft 2020-12 schemas are compiled.
+//!
+//! # Example
+//!
+//! ```rust
+//! use oasert::upgrade;
+//! use serde_json::json;
+//!
+//! let specification = json!({
+//!     "openapi": "3.0.3",
+//!     "info": { "title": "Pets", "version": "1.0.0" },
+//!     "paths": {},
+//!     "components": {
+//!         "schemas": {
+//!             "Age": { "type": "integer", "minimum": 0, "exclusiveMinimum": true, "nullable": true }
+//!         }
+//!     }
+//! });
+//! let upgraded = upgrade::upgrade(&specification).unwrap();
+//! assert_eq!(upgraded["openapi"], "3.1.0");
+//! assert_eq!(
+//!     upgraded["components"]["schemas"]["Age"],
+//!     json!({ "type": ["integer", "null"], "exclusiveMinimum": 0 })
+//! );
+//! ```
+use crate::dialect;
+use crate::types::version::OpenApiVersion;
+use crate::OPENAPI_FIELD;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// The document cannot be upgraded.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpgradeError(pub String);
+
+impl Display for UpgradeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Upgrade Failure: {}", self.0)
+    }
+}
+
+impl std::error::Error for UpgradeError {}
+
+/// Upgrades an OpenAPI 3.0.x document to OpenAPI 3.1.0.
+///
+/// # Arguments
+/// * `specification` - The OpenAPI document
+///
+/// # Returns
+/// * `Ok(Value)` - The upgraded document, or a copy of the document if it already is 3.1.x
+/// * `Err(UpgradeError)` - If the document has no `openapi` version, or one that is not supported.
+pub fn upgrade(specification: &Value) -> Result<Value, UpgradeError> {
+    let version = match specification.get(OPENAPI_FIELD).and_then(Value::as_str) {
+        Some(version) => version,
+        None => {
+            return Err(UpgradeError(format!(
+                "'{}' must be a version string",
+                OPENAPI_FIELD
+            )));
+        }
+    };
+    let mut upgraded = specification.clone();
+    match OpenApiVersion::from_str(version) {
+        Ok(OpenApiVersion::V30x) => dialect::upgrade_oas30_document(&mut upgraded),
+        Ok(OpenApiVersion::V31x) => {}
+        Err(e) => return Err(UpgradeError(e.to_string())),
+    }
+    Ok(upgraded)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+    use http::Request;
+    use serde_json::json;
+
+    fn specification() -> Value {
+        json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Pets", "version": "1.0.0" },
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Pet" },
+                                    "example": { "name": "Rex", "age": null }
+                                }
+                            }
+                        },
+                        "responses": { "201": { "description": "Created" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "required": ["name"],
+                        "example": { "name": "Rex" },
+                        "properties": {
+                            "name": { "type": "string" },
+                            "age": { "type": "integer", "minimum": 0, "exclusiveMinimum": true, "nullable": true }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_upgrade() {
+        let upgraded = upgrade(&specification()).unwrap();
+        assert_eq!(upgraded["openapi"], "3.1.0");
+        assert_eq!(
+            upgraded["components"]["schemas"]["Pet"]["properties"]["age"],
+            json!({ "type": ["integer", "null"], "exclusiveMinimum": 0 })
+        );
+        assert_eq!(
+            upgraded["components"]["schemas"]["Pet"]["examples"],
+            json!([{ "name": "Rex" }])
+        );
+        // Examples of media types are not Schema Objects, they are left as they are.
+        assert_eq!(
+            upgraded["paths"]["/pets"]["post"]["requestBody"]["content"]["application/json"]
+                ["example"],
+            json!({ "name": "Rex", "age": null })
+        );
+        assert_eq!(upgrade(&upgraded).unwrap(), upgraded);
+
+        let error = upgrade(&json!({ "openapi": "2.0" })).unwrap_err();
+        assert!(error.to_string().starts_with("Upgrade Failure: "));
+    }
+
+    #[test]
+    fn test_builder_upgrade() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(specification())
+            .upgrade_to_oas31(true)
+            .validate_examples(true)
+            .build()
+            .unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/pets")
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+        let pet = json!({ "name": "Rex", "age": null });
+        assert!(validator.validate_request(&request(pet), None).is_ok());
+        let pet = json!({ "name": "Rex", "age": 0 });
+        assert!(validator.validate_request(&request(pet), None).is_err());
+    }
+}
@@ -0,0 +1,449 @@
+use crate::dialect;
+use crate::document::{resolve_local_ref, MAX_REF_DEPTH};
+use crate::types::version::{OpenApiVersion, VersionError};
+use crate::types::{AccessModePolicy, Direction};
+use crate::validator::ValidationError;
+use crate::REQUIRED_FIELD;
+use jsonschema::{Draft, Resource};
+use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+const PROPERTIES_FIELD: &str = "properties";
+const READ_ONLY_FIELD: &str = "readOnly";
+const WRITE_ONLY_FIELD: &str = "writeOnly";
+
+/// Keywords whose subschemas apply to the same instance as the schema itself.
+const IN_PLACE_KEYWORDS: [&str; 3] = ["allOf", "anyOf", "oneOf"];
+
+/// How a validator treats `readOnly` and `writeOnly` properties.
+#[derive(Debug, Clone)]
+pub(crate) struct AccessModes {
+    pub(crate) policy: AccessModePolicy,
+    /// The id the specification is registered under, which the request and response views are
+    /// derived from.
+    pub(crate) root_id: String,
+    /// Whether the specification marks any property `readOnly`. When it does not, requests are
+    /// validated against the specification as is.
+    pub(crate) read_only: bool,
+    /// Whether the specification marks any property `writeOnly`. When it does not, responses are
+    /// validated against the specification as is.
+    pub(crate) write_only: bool,
+}
+
+impl AccessModes {
+    pub(crate) fn new(specification: &Value, root_id: &str, policy: AccessModePolicy) -> Self {
+        Self {
+            policy,
+            root_id: root_id.to_string(),
+            read_only: declares_access_mode(specification, READ_ONLY_FIELD),
+            write_only: declares_access_mode(specification, WRITE_ONLY_FIELD),
+        }
+    }
+
+    /// Whether a property is not allowed in `direction`, so that payloads travelling in it need
+    /// their own view of the specification.
+    pub(crate) fn restricts(&self, direction: Direction) -> bool {
+        match direction {
+            Direction::Request => self.read_only,
+            Direction::Response => self.write_only,
+        }
+    }
+
+    /// The resource payloads travelling in `direction` are validated against.
+    pub(crate) fn root_resource(&self, direction: Direction) -> String {
+        match self.restricts(direction) {
+            true => format!("{}-{}", self.root_id, direction),
+            false => self.root_id.clone(),
+        }
+    }
+
+    /// Applies the policy to a payload before it is validated against its schema.
+    ///
+    /// # Arguments
+    /// * `document` - The specification
+    /// * `schema` - The schema of the payload
+    /// * `instance` - The payload
+    /// * `direction` - The direction the payload travels in
+    /// * `operation_id` - The operation, for error reporting
+    ///
+    /// # Returns
+    /// * `Ok(Cow<Value>)` - The payload to validate, without the stripped properties
+    /// * `Err(ValidationError)` - If the policy rejects a property of the payload.
+    pub(crate) fn filter<'i>(
+        &self,
+        document: &Value,
+        schema: &Value,
+        instance: &'i Value,
+        direction: Direction,
+        operation_id: &str,
+    ) -> Result<Cow<'i, Value>, ValidationError> {
+        if !self.restricts(direction) {
+            return Ok(Cow::Borrowed(instance));
+        }
+        let mut filtered = instance.clone();
+        let strip = self.policy == AccessModePolicy::Strip;
+        let found = AccessModeFilter::new(document, direction).apply(schema, &mut filtered, strip);
+        if found.is_empty() {
+            return Ok(Cow::Borrowed(instance));
+        }
+        match self.policy {
+            AccessModePolicy::Strip => Ok(Cow::Owned(filtered)),
+            AccessModePolicy::Reject => Err(ValidationError::validation_error(
+                format!(
+                    "'{}' properties are not allowed in the {} body of operation '{}': {}",
+                    access_mode_field(direction),
+                    direction,
+                    operation_id,
+                    found.join(", ")
+                ),
+                instance,
+            )),
+        }
+    }
+}
+
+fn access_mode_field(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Request => READ_ONLY_FIELD,
+        Direction::Response => WRITE_ONLY_FIELD,
+    }
+}
+
+fn declares_access_mode(value: &Value, field: &str) -> bool {
+    match value {
+        Value::Object(map) => map.iter().any(|(key, value)| {
+            (key == field && value == &Value::Bool(true)) || declares_access_mode(value, field)
+        }),
+        Value::Array(items) => items.iter().any(|value| declares_access_mode(value, field)),
+        _ => false,
+    }
+}
+
+/// Creates the request and response views of a specification, in which `required` no longer
+/// lists the properties that are not allowed in that direction.
+///
+/// Each view is a full copy of the specification, so only the directions that restrict a
+/// property get one: a specification with `readOnly` properties only is copied once.
+///
+/// # Arguments
+/// * `version` - The version of the specification
+/// * `specification` - The normalized specification
+/// * `draft` - The draft the specification is compiled with
+/// * `access_modes` - The access modes of the validator
+///
+/// # Returns
+/// * `Ok(Vec<(String, Resource)>)` - The resources of the views, empty when no property
+///   declares an access mode
+/// * `Err(VersionError)` - If a schema declares an unsupported dialect.
+pub(crate) fn directional_resources(
+    version: &OpenApiVersion,
+    specification: &Value,
+    draft: Draft,
+    access_modes: &AccessModes,
+) -> Result<Vec<(String, Resource)>, VersionError> {
+    let mut resources = vec![];
+    for direction in [Direction::Request, Direction::Response] {
+        if !access_modes.restricts(direction) {
+            continue;
+        }
+        let root_id = access_modes.root_resource(direction);
+        let mut view = specification.clone();
+        exempt_required(specification, &mut view, direction);
+        view[dialect::id_keyword(draft)] = Value::String(root_id.clone());
+        resources.extend(version.create_resources(&view, &root_id, draft)?);
+    }
+    Ok(resources)
+}
+
+/// Removes the properties not allowed in `direction` from every `required` list of `value`.
+fn exempt_required(document: &Value, value: &mut Value, direction: Direction) {
+    match value {
+        Value::Object(map) => {
+            if let (Some(Value::Array(required)), Some(properties)) =
+                (map.get(REQUIRED_FIELD), map.get(PROPERTIES_FIELD))
+            {
+                let restricted = restricted_properties(document, properties, direction);
+                if required.iter().any(|name| restricted.contains(name)) {
+                    let required = required
+                        .iter()
+                        .filter(|name| !restricted.contains(*name))
+                        .cloned()
+                        .collect();
+                    map.insert(REQUIRED_FIELD.to_string(), Value::Array(required));
+                }
+            }
+            map.values_mut()
+                .for_each(|value| exempt_required(document, value, direction));
+        }
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|value| exempt_required(document, value, direction)),
+        _ => {}
+    }
+}
+
+/// Returns the names, as JSON strings, of the `properties` not allowed in `direction`.
+pub(crate) fn restricted_properties(
+    document: &Value,
+    properties: &Value,
+    direction: Direction,
+) -> HashSet<Value> {
+    match properties.as_object() {
+        Some(properties) => properties
+            .iter()
+            .filter(|(_, schema)| is_restricted(document, schema, direction, 0))
+            .map(|(name, _)| Value::String(name.clone()))
+            .collect(),
+        None => HashSet::new(),
+    }
+}
+
+/// Whether a property schema (or a schema it references or is composed of with `allOf`) is
+/// marked with the access mode not allowed in `direction`.
+fn is_restricted(document: &Value, schema: &Value, direction: Direction, depth: usize) -> bool {
+    if depth > MAX_REF_DEPTH {
+        return false;
+    }
+    if schema.get(access_mode_field(direction)) == Some(&Value::Bool(true)) {
+        return true;
+    }
+    if resolve_local_ref(document, schema)
+        .is_some_and(|resolved| is_restricted(document, resolved, direction, depth + 1))
+    {
+        return true;
+    }
+    match schema.get("allOf").and_then(Value::as_array) {
+        Some(subschemas) => subschemas
+            .iter()
+            .any(|subschema| is_restricted(document, subschema, direction, depth + 1)),
+        None => false,
+    }
+}
+
+/// Finds (and optionally removes) the properties of a payload that are not allowed in the
+/// direction it travels in.
+pub(crate) struct AccessModeFilter<'d> {
+    document: &'d Value,
+    direction: Direction,
+}
+
+impl<'d> AccessModeFilter<'d> {
+    pub(crate) fn new(document: &'d Value, direction: Direction) -> Self {
+        Self {
+            document,
+            direction,
+        }
+    }
+
+    /// Walks a payload alongside its schema.
+    ///
+    /// # Arguments
+    /// * `schema` - The schema of the payload
+    /// * `instance` - The payload
+    /// * `strip` - Whether to remove the properties that are found
+    ///
+    /// # Returns
+    /// The JSON pointers of the properties that are not allowed in the payload.
+    pub(crate) fn apply(&self, schema: &Value, instance: &mut Value, strip: bool) -> Vec<String> {
+        let mut found = vec![];
+        self.walk(schema, instance, "", strip, &mut found, 0);
+        found
+    }
+
+    fn walk(
+        &self,
+        schema: &Value,
+        instance: &mut Value,
+        pointer: &str,
+        strip: bool,
+        found: &mut Vec<String>,
+        depth: usize,
+    ) {
+        if depth > MAX_REF_DEPTH {
+            return;
+        }
+        if let Some(resolved) = resolve_local_ref(self.document, schema) {
+            self.walk(resolved, instance, pointer, strip, found, depth + 1);
+        }
+        for keyword in IN_PLACE_KEYWORDS {
+            if let Some(Value::Array(subschemas)) = schema.get(keyword) {
+                for subschema in subschemas {
+                    self.walk(subschema, instance, pointer, strip, found, depth + 1);
+                }
+            }
+        }
+
+        match instance {
+            Value::Object(map) => {
+                let mut restricted = vec![];
+                for (name, value) in map.iter_mut() {
+                    let property_schema = match property_schema(schema, name) {
+                        Some(property_schema) => property_schema,
+                        None => continue,
+                    };
+                    let property_pointer = format!(
+                        "{}/{}",
+                        pointer,
+                        name.replace('~', "~0").replace('/', "~1")
+                    );
+                    if is_restricted(self.document, property_schema, self.direction, 0) {
+                        if !found.contains(&property_pointer) {
+                            found.push(property_pointer);
+                        }
+                        restricted.push(name.clone());
+                    } else {
+                        self.walk(property_schema, value, &property_pointer, strip, found, 0);
+                    }
+                }
+                if strip {
+                    restricted.iter().for_each(|name| {
+                        map.remove(name);
+                    });
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter_mut().enumerate() {
+                    let item_schema = match item_schema(schema, index) {
+                        Some(item_schema) => item_schema,
+                        None => continue,
+                    };
+                    let item_pointer = format!("{}/{}", pointer, index);
+                    self.walk(item_schema, item, &item_pointer, strip, found, 0);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The schema of an object property, from `properties` or else `additionalProperties`.
+pub(crate) fn property_schema<'s>(schema: &'s Value, name: &str) -> Option<&'s Value> {
+    if let Some(property_schema) = schema
+        .get(PROPERTIES_FIELD)
+        .and_then(|properties| properties.get(name))
+    {
+        return Some(property_schema);
+    }
+    schema
+        .get("additionalProperties")
+        .filter(|additional| additional.is_object())
+}
+
+/// The schema of an array item, from `prefixItems` or else `items`.
+pub(crate) fn item_schema(schema: &Value, index: usize) -> Option<&Value> {
+    if let Some(item_schema) = schema
+        .get("prefixItems")
+        .and_then(|prefix_items| prefix_items.get(index))
+    {
+        return Some(item_schema);
+    }
+    match schema.get("items") {
+        Some(Value::Array(items)) => items.get(index),
+        Some(items) if items.is_object() => Some(items),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "components": {
+                "schemas": {
+                    "Id": { "type": "integer", "readOnly": true },
+                    "Pet": {
+                        "type": "object",
+                        "required": ["id", "name", "password"],
+                        "properties": {
+                            "id": { "$ref": "#/components/schemas/Id" },
+                            "name": { "type": "string" },
+                            "password": { "type": "string", "writeOnly": true },
+                            "tags": {
+                                "type": "array",
+                                "items": {
+                                    "properties": {
+                                        "created": { "type": "string", "readOnly": true }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn test_filter_request() {
+        let document = document();
+        let schema = json!({ "$ref": "#/components/schemas/Pet" });
+        let mut pet = json!({
+            "id": 1,
+            "name": "Rex",
+            "password": "secret",
+            "tags": [{ "created": "today" }, {}]
+        });
+        let filter = AccessModeFilter::new(&document, Direction::Request);
+        assert_eq!(
+            filter.apply(&schema, &mut pet.clone(), false),
+            vec!["/id".to_string(), "/tags/0/created".to_string()]
+        );
+        filter.apply(&schema, &mut pet, true);
+        assert_eq!(
+            pet,
+            json!({ "name": "Rex", "password": "secret", "tags": [{}, {}] })
+        );
+    }
+
+    #[test]
+    fn test_filter_response() {
+        let document = document();
+        let schema = json!({ "allOf": [{ "$ref": "#/components/schemas/Pet" }] });
+        let mut pet = json!({ "id": 1, "name": "Rex", "password": "secret" });
+        let filter = AccessModeFilter::new(&document, Direction::Response);
+        assert_eq!(filter.apply(&schema, &mut pet, true), vec!["/password"]);
+        assert_eq!(pet, json!({ "id": 1, "name": "Rex" }));
+    }
+
+    #[test]
+    fn test_exempt_required() {
+        let document = document();
+        let mut view = document.clone();
+        exempt_required(&document, &mut view, Direction::Request);
+        assert_eq!(
+            view["components"]["schemas"]["Pet"]["required"],
+            json!(["name", "password"])
+        );
+        let mut view = document.clone();
+        exempt_required(&document, &mut view, Direction::Response);
+        assert_eq!(
+            view["components"]["schemas"]["Pet"]["required"],
+            json!(["id", "name"])
+        );
+    }
+
+    #[test]
+    fn test_root_resources() {
+        let access_modes = AccessModes::new(&document(), "custom", AccessModePolicy::Reject);
+        assert_eq!(access_modes.root_resource(Direction::Request), "custom-request");
+        assert_eq!(access_modes.root_resource(Direction::Response), "custom-response");
+
+        let document = json!({ "properties": { "id": { "readOnly": true } } });
+        let access_modes = AccessModes::new(&document, "custom", AccessModePolicy::Reject);
+        assert_eq!(access_modes.root_resource(Direction::Request), "custom-request");
+        assert_eq!(access_modes.root_resource(Direction::Response), "custom");
+        let views = directional_resources(
+            &OpenApiVersion::V31x,
+            &document,
+            Draft::Draft202012,
+            &access_modes,
+        )
+        .unwrap();
+        let ids: Vec<&str> = views.iter().map(|(id, _)| id.as_str()).collect();
+        assert_eq!(ids, vec!["custom-request"]);
+    }
+}
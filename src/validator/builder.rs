@@ -1,11 +1,34 @@
+use crate::dialect;
+use crate::external::{self, ResolverRetriever};
+#[cfg(feature = "jwt")]
+use crate::jwt::JwtVerifier;
+use crate::loader::{self, SpecificationFormat};
+use crate::meta_schema::{self, SpecificationError};
+use crate::overlay::{Overlay, OverlayError};
+use crate::swagger;
 use crate::traverser::OpenApiTraverser;
+use crate::types::filter::{OperationFilter, OperationMatcher};
+use crate::types::resolver::ReferenceResolver;
+use crate::types::security::SecurityHandler;
 use crate::types::version::OpenApiVersion;
+use crate::types::AccessModePolicy;
+use crate::validator::access_mode::{self, AccessModes};
+use crate::validator::custom::{CustomValidators, RequestValidator};
+use crate::validator::example::{self, ExampleError};
+use crate::validator::security::{RequestSecurityValidator, SecurityHandlers};
 use crate::validator::OpenApiPayloadValidator;
-use crate::OPENAPI_FIELD;
-use jsonschema::{Draft, Resource, Validator as JsonValidator};
-use serde_json::Value;
+use crate::{OPENAPI_FIELD, ROOT_RESOURCE};
+use jsonschema::paths::Location;
+use jsonschema::{
+    Keyword, ValidationError as JsonSchemaError, ValidationOptions, Validator as JsonValidator,
+};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::Arc;
 
 #[derive(Debug)]
 pub enum ValidatorBuilderError {
@@ -13,6 +36,12 @@ pub enum ValidatorBuilderError {
     InvalidVersion(String),
     InvalidSpecification(String),
     LoadFailure(String),
+    /// The specification does not conform to the OpenAPI meta-schema, see `validate_specification`.
+    NonConformant(Vec<SpecificationError>),
+    /// Examples of the specification do not validate against their schemas, see `validate_examples`.
+    InvalidExamples(Vec<ExampleError>),
+    /// Actions of an overlay could not be applied, see `overlay`.
+    InvalidOverlay(Vec<OverlayError>),
 }
 
 impl ValidatorBuilderError {
@@ -48,6 +77,27 @@ impl Display for ValidatorBuilderError {
             ValidatorBuilderError::LoadFailure(msg) => {
                 write!(f, "Load Failure: {}", msg)
             }
+            ValidatorBuilderError::NonConformant(errors) => {
+                write!(f, "Non-Conformant Specification: {} error(s)", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+            ValidatorBuilderError::InvalidExamples(errors) => {
+                write!(f, "Invalid Examples: {} example(s)", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+            ValidatorBuilderError::InvalidOverlay(errors) => {
+                write!(f, "Invalid Overlay: {} action(s)", errors.len())?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
         }
     }
 }
@@ -57,14 +107,33 @@ impl std::error::Error for ValidatorBuilderError {}
 enum SpecificationLoader {
     None,
     File(String),
-    //    Raw(String),
-    //    External(String),
+    Raw(String),
+    Bytes(Vec<u8>),
+    Value(Value),
+    Unreadable(String),
 }
 
+/// Applies a registered format, keyword or switch to the options the validator is built with.
+type OptionsHook = Box<dyn FnOnce(ValidationOptions) -> ValidationOptions + Send>;
+
 pub struct OpenApiPayloadValidatorBuilder {
     specification_loader: SpecificationLoader,
     version: Option<OpenApiVersion>,
     root_id: Value,
+    security_handlers: SecurityHandlers,
+    #[cfg(feature = "jwt")]
+    jwt_verifier: Option<Arc<JwtVerifier>>,
+    custom_validators: CustomValidators,
+    enforce_security: Option<bool>,
+    options_hooks: Vec<OptionsHook>,
+    access_mode_policy: AccessModePolicy,
+    sandbox: Option<PathBuf>,
+    reference_resolver: Option<Arc<dyn ReferenceResolver>>,
+    validate_specification: bool,
+    validate_examples: bool,
+    upgrade_to_oas31: bool,
+    overlays: Vec<Overlay>,
+    operation_filter: OperationFilter,
 }
 
 impl OpenApiPayloadValidatorBuilder {
@@ -72,7 +141,21 @@ impl OpenApiPayloadValidatorBuilder {
         Self {
             specification_loader: SpecificationLoader::None,
             version: None,
-            root_id: Value::String(String::from("@@root")),
+            root_id: Value::String(String::from(ROOT_RESOURCE)),
+            security_handlers: HashMap::new(),
+            #[cfg(feature = "jwt")]
+            jwt_verifier: None,
+            custom_validators: CustomValidators::default(),
+            enforce_security: None,
+            options_hooks: vec![],
+            access_mode_policy: AccessModePolicy::default(),
+            sandbox: None,
+            reference_resolver: None,
+            validate_specification: false,
+            validate_examples: false,
+            upgrade_to_oas31: false,
+            overlays: vec![],
+            operation_filter: OperationFilter::default(),
         }
     }
 
@@ -91,12 +174,251 @@ impl OpenApiPayloadValidatorBuilder {
         self
     }
 
+    /// Loads the specification from a JSON or YAML file. The format is taken from the extension
+    /// (`.json`, `.yaml` or `.yml`), or else detected from the content.
     pub fn load_from_file(mut self, path: impl Into<String>) -> Self {
         self.specification_loader = SpecificationLoader::File(path.into());
         self
     }
 
-    fn resolve_draft(spec: &Value) -> Result<Draft, ValidatorBuilderError> {
+    /// Loads the specification from a JSON or YAML string. The format is detected from the content.
+    pub fn load_from_str(mut self, content: impl Into<String>) -> Self {
+        self.specification_loader = SpecificationLoader::Raw(content.into());
+        self
+    }
+
+    /// Loads the specification from UTF-8 encoded JSON or YAML bytes. The format is detected from
+    /// the content.
+    pub fn load_from_bytes(mut self, bytes: impl Into<Vec<u8>>) -> Self {
+        self.specification_loader = SpecificationLoader::Bytes(bytes.into());
+        self
+    }
+
+    /// Loads the specification from a reader of UTF-8 encoded JSON or YAML. The reader is read
+    /// to the end right away, a read error fails the build.
+    pub fn load_from_reader(mut self, mut reader: impl Read) -> Self {
+        let mut bytes = vec![];
+        self.specification_loader = match reader.read_to_end(&mut bytes) {
+            Ok(_) => SpecificationLoader::Bytes(bytes),
+            Err(e) => SpecificationLoader::Unreadable(e.to_string()),
+        };
+        self
+    }
+
+    /// Loads the specification from an already parsed document.
+    pub fn load_from_value(mut self, specification: Value) -> Self {
+        self.specification_loader = SpecificationLoader::Value(specification);
+        self
+    }
+
+    /// Restricts the files a specification loaded with `load_from_file` can reference to those
+    /// inside `directory`.
+    ///
+    /// Relative file references (e.g. `./schemas/user.yaml#/User`) are resolved against the
+    /// directory of the referring document. Those of a specification that was not loaded from a
+    /// file are resolved against the sandbox, or else the current directory. Without a sandbox,
+    /// any readable file can be referenced.
+    pub fn sandbox(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.sandbox = Some(directory.into());
+        self
+    }
+
+    /// Registers the resolver that serves the documents of `$ref` URIs that are not files
+    /// (e.g. `urn:schemas:money` or `https://schemas.internal/money.json`).
+    ///
+    /// Without a resolver, references to anything but files fail the build.
+    pub fn reference_resolver(mut self, resolver: impl ReferenceResolver + 'static) -> Self {
+        self.reference_resolver = Some(Arc::new(resolver));
+        self
+    }
+
+    /// Turns validation of the specification itself on or off. This is off by default.
+    ///
+    /// When on, the build fails with every structural error the bundled OAS 3.0/3.1 meta-schema
+    /// finds (e.g. a parameter without `in`, or a `requestBody` without `content`), every local
+    /// `$ref` that does not resolve, and every path template expression without a matching path
+    /// parameter, instead of a request hitting them later.
+    pub fn validate_specification(mut self, validate_specification: bool) -> Self {
+        self.validate_specification = validate_specification;
+        self
+    }
+
+    /// Turns validation of the examples of the specification on or off. This is off by default.
+    ///
+    /// When on, the build fails with every `example` and `examples` entry of a media type,
+    /// parameter, header or schema that does not validate against its schema, e.g. an example
+    /// that drifted from a schema it was written for. Examples are validated the way payloads
+    /// are, those of request and response bodies with their `readOnly`/`writeOnly` views.
+    pub fn validate_examples(mut self, validate_examples: bool) -> Self {
+        self.validate_examples = validate_examples;
+        self
+    }
+
+    /// Adds an overlay to apply to the specification once it has been loaded, before anything is
+    /// validated or compiled from it. Overlays are applied in the order they are added.
+    ///
+    /// The build fails with every action whose target matched nothing.
+    pub fn overlay(mut self, overlay: Overlay) -> Self {
+        self.overlays.push(overlay);
+        self
+    }
+
+    /// Serves the operations matching `matcher` (by tag, operationId, path prefix or `x-`
+    /// extension). Once an operation is included, the operations matching no `include_operations`
+    /// are not served.
+    ///
+    /// Requests to operations that are not served fail with `OperationNotServed`, rather than
+    /// `PathNotFound`.
+    pub fn include_operations(mut self, matcher: OperationMatcher) -> Self {
+        self.operation_filter = self.operation_filter.include(matcher);
+        self
+    }
+
+    /// Does not serve the operations matching `matcher`, even if they are included.
+    pub fn exclude_operations(mut self, matcher: OperationMatcher) -> Self {
+        self.operation_filter = self.operation_filter.exclude(matcher);
+        self
+    }
+
+    /// Turns upgrading OpenAPI 3.0 specifications to 3.1 on or off. This is off by default.
+    ///
+    /// When on, a 3.0 specification is rewritten as `oasert::upgrade::upgrade` does once it has
+    /// been loaded (and validated, with `validate_specification`), and its schemas are compiled
+    /// as Draft 2020-12 schemas instead of Draft 4 ones.
+    pub fn upgrade_to_oas31(mut self, upgrade_to_oas31: bool) -> Self {
+        self.upgrade_to_oas31 = upgrade_to_oas31;
+        self
+    }
+
+    /// Sets what happens to `readOnly` properties in request bodies and `writeOnly` properties
+    /// in response bodies. These are rejected by default.
+    pub fn access_mode_policy(mut self, policy: AccessModePolicy) -> Self {
+        self.access_mode_policy = policy;
+        self
+    }
+
+    /// Registers the handler that verifies credentials for the `securitySchemes` entry `scheme_name`.
+    ///
+    /// Registering a handler for a scheme the specification does not define fails the build.
+    pub fn security_handler(
+        mut self,
+        scheme_name: impl Into<String>,
+        handler: impl SecurityHandler + 'static,
+    ) -> Self {
+        self.security_handlers
+            .insert(scheme_name.into(), Arc::new(handler));
+        self
+    }
+
+    /// Turns enforcement of the `security` requirements of operations on or off.
+    ///
+    /// By default, requirements are enforced once a `SecurityHandler` or `JwtVerifier` is
+    /// registered. When on, `validate_request` rejects requests without the credentials of any
+    /// requirement even with nothing registered, the credentials present being accepted as is.
+    /// When off, requirements are not evaluated at all, and requests validated with
+    /// caller-provided scopes are only checked against those. When enforced, these scopes are
+    /// granted to the credentials of the schemes nothing verifies.
+    pub fn enforce_security(mut self, enforce_security: bool) -> Self {
+        self.enforce_security = Some(enforce_security);
+        self
+    }
+
+    /// Registers the verifier for the bearer tokens of `oauth2` and `openIdConnect` schemes.
+    ///
+    /// `validate_request` then verifies these tokens, unless a `SecurityHandler` is registered
+    /// for their scheme, and a token only satisfies a scheme when it grants every scope the
+    /// requirement lists for it.
+    #[cfg(feature = "jwt")]
+    pub fn jwt_verifier(mut self, verifier: JwtVerifier) -> Self {
+        self.jwt_verifier = Some(Arc::new(verifier));
+        self
+    }
+
+    /// Registers a custom validator that runs for every operation.
+    pub fn validator(mut self, validator: impl RequestValidator + 'static) -> Self {
+        self.custom_validators.global.push(Arc::new(validator));
+        self
+    }
+
+    /// Registers a custom validator that runs for the operation with the given `operationId`.
+    pub fn operation_validator(
+        mut self,
+        operation_id: impl Into<String>,
+        validator: impl RequestValidator + 'static,
+    ) -> Self {
+        self.custom_validators
+            .by_operation_id
+            .entry(operation_id.into())
+            .or_default()
+            .push(Arc::new(validator));
+        self
+    }
+
+    /// Registers a custom validator that runs for every operation with the given tag.
+    pub fn tag_validator(
+        mut self,
+        tag: impl Into<String>,
+        validator: impl RequestValidator + 'static,
+    ) -> Self {
+        self.custom_validators
+            .by_tag
+            .entry(tag.into())
+            .or_default()
+            .push(Arc::new(validator));
+        self
+    }
+
+    /// Registers a checker for a custom `format` (e.g. `iban`, `e164`, `ulid`).
+    ///
+    /// Formats are only asserted when format assertion is on, see `assert_formats`.
+    pub fn format<F>(mut self, name: impl Into<String>, checker: F) -> Self
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        let name = name.into();
+        self.options_hooks
+            .push(Box::new(move |options| options.with_format(name, checker)));
+        self
+    }
+
+    /// Registers a custom keyword (e.g. `x-oasert-max-depth`).
+    ///
+    /// The factory receives the parent schema object, the value of the keyword and its
+    /// location, and returns the `Keyword` that validates instances.
+    pub fn keyword<F>(mut self, name: impl Into<String>, factory: F) -> Self
+    where
+        F: for<'a> Fn(
+                &'a Map<String, Value>,
+                &'a Value,
+                Location,
+            ) -> Result<Box<dyn Keyword>, JsonSchemaError<'a>>
+            + Send
+            + Sync
+            + 'static,
+    {
+        let name = name.into();
+        self.options_hooks
+            .push(Box::new(move |options| options.with_keyword(name, factory)));
+        self
+    }
+
+    /// Turns `format` assertion on or off.
+    ///
+    /// Draft 2020-12 (OpenAPI 3.1) treats `format` as an annotation by default, so formats like
+    /// `date-time` or `uuid` are not checked unless this is turned on.
+    pub fn assert_formats(mut self, assert_formats: bool) -> Self {
+        self.options_hooks.push(Box::new(move |options| {
+            options.should_validate_formats(assert_formats)
+        }));
+        self
+    }
+
+    fn resolve_version(spec: &Value) -> Result<OpenApiVersion, ValidatorBuilderError> {
+        if swagger::is_swagger(spec) {
+            return Err(ValidatorBuilderError::invalid_version(
+                "Swagger 2.0 is not supported, convert it with `oasert::swagger::convert`",
+            ));
+        }
         let version = match OpenApiTraverser::get_as_str(&spec, OPENAPI_FIELD) {
             Ok(version) => version,
             Err(e) => return Err(ValidatorBuilderError::invalid_specification(e.to_string())),
@@ -105,55 +427,639 @@ impl OpenApiPayloadValidatorBuilder {
             Ok(version) => version,
             Err(e) => return Err(ValidatorBuilderError::invalid_version(e.to_string())),
         };
-        Ok(version.get_draft())
+        Ok(version)
     }
 
-    pub fn build(self) -> Result<OpenApiPayloadValidator, ValidatorBuilderError> {
-        let mut spec = match self.specification_loader {
+    pub fn build(mut self) -> Result<OpenApiPayloadValidator, ValidatorBuilderError> {
+        let loader = std::mem::replace(&mut self.specification_loader, SpecificationLoader::None);
+        let mut spec = match loader {
             SpecificationLoader::None => {
                 return Err(ValidatorBuilderError::invalid_option(
                     "No specification loader provided.",
                 ));
             }
-            SpecificationLoader::File(path) => Self::load_file_spec(path)?,
+            SpecificationLoader::File(path) => {
+                let content = match std::fs::read_to_string(&path) {
+                    Ok(content) => content,
+                    Err(e) => return Err(ValidatorBuilderError::load_failure(e.to_string())),
+                };
+                let format = SpecificationFormat::from_path(&path);
+                let specification = Self::parse_spec(&content, format)?;
+                self.embed_documents(specification, Some(Path::new(&path)))?
+            }
+            SpecificationLoader::Raw(content) => {
+                let specification = Self::parse_spec(&content, None)?;
+                self.embed_documents(specification, None)?
+            }
+            SpecificationLoader::Bytes(bytes) => {
+                let content = match String::from_utf8(bytes) {
+                    Ok(content) => content,
+                    Err(e) => return Err(ValidatorBuilderError::load_failure(e.to_string())),
+                };
+                let specification = Self::parse_spec(&content, None)?;
+                self.embed_documents(specification, None)?
+            }
+            SpecificationLoader::Value(specification) => {
+                self.embed_documents(specification, None)?
+            }
+            SpecificationLoader::Unreadable(e) => {
+                return Err(ValidatorBuilderError::load_failure(e));
+            }
         };
 
-        spec["$id"] = self.root_id;
-        let draft = if self.version.is_none() {
-            Self::resolve_draft(&spec)?
-        } else {
-            self.version.unwrap().get_draft()
+        let version = match self.version {
+            Some(version) => version,
+            None => Self::resolve_version(&spec)?,
+        };
+        let conformance = match self.validate_specification {
+            true => meta_schema::check_specification(&spec, &version),
+            false => Ok(()),
+        };
+        if let Err(errors) = conformance {
+            return Err(ValidatorBuilderError::NonConformant(errors));
+        }
+        let version = match (self.upgrade_to_oas31, version) {
+            (true, OpenApiVersion::V30x) => {
+                dialect::upgrade_oas30_document(&mut spec);
+                OpenApiVersion::V31x
+            }
+            (_, version) => version,
+        };
+        let draft = match version.resolve_draft(&spec) {
+            Ok(draft) => draft,
+            Err(e) => return Err(ValidatorBuilderError::invalid_version(e.to_string())),
         };
+        let root_id = match self.root_id.as_str() {
+            Some(root_id) => root_id.to_string(),
+            None => return Err(ValidatorBuilderError::invalid_option("Root id must be a string.")),
+        };
+        // Examples are found before normalization, which drops the 'example' of 3.0 schemas.
+        let examples = match self.validate_examples {
+            true => example::find_examples(&spec),
+            false => vec![],
+        };
+        spec[dialect::id_keyword(draft)] = self.root_id;
+        version.normalize_specification(&mut spec);
 
-        // Create this resource once and re-use it for multiple validation calls.
-        let resource = match Resource::from_contents(spec.clone()) {
-            Ok(res) => res,
+        // Create these resources once and re-use them for multiple validation calls.
+        // The draft is set explicitly, the specification does not declare it with '$schema'.
+        let mut resources = match version.create_resources(&spec, &root_id, draft) {
+            Ok(resources) => resources,
             Err(e) => return Err(ValidatorBuilderError::invalid_specification(e.to_string())),
         };
 
-        // Assign draft and provide resource
-        let options = JsonValidator::options()
+        // Request and response views, in which 'required' skips 'readOnly'/'writeOnly' properties.
+        let access_modes = AccessModes::new(&spec, &root_id, self.access_mode_policy);
+        match access_mode::directional_resources(&version, &spec, draft, &access_modes) {
+            Ok(views) => resources.extend(views),
+            Err(e) => return Err(ValidatorBuilderError::invalid_specification(e.to_string())),
+        }
+
+        // Assign draft and provide resources
+        let mut options = JsonValidator::options()
             .with_draft(draft)
-            .with_resource("@@inner", resource);
+            .with_resources(resources.into_iter());
+        if let Some(resolver) = self.reference_resolver {
+            options = options.with_retriever(ResolverRetriever(resolver));
+        }
+        for hook in self.options_hooks {
+            options = hook(options);
+        }
+
+        if let Err(errors) = example::check_examples(&examples, &spec, &options, &access_modes) {
+            return Err(ValidatorBuilderError::InvalidExamples(errors));
+        }
 
         // Create the traverser with owned value
-        let traverser = match OpenApiTraverser::new(spec) {
+        let traverser = match OpenApiTraverser::with_filter(spec, &self.operation_filter) {
             Ok(traverser) => traverser,
             Err(e) => return Err(ValidatorBuilderError::invalid_specification(e.to_string())),
         };
 
-        Ok(OpenApiPayloadValidator { traverser, options })
+        for scheme_name in self.security_handlers.keys() {
+            if let Err(e) = RequestSecurityValidator::resolve_scheme(&traverser, scheme_name) {
+                return Err(ValidatorBuilderError::invalid_option(format!(
+                    "Security handler registered for unknown scheme '{}': {}",
+                    scheme_name, e
+                )));
+            }
+        }
+
+        #[cfg(feature = "jwt")]
+        let verifies_credentials = self.jwt_verifier.is_some() || !self.security_handlers.is_empty();
+        #[cfg(not(feature = "jwt"))]
+        let verifies_credentials = !self.security_handlers.is_empty();
+        let enforce_security = self.enforce_security.unwrap_or(verifies_credentials);
+
+        Ok(OpenApiPayloadValidator {
+            traverser,
+            options,
+            security_handlers: self.security_handlers,
+            #[cfg(feature = "jwt")]
+            jwt_verifier: self.jwt_verifier,
+            custom_validators: self.custom_validators,
+            enforce_security,
+            access_modes,
+        })
+    }
+
+    fn parse_spec(
+        content: &str,
+        format: Option<SpecificationFormat>,
+    ) -> Result<Value, ValidatorBuilderError> {
+        match loader::parse_specification(content, format) {
+            Ok(specification) => Ok(specification),
+            Err(e) => Err(ValidatorBuilderError::invalid_specification(e.to_string())),
+        }
+    }
+
+    /// Applies the overlays to the specification, then embeds the documents it references, see
+    /// `overlay`, `sandbox` and `reference_resolver`.
+    ///
+    /// # Arguments
+    /// * `specification` - The root document
+    /// * `path` - The file the root document was loaded from, `None` if it was not loaded from a file
+    fn embed_documents(
+        &self,
+        mut specification: Value,
+        path: Option<&Path>,
+    ) -> Result<Value, ValidatorBuilderError> {
+        for overlay in &self.overlays {
+            if let Err(errors) = overlay.apply(&mut specification) {
+                return Err(ValidatorBuilderError::InvalidOverlay(errors));
+            }
+        }
+        if let Err(e) = external::embed_external_documents(
+            &mut specification,
+            path,
+            self.sandbox.as_deref(),
+            self.reference_resolver.as_deref(),
+        ) {
+            return Err(ValidatorBuilderError::load_failure(e));
+        }
+        Ok(specification)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::Request;
+    use jsonschema::paths::LazyLocation;
+    use serde_json::json;
+    use crate::traverser::TraverserError;
+    use crate::types::resolver::InMemoryResolver;
+    use crate::validator::ValidationError;
+
+    const SPEC: &str = "./test/openapi-v3.1.0-formats.json";
+
+    struct MaxDepth(u64);
+
+    impl MaxDepth {
+        fn depth(value: &Value) -> u64 {
+            match value {
+                Value::Object(map) => 1 + map.values().map(Self::depth).max().unwrap_or(0),
+                Value::Array(items) => 1 + items.iter().map(Self::depth).max().unwrap_or(0),
+                _ => 0,
+            }
+        }
+    }
+
+    impl Keyword for MaxDepth {
+        fn validate<'i>(
+            &self,
+            instance: &'i Value,
+            location: &LazyLocation,
+        ) -> Result<(), JsonSchemaError<'i>> {
+            if self.is_valid(instance) {
+                return Ok(());
+            }
+            Err(JsonSchemaError::custom(
+                Location::new(),
+                location.into(),
+                instance,
+                format!("nested deeper than {}", self.0),
+            ))
+        }
+
+        fn is_valid(&self, instance: &Value) -> bool {
+            Self::depth(instance) <= self.0
+        }
+    }
+
+    fn request(body: Value) -> Request<Value> {
+        Request::builder()
+            .method("POST")
+            .uri("/accounts")
+            .header("Content-Type", "application/json")
+            .body(body)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_format_assertion_switch() {
+        let body = json!({ "createdAt": "yesterday" });
+
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file(SPEC)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&request(body.clone()), None).is_ok());
+
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file(SPEC)
+            .assert_formats(true)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&request(body), None).is_err());
+        let body = json!({ "createdAt": "2024-01-01T00:00:00Z" });
+        assert!(validator.validate_request(&request(body), None).is_ok());
     }
 
-    fn load_file_spec(path: String) -> Result<Value, ValidatorBuilderError> {
-        let content = match std::fs::read_to_string(&path) {
-            Ok(content) => content,
-            Err(e) => return Err(ValidatorBuilderError::load_failure(e.to_string())),
+    #[test]
+    fn test_custom_format() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file(SPEC)
+            .assert_formats(true)
+            .format("iban", |value| {
+                value.len() >= 15 && value[..2].chars().all(|c| c.is_ascii_uppercase())
+            })
+            .build()
+            .unwrap();
+        let body = json!({ "iban": "DE89370400440532013000" });
+        assert!(validator.validate_request(&request(body), None).is_ok());
+        let body = json!({ "iban": "not-an-iban" });
+        assert!(validator.validate_request(&request(body), None).is_err());
+    }
+
+    #[test]
+    #[allow(clippy::result_large_err)]
+    fn test_custom_keyword() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file(SPEC)
+            .keyword("x-oasert-max-depth", |_, value, _| {
+                Ok(Box::new(MaxDepth(value.as_u64().unwrap_or(u64::MAX))))
+            })
+            .build()
+            .unwrap();
+        let body = json!({ "metadata": { "a": { "b": 1 } } });
+        assert!(validator.validate_request(&request(body), None).is_ok());
+        let body = json!({ "metadata": { "a": { "b": { "c": 1 } } } });
+        assert!(validator.validate_request(&request(body), None).is_err());
+    }
+
+    #[test]
+    fn test_load_yaml() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.0.3-pets.yaml")
+            .build()
+            .unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/pets")
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap()
         };
-        let specification = match serde_json::from_str(&content) {
-            Ok(specification) => specification,
-            Err(e) => return Err(ValidatorBuilderError::invalid_specification(e.to_string())),
+        let body = json!({ "name": "Rex", "mood": "on" });
+        assert!(validator.validate_request(&request(body), None).is_ok());
+        let body = json!({ "name": "Rex", "mood": true });
+        assert!(validator.validate_request(&request(body), None).is_err());
+        // 'required' is merged in from the 'Named' anchor
+        assert!(validator.validate_request(&request(json!({})), None).is_err());
+
+        let operation = validator.find_operation("/pets", "POST").unwrap();
+        assert!(operation.data["responses"]["201"].is_object());
+        assert_eq!(
+            operation.data["responses"]["4XX"]["description"],
+            json!("Error")
+        );
+    }
+
+    #[test]
+    fn test_multi_file_specification() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/multi-file/openapi.yaml")
+            .sandbox("./test/multi-file")
+            .build()
+            .unwrap();
+        let request = |trace_id: &str, body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/users")
+                .header("Content-Type", "application/json")
+                .header("x-trace-id", trace_id)
+                .body(body)
+                .unwrap()
         };
-        Ok(specification)
+        let user = json!({
+            "id": 1,
+            "name": "a",
+            "nickname": null,
+            "address": { "street": "b", "resident": { "id": 2, "name": "c" } }
+        });
+        assert!(validator.validate_request(&request("abcd", user), None).is_ok());
+        let invalid_id = json!({ "id": 0, "name": "a" });
+        assert!(validator.validate_request(&request("abcd", invalid_id), None).is_err());
+        let invalid_resident = json!({ "id": 1, "name": "a", "address": { "street": "b", "resident": {} } });
+        assert!(validator.validate_request(&request("abcd", invalid_resident), None).is_err());
+        let user = json!({ "id": 1, "name": "a" });
+        assert!(validator.validate_request(&request("abc", user), None).is_err());
+
+        let outside = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/multi-file/openapi.yaml")
+            .sandbox("./test/multi-file/schemas")
+            .build();
+        assert!(matches!(outside, Err(ValidatorBuilderError::LoadFailure(_))));
+    }
+
+    #[test]
+    fn test_reference_resolver() {
+        let resolver = InMemoryResolver::new()
+            .with_document(
+                "urn:schemas:money",
+                json!({ "Money": { "type": "string", "pattern": "^[0-9]+\\.[0-9]{2}$" } }),
+            )
+            .with_document(
+                "https://schemas.internal/lib/party.json",
+                json!({ "Party": {
+                    "type": "object",
+                    "properties": { "iban": { "$ref": "../common/iban.json" } }
+                } }),
+            )
+            .with_document(
+                "https://schemas.internal/common/iban.json",
+                json!({ "type": "string", "minLength": 15 }),
+            );
+        let request = |body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/payments")
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.1.0-resolver.yaml")
+            .reference_resolver(resolver)
+            .build()
+            .unwrap();
+        let body = json!({ "amount": "10.00", "payee": { "iban": "DE89370400440532013000" } });
+        assert!(validator.validate_request(&request(body), None).is_ok());
+        let body = json!({ "amount": "10", "payee": {} });
+        assert!(validator.validate_request(&request(body), None).is_err());
+        let body = json!({ "amount": "10.00", "payee": { "iban": "DE89" } });
+        assert!(validator.validate_request(&request(body), None).is_err());
+
+        let unresolved = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.1.0-resolver.yaml")
+            .build();
+        assert!(matches!(unresolved, Err(ValidatorBuilderError::LoadFailure(_))));
+    }
+
+    #[test]
+    fn test_load_from_memory() {
+        const MULTI_FILE_SPEC: &str = "./test/multi-file/openapi.yaml";
+        let content = std::fs::read_to_string(MULTI_FILE_SPEC).unwrap();
+        let value = loader::parse_specification(&content, None).unwrap();
+        let builders = vec![
+            OpenApiPayloadValidatorBuilder::new().load_from_str(content.as_str()),
+            OpenApiPayloadValidatorBuilder::new().load_from_bytes(content.as_bytes()),
+            OpenApiPayloadValidatorBuilder::new().load_from_reader(content.as_bytes()),
+            OpenApiPayloadValidatorBuilder::new().load_from_value(value),
+        ];
+        let request = |body: Value| {
+            Request::builder()
+                .method("POST")
+                .uri("/users")
+                .header("Content-Type", "application/json")
+                .header("x-trace-id", "abcd")
+                .body(body)
+                .unwrap()
+        };
+        for builder in builders {
+            // Relative references of an in-memory specification resolve against the sandbox.
+            let validator = builder.sandbox("./test/multi-file").build().unwrap();
+            let user = json!({ "id": 1, "name": "a" });
+            assert!(validator.validate_request(&request(user), None).is_ok());
+            let invalid_id = json!({ "id": 0, "name": "a" });
+            assert!(validator.validate_request(&request(invalid_id), None).is_err());
+        }
+
+        let invalid_utf8 = OpenApiPayloadValidatorBuilder::new()
+            .load_from_bytes(vec![0xff, 0xfe])
+            .build();
+        assert!(matches!(invalid_utf8, Err(ValidatorBuilderError::LoadFailure(_))));
+
+        struct FailingReader;
+        impl Read for FailingReader {
+            fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::other("connection reset"))
+            }
+        }
+        let unreadable = OpenApiPayloadValidatorBuilder::new()
+            .load_from_reader(FailingReader)
+            .build();
+        assert!(matches!(unreadable, Err(ValidatorBuilderError::LoadFailure(msg)) if msg == "connection reset"));
+    }
+
+    #[test]
+    fn test_validate_specification() {
+        for path in [
+            "./test/openapi-v3.0.3-pets.yaml",
+            "./test/multi-file/openapi.yaml",
+            "./test/openapi-v3.1.0-resolver.yaml",
+        ] {
+            let validator = OpenApiPayloadValidatorBuilder::new()
+                .load_from_file(path)
+                .reference_resolver(
+                    InMemoryResolver::new()
+                        .with_document("urn:schemas:money", json!({ "Money": { "type": "string" } }))
+                        .with_document(
+                            "https://schemas.internal/lib/party.json",
+                            json!({ "Party": { "type": "object" } }),
+                        ),
+                )
+                .validate_specification(true)
+                .build();
+            assert!(validator.is_ok(), "{}: {}", path, validator.err().unwrap());
+        }
+
+        let broken = r#"
+openapi: 3.0.3
+info: { title: Users, version: 1.0.0 }
+paths:
+  /users/{id}:
+    get:
+      parameters:
+        - name: id
+          schema: { type: integer }
+      responses:
+        200:
+          description: Success
+"#;
+        let error = OpenApiPayloadValidatorBuilder::new()
+            .load_from_str(broken)
+            .validate_specification(true)
+            .build()
+            .err()
+            .unwrap();
+        let errors = match &error {
+            ValidatorBuilderError::NonConformant(errors) => errors,
+            other => panic!("Unexpected error: {}", other),
+        };
+        let pointers: Vec<&str> = errors.iter().map(|error| error.pointer.as_str()).collect();
+        assert_eq!(
+            pointers,
+            vec!["/paths/~1users~1{id}/get/parameters/0", "/paths/~1users~1{id}/get"]
+        );
+        assert!(error.to_string().starts_with("Non-Conformant Specification: 2 error(s)\n  #/paths"));
+
+        // Off by default, the broken specification loads.
+        let unchecked = OpenApiPayloadValidatorBuilder::new().load_from_str(broken).build();
+        assert!(unchecked.is_ok());
+    }
+
+    #[test]
+    fn test_overlays() {
+        let overlay = |actions: Value| {
+            Overlay::from_value(json!({
+                "overlay": "1.0.0",
+                "info": { "title": "Production", "version": "1.0.0" },
+                "actions": actions
+            }))
+            .unwrap()
+        };
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.0.3-pets.yaml")
+            .overlay(overlay(json!([
+                { "target": "$.components.schemas.Pet.properties.name", "update": { "maxLength": 4 } }
+            ])))
+            .overlay(overlay(json!([
+                { "target": "$.components.schemas.Pet.properties.name", "update": { "maxLength": 3 } }
+            ])))
+            .build()
+            .unwrap();
+        let request = |name: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/pets")
+                .header("Content-Type", "application/json")
+                .body(json!({ "name": name }))
+                .unwrap()
+        };
+        assert!(validator.validate_request(&request("Rex"), None).is_ok());
+        assert!(validator.validate_request(&request("Rexy"), None).is_err());
+
+        let error = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.0.3-pets.yaml")
+            .overlay(overlay(json!([
+                { "target": "$.paths['/pets'].post", "update": { "deprecated": true } },
+                { "target": "$.paths['/users']", "remove": true }
+            ])))
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Invalid Overlay: 1 action(s)\n  #/actions/1: Target '$.paths['/users']' matched nothing"
+        );
+    }
+
+    #[test]
+    fn test_operation_filters() {
+        let specification = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Monolith", "version": "1.0.0" },
+            "paths": {
+                "/users": {
+                    "get": { "tags": ["users"], "responses": { "200": { "description": "Success" } } },
+                    "delete": {
+                        "tags": ["users"],
+                        "x-internal": true,
+                        "responses": { "204": { "description": "Deleted" } }
+                    }
+                },
+                "/invoices": {
+                    "get": { "tags": ["billing"], "responses": { "200": { "description": "Success" } } }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(specification)
+            .include_operations(OperationMatcher::Tag("users".to_string()))
+            .exclude_operations(OperationMatcher::Extension("x-internal".to_string(), json!(true)))
+            .build()
+            .unwrap();
+        assert!(validator.find_operation("/users", "GET").is_ok());
+        let not_served = |path: &str, method: &str| match validator.find_operation(path, method) {
+            Err(ValidationError::ValidationTraversalError(TraverserError::OperationNotServed(
+                operation,
+            ))) => operation,
+            other => panic!("Unexpected result: {:?}", other.map(|_| ())),
+        };
+        assert_eq!(not_served("/invoices", "GET"), "GET /invoices");
+        assert_eq!(not_served("/users", "DELETE"), "DELETE /users");
+        let error = validator.find_operation("/orders", "GET").err().unwrap();
+        assert_eq!(
+            error.to_string(),
+            "Traversal error occurred while validating: Path not found: /orders"
+        );
+    }
+    #[test]
+    fn test_custom_root_id() {
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file(SPEC)
+            .root_id("custom")
+            .build()
+            .unwrap();
+        let body = json!({ "iban": "DE89370400440532013000" });
+        assert!(validator.validate_request(&request(body), None).is_ok());
+        let body = json!({ "iban": 42 });
+        assert!(matches!(
+            validator.validate_request(&request(body), None),
+            Err(ValidationError::ValidationError(_))
+        ));
+
+        // The request and response views are registered under the custom root id as well
+        let specification = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Accounts", "version": "1.0.0" },
+            "paths": {
+                "/accounts": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["id", "name"],
+                                        "properties": {
+                                            "id": { "type": "integer", "readOnly": true },
+                                            "name": { "type": "string" }
+                                        }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": { "201": { "description": "Created" } }
+                    }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(specification)
+            .root_id("custom")
+            .build()
+            .unwrap();
+        let body = json!({ "name": "Savings" });
+        assert!(validator.validate_request(&request(body), None).is_ok());
+        let body = json!({ "name": 42 });
+        assert!(matches!(
+            validator.validate_request(&request(body), None),
+            Err(ValidationError::ValidationError(_))
+        ));
     }
 }
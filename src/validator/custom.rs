@@ -0,0 +1,242 @@
+use crate::traverser::OpenApiTraverser;
+use crate::types::operation::Operation;
+use crate::validator::ValidationError;
+use http::{HeaderMap, Method};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// A request that was matched to an operation, as seen by a `RequestValidator`.
+pub struct DecodedRequest<'request> {
+    pub(crate) method: &'request Method,
+    pub(crate) path: &'request str,
+    pub(crate) headers: &'request HeaderMap,
+    pub(crate) body: Option<Value>,
+    pub(crate) query_params: HashMap<String, String>,
+    pub(crate) path_params: HashMap<String, String>,
+}
+
+impl DecodedRequest<'_> {
+    pub fn method(&self) -> &Method {
+        self.method
+    }
+
+    pub fn path(&self) -> &str {
+        self.path
+    }
+
+    pub fn headers(&self) -> &HeaderMap {
+        self.headers
+    }
+
+    /// The body converted to JSON, if the request has one.
+    pub fn body(&self) -> Option<&Value> {
+        self.body.as_ref()
+    }
+
+    /// The percent-decoded query parameters.
+    pub fn query_params(&self) -> &HashMap<String, String> {
+        &self.query_params
+    }
+
+    /// The values of the templated path segments, keyed by their name in the path template.
+    pub fn path_params(&self) -> &HashMap<String, String> {
+        &self.path_params
+    }
+}
+
+/// A user-defined validation stage.
+///
+/// Custom validators are registered on the `OpenApiPayloadValidatorBuilder`, either for every
+/// operation or for operations with a given `operationId` or tag. They run in `validate_request`
+/// after the built-in stages, with the same traverser and resolved operation.
+///
+/// # Example
+///
+/// ```rust
+/// use oasert::traverser::OpenApiTraverser;
+/// use oasert::types::operation::Operation;
+/// use oasert::validator::custom::{DecodedRequest, RequestValidator};
+/// use oasert::validator::ValidationError;
+/// use serde_json::Value;
+///
+/// struct TenantMatchesPath;
+///
+/// impl RequestValidator for TenantMatchesPath {
+///     fn validate(
+///         &self,
+///         _traverser: &OpenApiTraverser,
+///         _operation: &Operation,
+///         request: &DecodedRequest<'_>,
+///     ) -> Result<(), ValidationError> {
+///         let header = request.headers().get("x-tenant").and_then(|v| v.to_str().ok());
+///         match (header, request.path_params().get("tenant")) {
+///             (Some(header), Some(tenant)) if header == tenant => Ok(()),
+///             _ => Err(ValidationError::validation_error(
+///                 "Tenant header does not match the path",
+///                 &Value::Null,
+///             )),
+///         }
+///     }
+/// }
+/// ```
+pub trait RequestValidator: Send + Sync {
+    /// # validate
+    ///
+    /// # Arguments
+    ///
+    /// * `traverser` - Provides access to the full OpenAPI specification
+    /// * `operation` - The operation the request was matched to
+    /// * `request` - The decoded request
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the validation passes without errors.
+    /// * `Err(ValidationError)` - If validation fails.
+    fn validate(
+        &self,
+        traverser: &OpenApiTraverser,
+        operation: &Operation,
+        request: &DecodedRequest<'_>,
+    ) -> Result<(), ValidationError>;
+}
+
+/// The custom validators registered on the builder.
+#[derive(Default, Clone)]
+pub(crate) struct CustomValidators {
+    pub(crate) global: Vec<Arc<dyn RequestValidator>>,
+    pub(crate) by_operation_id: HashMap<String, Vec<Arc<dyn RequestValidator>>>,
+    pub(crate) by_tag: HashMap<String, Vec<Arc<dyn RequestValidator>>>,
+}
+
+impl CustomValidators {
+    /// Returns the validators that apply to an operation: global ones first, then the ones
+    /// registered for its `operationId`, then the ones registered for its tags.
+    pub(crate) fn for_operation(&self, operation: &Operation) -> Vec<&Arc<dyn RequestValidator>> {
+        let mut validators: Vec<&Arc<dyn RequestValidator>> = self.global.iter().collect();
+        if let Some(registered) = operation
+            .operation_id()
+            .and_then(|operation_id| self.by_operation_id.get(operation_id))
+        {
+            validators.extend(registered);
+        }
+        for tag in operation.tags() {
+            if let Some(registered) = self.by_tag.get(tag) {
+                validators.extend(registered);
+            }
+        }
+        validators
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+    use http::Request;
+    use serde_json::json;
+
+    struct TenantMatchesPath;
+
+    impl RequestValidator for TenantMatchesPath {
+        fn validate(
+            &self,
+            _traverser: &OpenApiTraverser,
+            _operation: &Operation,
+            request: &DecodedRequest<'_>,
+        ) -> Result<(), ValidationError> {
+            let header = request
+                .headers()
+                .get("x-tenant")
+                .and_then(|value| value.to_str().ok());
+            match (header, request.path_params().get("tenant")) {
+                (Some(header), Some(tenant)) if header == tenant => Ok(()),
+                _ => Err(ValidationError::validation_error(
+                    "Tenant header does not match the path",
+                    &Value::Null,
+                )),
+            }
+        }
+    }
+
+    struct RejectAll;
+
+    impl RequestValidator for RejectAll {
+        fn validate(
+            &self,
+            _traverser: &OpenApiTraverser,
+            operation: &Operation,
+            _request: &DecodedRequest<'_>,
+        ) -> Result<(), ValidationError> {
+            Err(ValidationError::validation_error(
+                format!("Rejected {}", operation.operation_id().unwrap_or_default()),
+                &Value::Null,
+            ))
+        }
+    }
+
+    fn create_builder() -> OpenApiPayloadValidatorBuilder {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/tenants/{tenant}/pets": {
+                    "get": {
+                        "operationId": "listPets",
+                        "tags": ["pets"],
+                        "parameters": [
+                            { "name": "tenant", "in": "path", "required": true, "schema": { "type": "string" } }
+                        ]
+                    },
+                    "post": {
+                        "operationId": "createPet",
+                        "tags": ["admin"],
+                        "parameters": [
+                            { "name": "tenant", "in": "path", "required": true, "schema": { "type": "string" } }
+                        ]
+                    }
+                }
+            }
+        });
+        OpenApiPayloadValidatorBuilder::new().load_from_value(spec)
+    }
+
+    fn request(method: &str, tenant_header: &str) -> Request<Value> {
+        Request::builder()
+            .method(method)
+            .uri("/tenants/acme/pets")
+            .header("x-tenant", tenant_header)
+            .body(Value::Null)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_global_validator() {
+        let validator = create_builder()
+            .validator(TenantMatchesPath)
+            .build()
+            .unwrap();
+
+        assert!(validator.validate_request(&request("GET", "acme"), None).is_ok());
+        assert!(validator.validate_request(&request("GET", "other"), None).is_err());
+        assert!(validator.validate_request(&request("POST", "other"), None).is_err());
+    }
+
+    #[test]
+    fn test_operation_and_tag_validators() {
+        let validator = create_builder()
+            .operation_validator("createPet", RejectAll)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&request("GET", "acme"), None).is_ok());
+        let result = validator.validate_request(&request("POST", "acme"), None);
+        assert!(result.unwrap_err().to_string().contains("Rejected createPet"));
+
+        let validator = create_builder()
+            .tag_validator("pets", RejectAll)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&request("GET", "acme"), None).is_err());
+        assert!(validator.validate_request(&request("POST", "acme"), None).is_ok());
+    }
+}
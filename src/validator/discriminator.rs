@@ -0,0 +1,368 @@
+use crate::document::{mapping_ref, resolve_local_ref, MAX_REF_DEPTH};
+use crate::traverser::OpenApiTraverser;
+use crate::types::json_path::JsonPath;
+use crate::types::operation::Operation;
+use crate::validator::access_mode::{item_schema, property_schema};
+use crate::validator::{ValidationError, Validator};
+use crate::{PATH_SEPARATOR, REF_FIELD};
+use jsonschema::ValidationOptions;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+const DISCRIMINATOR_FIELD: &str = "discriminator";
+const PROPERTY_NAME_FIELD: &str = "propertyName";
+const MAPPING_FIELD: &str = "mapping";
+
+/// Keywords whose subschemas a discriminator selects from.
+const DISCRIMINATED_KEYWORDS: [&str; 2] = ["oneOf", "anyOf"];
+
+/// The subschema a discriminator selected for a part of a payload.
+#[derive(Debug, PartialEq)]
+struct Selection {
+    property: String,
+    value: String,
+    schema_ref: String,
+    pointer: String,
+}
+
+/// Validates the parts of a payload described by a schema with a `discriminator` against the
+/// subschema the discriminator selects, instead of against all subschemas of its `oneOf`/`anyOf`.
+pub(crate) struct DiscriminatorValidator<'v> {
+    schema: &'v Value,
+    instance: &'v Value,
+    root: String,
+}
+
+impl<'v> DiscriminatorValidator<'v> {
+    pub(crate) fn new<'node>(schema: &'node Value, instance: &'node Value, root: String) -> Self
+    where
+        'node: 'v,
+    {
+        Self {
+            schema,
+            instance,
+            root,
+        }
+    }
+}
+
+impl Validator for DiscriminatorValidator<'_> {
+    /// Validates every discriminated part of the payload against its selected subschema.
+    fn validate(
+        &self,
+        traverser: &OpenApiTraverser,
+        _operation: &Operation,
+        validation_opts: &ValidationOptions,
+    ) -> Result<(), ValidationError> {
+        self.check(traverser.specification(), validation_opts)
+    }
+}
+
+impl DiscriminatorValidator<'_> {
+    /// Validates every discriminated part of the payload against its selected subschema, with
+    /// the discriminators and subschemas looked up in `document`.
+    pub(crate) fn check(
+        &self,
+        document: &Value,
+        validation_opts: &ValidationOptions,
+    ) -> Result<(), ValidationError> {
+        let mut walker = DiscriminatorWalker::new(document);
+        walker.walk(self.schema, self.instance, "", 0)?;
+
+        for selection in walker.selections {
+            let instance = match self.instance.pointer(&selection.pointer) {
+                Some(instance) => instance,
+                None => continue,
+            };
+            let schema_path = match selection.schema_ref.strip_prefix("#/") {
+                Some(pointer) => JsonPath(pointer.split(PATH_SEPARATOR).map(String::from).collect()),
+                None => {
+                    return Err(ValidationError::validation_error(
+                        format!(
+                            "Discriminator '{}' maps '{}' to '{}', which is not a local reference",
+                            selection.property, selection.value, selection.schema_ref
+                        ),
+                        instance,
+                    ));
+                }
+            };
+            match Self::complex_validation_by_path(
+                validation_opts,
+                &self.root,
+                &schema_path,
+                instance,
+            ) {
+                Ok(()) => {}
+                Err(ValidationError::ValidationError(msg)) => {
+                    return Err(ValidationError::ValidationError(format!(
+                        "Value at '{}' does not match '{}', selected by discriminator '{}' = '{}': {}",
+                        selection.pointer,
+                        selection.schema_ref,
+                        selection.property,
+                        selection.value,
+                        msg
+                    )));
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Finds the discriminated parts of a payload and the subschemas their discriminators select.
+struct DiscriminatorWalker<'d> {
+    document: &'d Value,
+    selections: Vec<Selection>,
+    seen: HashSet<(String, String)>,
+}
+
+impl<'d> DiscriminatorWalker<'d> {
+    fn new(document: &'d Value) -> Self {
+        Self {
+            document,
+            selections: vec![],
+            seen: HashSet::new(),
+        }
+    }
+
+    fn walk(
+        &mut self,
+        schema: &Value,
+        instance: &Value,
+        pointer: &str,
+        depth: usize,
+    ) -> Result<(), ValidationError> {
+        if depth > MAX_REF_DEPTH {
+            return Ok(());
+        }
+        if let Some(resolved) = resolve_local_ref(self.document, schema) {
+            self.walk(resolved, instance, pointer, depth + 1)?;
+        }
+        if let Some(Value::Array(subschemas)) = schema.get("allOf") {
+            for subschema in subschemas {
+                self.walk(subschema, instance, pointer, depth + 1)?;
+            }
+        }
+
+        match instance {
+            Value::Object(map) => {
+                if let Some(selection) = Self::select(schema, map, pointer)? {
+                    let key = (selection.schema_ref.clone(), pointer.to_string());
+                    // An 'allOf' back to the discriminated schema selects the same subschema again.
+                    if self.seen.insert(key) {
+                        let branch = selection
+                            .schema_ref
+                            .strip_prefix('#')
+                            .and_then(|branch_pointer| self.document.pointer(branch_pointer));
+                        self.selections.push(selection);
+                        if let Some(branch) = branch {
+                            self.walk(branch, instance, pointer, depth + 1)?;
+                        }
+                    }
+                }
+                for (name, value) in map {
+                    if let Some(property_schema) = property_schema(schema, name) {
+                        let property_pointer = format!(
+                            "{}/{}",
+                            pointer,
+                            name.replace('~', "~0").replace('/', "~1")
+                        );
+                        self.walk(property_schema, value, &property_pointer, 0)?;
+                    }
+                }
+            }
+            Value::Array(items) => {
+                for (index, item) in items.iter().enumerate() {
+                    if let Some(item_schema) = item_schema(schema, index) {
+                        let item_pointer = format!("{}/{}", pointer, index);
+                        self.walk(item_schema, item, &item_pointer, 0)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Selects the subschema named by the discriminator of a schema.
+    ///
+    /// The discriminator value is looked up in `mapping` first. Otherwise, it is the name of a
+    /// component schema referenced from `oneOf`/`anyOf`.
+    ///
+    /// # Returns
+    /// * `Ok(Some(Selection))` - The selected subschema
+    /// * `Ok(None)` - If the schema has no discriminator, or no subschemas to select from
+    /// * `Err(ValidationError)` - If the discriminator property is missing or has an unknown value.
+    fn select(
+        schema: &Value,
+        instance: &Map<String, Value>,
+        pointer: &str,
+    ) -> Result<Option<Selection>, ValidationError> {
+        // A discriminator without subschemas (on the parent of an 'allOf' hierarchy) selects nothing.
+        let discriminator = match schema.get(DISCRIMINATOR_FIELD) {
+            Some(discriminator) if Self::is_composed(schema) => discriminator,
+            _ => return Ok(None),
+        };
+        let property = match OpenApiTraverser::get_as_str(discriminator, PROPERTY_NAME_FIELD) {
+            Ok(property) => property,
+            Err(_) => return Ok(None),
+        };
+        let value = match instance.get(property) {
+            Some(Value::String(value)) => value,
+            Some(other) => {
+                return Err(ValidationError::validation_error(
+                    format!(
+                        "Discriminator property '{}' at '{}' must be a string",
+                        property, pointer
+                    ),
+                    other,
+                ));
+            }
+            None => {
+                return Err(ValidationError::validation_error(
+                    format!(
+                        "Discriminator property '{}' is missing at '{}'",
+                        property, pointer
+                    ),
+                    &Value::Object(instance.clone()),
+                ));
+            }
+        };
+
+        let mapping = discriminator.get(MAPPING_FIELD).and_then(Value::as_object);
+        let mapped = mapping
+            .and_then(|mapping| mapping.get(value))
+            .and_then(Value::as_str)
+            .map(mapping_ref);
+        let branches = Self::branch_refs(schema);
+        let schema_ref = mapped.or_else(|| {
+            branches
+                .iter()
+                .find(|branch| branch.rsplit(PATH_SEPARATOR).next() == Some(value.as_str()))
+                .map(|branch| branch.to_string())
+        });
+
+        match schema_ref {
+            Some(schema_ref) => Ok(Some(Selection {
+                property: property.to_string(),
+                value: value.clone(),
+                schema_ref,
+                pointer: pointer.to_string(),
+            })),
+            None => {
+                let mut expected: Vec<&str> = mapping
+                    .map(|mapping| mapping.keys().map(String::as_str).collect())
+                    .unwrap_or_default();
+                for name in branches.iter().filter_map(|b| b.rsplit(PATH_SEPARATOR).next()) {
+                    if !expected.contains(&name) {
+                        expected.push(name);
+                    }
+                }
+                Err(ValidationError::validation_error(
+                    format!(
+                        "Unknown value '{}' of discriminator property '{}' at '{}', expected one of: {}",
+                        value,
+                        property,
+                        pointer,
+                        expected.join(", ")
+                    ),
+                    &Value::Object(instance.clone()),
+                ))
+            }
+        }
+    }
+
+    fn is_composed(schema: &Value) -> bool {
+        DISCRIMINATED_KEYWORDS
+            .iter()
+            .any(|keyword| schema.get(*keyword).is_some_and(Value::is_array))
+    }
+
+    /// The `$ref`s of the `oneOf`/`anyOf` subschemas of a schema.
+    fn branch_refs(schema: &Value) -> Vec<&str> {
+        DISCRIMINATED_KEYWORDS
+            .iter()
+            .filter_map(|keyword| schema.get(*keyword).and_then(Value::as_array))
+            .flatten()
+            .filter_map(|branch| branch.get(REF_FIELD).and_then(Value::as_str))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    fn document() -> Value {
+        json!({
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "anyOf": [
+                            { "$ref": "#/components/schemas/Cat" },
+                            { "$ref": "#/components/schemas/Dog" }
+                        ],
+                        "discriminator": {
+                            "propertyName": "petType",
+                            "mapping": { "kitty": "Cat" }
+                        }
+                    },
+                    "Cat": {
+                        "allOf": [{ "$ref": "#/components/schemas/Pet" }],
+                        "properties": { "lives": { "type": "integer" } }
+                    },
+                    "Dog": {
+                        "properties": {
+                            "friends": {
+                                "type": "array",
+                                "items": { "$ref": "#/components/schemas/Pet" }
+                            }
+                        }
+                    }
+                }
+            }
+        })
+    }
+
+    fn select(instance: Value) -> Result<Vec<(String, String)>, ValidationError> {
+        let document = document();
+        let mut walker = DiscriminatorWalker::new(&document);
+        let schema = json!({ "$ref": "#/components/schemas/Pet" });
+        walker.walk(&schema, &instance, "", 0)?;
+        Ok(walker
+            .selections
+            .into_iter()
+            .map(|selection| (selection.pointer, selection.schema_ref))
+            .collect())
+    }
+
+    #[test]
+    fn test_select_subschema() {
+        let selected = select(json!({ "petType": "kitty", "lives": 9 })).unwrap();
+        assert_eq!(
+            selected,
+            vec![("".to_string(), "#/components/schemas/Cat".to_string())]
+        );
+
+        let dog = json!({ "petType": "Dog", "friends": [{ "petType": "kitty" }] });
+        assert_eq!(
+            select(dog).unwrap(),
+            vec![
+                ("".to_string(), "#/components/schemas/Dog".to_string()),
+                ("/friends/0".to_string(), "#/components/schemas/Cat".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unknown_discriminator_value() {
+        let error = select(json!({ "petType": "Cow" })).unwrap_err().to_string();
+        assert!(error.contains("Unknown value 'Cow'"));
+        assert!(error.contains("expected one of: kitty, Cat, Dog"));
+
+        let error = select(json!({ "lives": 9 })).unwrap_err().to_string();
+        assert!(error.contains("Discriminator property 'petType' is missing"));
+    }
+}
@@ -0,0 +1,489 @@
+//! Validation of the examples embedded in a specification against their schemas.
+use crate::document::{resolve_local_ref, INSTANCE_KEYWORDS, SUBSCHEMA_MAP_KEYWORDS};
+use crate::traverser::OpenApiTraverser;
+use crate::types::json_path::JsonPath;
+use crate::types::operation::Operation;
+use crate::types::Direction;
+use crate::validator::access_mode::AccessModes;
+use crate::validator::discriminator::DiscriminatorValidator;
+use crate::validator::{ValidationError, Validator};
+use crate::{PARAMETERS_FIELD, REF_FIELD, REQUEST_BODY_FIELD, RESPONSES_FIELD, SCHEMA_FIELD};
+use jsonschema::ValidationOptions;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+
+const EXAMPLE_FIELD: &str = "example";
+const EXAMPLES_FIELD: &str = "examples";
+const VALUE_FIELD: &str = "value";
+const SCHEMAS_FIELD: &str = "schemas";
+const HEADERS_FIELD: &str = "headers";
+const REQUEST_BODIES_FIELD: &str = "requestBodies";
+
+/// An example that does not validate against its schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExampleError {
+    /// JSON Pointer to the example in the specification, e.g.
+    /// `/paths/~1pets/post/requestBody/content/application~1json/example`
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Display for ExampleError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "#{}: {}", self.pointer, self.message)
+    }
+}
+
+/// An example found in a specification, with the schema it is an instance of.
+#[derive(Debug)]
+pub(crate) struct Example {
+    pointer: JsonPath,
+    value: Value,
+    schema: JsonPath,
+    /// The direction the payloads the example stands for travel in, `None` for the examples of
+    /// component schemas, which stand for both.
+    direction: Option<Direction>,
+}
+
+/// Finds every example of a specification: the `example` and `examples` of media types,
+/// parameters and headers, and those of Schema Objects (`example` in 3.0, `examples` in 3.1).
+///
+/// Example Objects given by `$ref` are resolved, those with only an `externalValue` are skipped.
+pub(crate) fn find_examples(specification: &Value) -> Vec<Example> {
+    let mut finder = ExampleFinder {
+        specification,
+        examples: vec![],
+    };
+    finder.walk_node(specification, &JsonPath::new(), None);
+    finder.examples
+}
+
+/// Validates every example against its schema, with the options the validator is built with.
+///
+/// # Arguments
+/// * `examples` - The examples found by `find_examples`
+/// * `specification` - The normalized specification, whose discriminators select the subschema
+///   an example is validated against
+/// * `options` - The options of the validator
+/// * `access_modes` - The access modes, whose root resource, or request and response views,
+///   examples are validated in
+///
+/// # Returns
+/// * `Ok(())` - If every example is valid
+/// * `Err(Vec<ExampleError>)` - Every example that is not.
+pub(crate) fn check_examples(
+    examples: &[Example],
+    specification: &Value,
+    options: &ValidationOptions,
+    access_modes: &AccessModes,
+) -> Result<(), Vec<ExampleError>> {
+    let errors: Vec<ExampleError> = examples
+        .iter()
+        .filter_map(|example| {
+            let root = match example.direction {
+                Some(direction) => access_modes.root_resource(direction),
+                None => access_modes.root_id.clone(),
+            };
+            match ExampleValidator::new(example, root).check(specification, options) {
+                Ok(()) => None,
+                Err(e) => Some(ExampleError {
+                    pointer: format!("/{}", example.pointer.format_path()),
+                    message: e.to_string(),
+                }),
+            }
+        })
+        .collect();
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(errors),
+    }
+}
+
+/// Validates an example against the schema it is an instance of.
+pub(crate) struct ExampleValidator<'v> {
+    example: &'v Example,
+    root: String,
+}
+
+impl<'v> ExampleValidator<'v> {
+    pub(crate) fn new(example: &'v Example, root: String) -> Self {
+        Self { example, root }
+    }
+
+    fn check(
+        &self,
+        specification: &Value,
+        validation_opts: &ValidationOptions,
+    ) -> Result<(), ValidationError> {
+        let pointer = format!("/{}", self.example.schema.format_path());
+        if let Some(schema) = specification.pointer(&pointer) {
+            DiscriminatorValidator::new(schema, &self.example.value, self.root.clone())
+                .check(specification, validation_opts)?;
+        }
+        Self::complex_validation_by_path(
+            validation_opts,
+            &self.root,
+            &self.example.schema,
+            &self.example.value,
+        )
+    }
+}
+
+impl Validator for ExampleValidator<'_> {
+    /// Validates the example, which does not depend on the operation.
+    fn validate(
+        &self,
+        traverser: &OpenApiTraverser,
+        _operation: &Operation,
+        validation_opts: &ValidationOptions,
+    ) -> Result<(), ValidationError> {
+        self.check(traverser.specification(), validation_opts)
+    }
+}
+
+struct ExampleFinder<'s> {
+    specification: &'s Value,
+    examples: Vec<Example>,
+}
+
+impl ExampleFinder<'_> {
+    /// Walks a non-schema part of the document. Objects with a `schema` (media types,
+    /// parameters and headers) have their examples collected against it.
+    fn walk_node(&mut self, node: &Value, pointer: &JsonPath, direction: Option<Direction>) {
+        match node {
+            Value::Object(map) => {
+                if map.get(SCHEMA_FIELD).is_some_and(Value::is_object) {
+                    let schema = child(pointer, SCHEMA_FIELD);
+                    self.collect_example(map.get(EXAMPLE_FIELD), pointer, &schema, direction);
+                    self.collect_example_objects(
+                        map.get(EXAMPLES_FIELD),
+                        pointer,
+                        &schema,
+                        direction,
+                    );
+                }
+                for (key, value) in map {
+                    let path = child(pointer, key);
+                    match key.as_str() {
+                        SCHEMA_FIELD => self.walk_schema(value, &path, direction),
+                        SCHEMAS_FIELD => {
+                            if let Some(schemas) = value.as_object() {
+                                for (name, schema) in schemas {
+                                    self.walk_schema(schema, &child(&path, name), direction);
+                                }
+                            }
+                        }
+                        EXAMPLE_FIELD | EXAMPLES_FIELD => {}
+                        REQUEST_BODY_FIELD | REQUEST_BODIES_FIELD | PARAMETERS_FIELD => {
+                            self.walk_node(value, &path, Some(Direction::Request))
+                        }
+                        RESPONSES_FIELD | HEADERS_FIELD => {
+                            self.walk_node(value, &path, Some(Direction::Response))
+                        }
+                        _ => self.walk_node(value, &path, direction),
+                    }
+                }
+            }
+            Value::Array(items) => items.iter().enumerate().for_each(|(index, item)| {
+                self.walk_node(item, &child(pointer, index.to_string()), direction)
+            }),
+            _ => {}
+        }
+    }
+
+    /// Walks a Schema Object and its subschemas, collecting their examples against themselves.
+    fn walk_schema(&mut self, schema: &Value, pointer: &JsonPath, direction: Option<Direction>) {
+        let map = match schema.as_object() {
+            Some(map) => map,
+            None => return,
+        };
+        self.collect_example(map.get(EXAMPLE_FIELD), pointer, pointer, direction);
+        if let Some(Value::Array(examples)) = map.get(EXAMPLES_FIELD) {
+            let path = child(pointer, EXAMPLES_FIELD);
+            for (index, example) in examples.iter().enumerate() {
+                self.push(child(&path, index.to_string()), example, pointer, direction);
+            }
+        }
+        for (key, value) in map {
+            let path = child(pointer, key);
+            match (key.as_str(), value) {
+                (key, _) if INSTANCE_KEYWORDS.contains(&key) || key == EXAMPLES_FIELD => {}
+                (key, Value::Object(subschemas)) if SUBSCHEMA_MAP_KEYWORDS.contains(&key) => {
+                    for (name, subschema) in subschemas {
+                        self.walk_schema(subschema, &child(&path, name), direction);
+                    }
+                }
+                (_, Value::Object(_)) => self.walk_schema(value, &path, direction),
+                (_, Value::Array(items)) => {
+                    for (index, item) in items.iter().enumerate() {
+                        self.walk_schema(item, &child(&path, index.to_string()), direction);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Collects the `example` of a media type, parameter, header or schema.
+    fn collect_example(
+        &mut self,
+        example: Option<&Value>,
+        pointer: &JsonPath,
+        schema: &JsonPath,
+        direction: Option<Direction>,
+    ) {
+        if let Some(example) = example {
+            self.push(child(pointer, EXAMPLE_FIELD), example, schema, direction);
+        }
+    }
+
+    /// Collects the Example Objects of the `examples` map of a media type, parameter or header.
+    fn collect_example_objects(
+        &mut self,
+        examples: Option<&Value>,
+        pointer: &JsonPath,
+        schema: &JsonPath,
+        direction: Option<Direction>,
+    ) {
+        let examples = match examples.and_then(Value::as_object) {
+            Some(examples) => examples,
+            None => return,
+        };
+        let path = child(pointer, EXAMPLES_FIELD);
+        for (name, example) in examples {
+            let path = child(&path, name);
+            // A referenced example is reported where it is used, it may be valid elsewhere.
+            let (path, example) = match example.get(REF_FIELD) {
+                Some(_) => (path, resolve_local_ref(self.specification, example)),
+                None => (child(&path, VALUE_FIELD), Some(example)),
+            };
+            if let Some(value) = example.and_then(|example| example.get(VALUE_FIELD)) {
+                self.push(path, value, schema, direction);
+            }
+        }
+    }
+
+    fn push(
+        &mut self,
+        pointer: JsonPath,
+        value: &Value,
+        schema: &JsonPath,
+        direction: Option<Direction>,
+    ) {
+        self.examples.push(Example {
+            pointer,
+            value: value.clone(),
+            schema: schema.clone(),
+            direction,
+        });
+    }
+}
+
+fn child(pointer: &JsonPath, segment: impl AsRef<str>) -> JsonPath {
+    let mut path = pointer.clone();
+    path.add(segment);
+    path
+}
+
+#[cfg(test)]
+mod test {
+    use crate::validator::builder::{OpenApiPayloadValidatorBuilder, ValidatorBuilderError};
+    use http::Request;
+    use serde_json::{json, Value};
+
+    fn spec(version: &str) -> Value {
+        json!({
+            "openapi": version,
+            "info": { "title": "Pets", "version": "1.0.0" },
+            "paths": {
+                "/pets/{petId}": {
+                    "put": {
+                        "parameters": [
+                            {
+                                "name": "petId",
+                                "in": "path",
+                                "required": true,
+                                "schema": { "type": "integer" },
+                                "example": "rex"
+                            }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Pet" },
+                                    "examples": {
+                                        "valid": { "value": { "name": "Rex" } },
+                                        "drifted": { "value": { "name": "Rex", "age": "two" } },
+                                        "shared": { "$ref": "#/components/examples/Nameless" },
+                                        "external": { "externalValue": "https://example.com/pet.json" }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "200": {
+                                "description": "Updated",
+                                "headers": {
+                                    "x-rate-limit": { "schema": { "type": "integer" }, "example": 100 }
+                                },
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "$ref": "#/components/schemas/Pet" },
+                                        "example": { "id": 1, "name": "Rex" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Pet": {
+                        "type": "object",
+                        "required": ["id", "name"],
+                        "properties": {
+                            "id": { "type": "integer", "readOnly": true },
+                            "name": { "type": "string", "example": "Rex" },
+                            "age": { "type": "integer", "example": 2.5 },
+                            "example": { "type": "string" }
+                        }
+                    }
+                },
+                "examples": {
+                    "Nameless": { "value": { "age": 2 } }
+                }
+            }
+        })
+    }
+
+    fn invalid_examples(specification: Value) -> Vec<String> {
+        match OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(specification)
+            .validate_examples(true)
+            .build()
+        {
+            Ok(_) => vec![],
+            Err(ValidatorBuilderError::InvalidExamples(errors)) => {
+                errors.into_iter().map(|error| error.pointer).collect()
+            }
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_invalid_examples() {
+        for version in ["3.0.3", "3.1.0"] {
+            assert_eq!(
+                invalid_examples(spec(version)),
+                vec![
+                    "/components/schemas/Pet/properties/age/example",
+                    "/paths/~1pets~1{petId}/put/parameters/0/example",
+                    "/paths/~1pets~1{petId}/put/requestBody/content/application~1json/examples/drifted/value",
+                    "/paths/~1pets~1{petId}/put/requestBody/content/application~1json/examples/shared",
+                ],
+                "OpenAPI {}",
+                version
+            );
+        }
+
+        // The schemas of templated paths resolve at runtime as well.
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(spec("3.1.0"))
+            .build()
+            .unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method("PUT")
+                .uri("/pets/1")
+                .header("Content-Type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+        assert!(validator
+            .validate_request(&request(json!({ "name": "Rex" })), None)
+            .is_ok());
+        assert!(validator
+            .validate_request(&request(json!({ "age": 2 })), None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_schema_examples() {
+        let mut specification = spec("3.1.0");
+        specification["paths"] = json!({});
+        specification["components"]["schemas"]["Pet"]["properties"]["age"] =
+            json!({ "type": "integer", "examples": [2, "two"] });
+        assert_eq!(
+            invalid_examples(specification.clone()),
+            vec!["/components/schemas/Pet/properties/age/examples/1"]
+        );
+
+        specification["components"]["schemas"]["Pet"]["properties"]["age"]["examples"] = json!([2]);
+        assert!(OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(specification)
+            .validate_examples(true)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn test_discriminated_examples() {
+        let specification = json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Pets", "version": "1.0.0" },
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "oneOf": [
+                                            { "$ref": "#/components/schemas/Cat" },
+                                            { "$ref": "#/components/schemas/Dog" }
+                                        ],
+                                        "discriminator": { "propertyName": "petType" }
+                                    },
+                                    "examples": {
+                                        "cat": { "value": { "petType": "Cat", "lives": 9 } },
+                                        "cow": { "value": { "petType": "Cow" } },
+                                        "mislabelled": { "value": { "petType": "Cat", "lives": "many" } }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Cat": {
+                        "type": "object",
+                        "required": ["petType"],
+                        "properties": {
+                            "petType": { "type": "string" },
+                            "lives": { "type": "integer" }
+                        }
+                    },
+                    "Dog": {
+                        "type": "object",
+                        "required": ["petType"],
+                        "properties": {
+                            "petType": { "type": "string" },
+                            "bark": { "type": "string" }
+                        }
+                    }
+                }
+            }
+        });
+        assert_eq!(
+            invalid_examples(specification),
+            vec![
+                "/paths/~1pets/post/requestBody/content/application~1json/examples/cow/value",
+                "/paths/~1pets/post/requestBody/content/application~1json/examples/mislabelled/value",
+            ]
+        );
+    }
+}
@@ -0,0 +1,479 @@
+use crate::types::operation::Operation;
+use crate::validator::custom::DecodedRequest;
+use crate::validator::{OpenApiPayloadValidator, ValidationError};
+use lambda_http::{Body, Request, RequestExt};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Claims and context keys that may carry the scopes granted by an authorizer.
+const SCOPE_KEYS: [&str; 3] = ["scope", "scopes", "scp"];
+
+impl OpenApiPayloadValidator {
+    /// # validate_lambda_request
+    ///
+    /// Validates a request received through `lambda_http` (API Gateway v1, API Gateway v2 or ALB).
+    ///
+    /// The request goes through the same stages as in `validate_request` (body, headers, query
+    /// and path parameters, security and custom validators), with the information already
+    /// extracted by the Lambda runtime:
+    /// - The operation is looked up by `raw_http_path()`, the path without the API Gateway stage
+    /// - Path parameters come from `path_parameters()` (falling back to the path template for ALB)
+    /// - Query parameters come from `query_string_parameters()`; multi-value parameters are joined
+    ///   with `,` so they can be validated against `array` schemas
+    /// - Binary bodies (base64 encoded events are decoded by the runtime) are parsed as JSON
+    /// - When the request went through an authorizer, the scopes it granted (JWT authorizer
+    ///   scopes/claims or the Lambda authorizer context) are validated as the scopes provided to
+    ///   `validate_request`; an authorizer that grants no scopes does not satisfy a requirement
+    ///   that lists some. Credentials are verified as in `validate_request`.
+    ///
+    /// # Arguments
+    ///
+    /// * `request` - The request as produced by `lambda_http`
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the request is valid, according to the OpenAPI specification
+    /// * `Err(ValidationError)` - If validation fails.
+    pub fn validate_lambda_request(&self, request: &Request) -> Result<(), ValidationError> {
+        let path = match request.raw_http_path() {
+            "" => request.uri().path(),
+            raw_http_path => raw_http_path,
+        };
+        let operation = self.find_operation(path, request.method().as_str())?;
+
+        let query_params = match request.query_string_parameters_ref() {
+            Some(query_map) => join_multi_values(query_map.iter()),
+            None => HashMap::new(),
+        };
+        let decoded_request = DecodedRequest {
+            method: request.method(),
+            path,
+            headers: request.headers(),
+            body: convert_lambda_body(request.body())?,
+            query_params,
+            path_params: merge_path_parameters(&operation, path, request),
+        };
+
+        let scopes = match request.request_context_ref() {
+            Some(context) => match serde_json::to_value(context) {
+                Ok(context) => extract_authorizer_scopes(&context),
+                Err(_) => None,
+            },
+            None => None,
+        };
+        self.validate_decoded_request(&operation, &decoded_request, scopes.as_ref())
+    }
+}
+
+/// Converts a Lambda body into JSON. Empty bodies are treated as absent.
+fn convert_lambda_body(body: &Body) -> Result<Option<Value>, ValidationError> {
+    let bytes: &[u8] = match body {
+        Body::Empty => return Ok(None),
+        Body::Text(text) => text.as_bytes(),
+        Body::Binary(binary) => binary.as_slice(),
+    };
+    if bytes.is_empty() {
+        return Ok(None);
+    }
+    match serde_json::from_slice(bytes) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) => Err(ValidationError::validation_error(
+            format!("Request body is not valid JSON: {}", e),
+            &Value::Null,
+        )),
+    }
+}
+
+/// Collapses repeated keys into a single comma-delimited value.
+fn join_multi_values<'a>(
+    pairs: impl Iterator<Item = (&'a str, &'a str)>,
+) -> HashMap<String, String> {
+    let mut joined: HashMap<String, String> = HashMap::new();
+    for (key, value) in pairs {
+        joined
+            .entry(key.to_string())
+            .and_modify(|existing| {
+                existing.push(',');
+                existing.push_str(value);
+            })
+            .or_insert_with(|| value.to_string());
+    }
+    joined
+}
+
+/// Uses the parameters extracted by API Gateway, filling in any gaps from the path template.
+fn merge_path_parameters(
+    operation: &Operation,
+    path: &str,
+    request: &Request,
+) -> HashMap<String, String> {
+    let mut path_params = operation.extract_path_parameters(path);
+    if let Some(lambda_params) = request.path_parameters_ref() {
+        for (key, value) in lambda_params.iter() {
+            path_params.insert(key.to_string(), value.to_string());
+        }
+    }
+    path_params
+}
+
+/// Finds the scopes granted by the authorizer in the request context.
+///
+/// Returns `None` when the request did not go through an authorizer, and no scopes when the
+/// authorizer has no scope claim. Supports the JWT authorizer (`authorizer.jwt.scopes` or the `scope`/`scp` claims), Cognito user
+/// pool authorizers on REST APIs (`authorizer.claims`) and Lambda authorizers (`authorizer.lambda`
+/// for HTTP APIs, the flattened `authorizer` context for REST APIs).
+fn extract_authorizer_scopes(context: &Value) -> Option<Vec<String>> {
+    let authorizer = match context.get("authorizer") {
+        Some(authorizer)
+            if authorizer
+                .as_object()
+                .is_some_and(|fields| !fields.is_empty()) =>
+        {
+            authorizer
+        }
+        _ => return None,
+    };
+    let jwt = authorizer.get("jwt");
+    let candidates = [
+        jwt.and_then(|jwt| jwt.get("scopes")),
+        jwt.and_then(|jwt| jwt.get("claims")),
+        authorizer.get("claims"),
+        authorizer.get("lambda"),
+        Some(authorizer),
+    ];
+
+    for candidate in candidates.into_iter().flatten() {
+        if let Some(scopes) = scopes_from_value(candidate) {
+            return Some(scopes);
+        }
+        for key in SCOPE_KEYS {
+            if let Some(scopes) = candidate.get(key).and_then(scopes_from_value) {
+                return Some(scopes);
+            }
+        }
+    }
+    Some(vec![])
+}
+
+/// Reads scopes either from a JSON array or a space (or comma) separated string.
+fn scopes_from_value(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::Array(scopes) => Some(
+            scopes
+                .iter()
+                .filter_map(Value::as_str)
+                .map(String::from)
+                .collect(),
+        ),
+        Value::String(scopes) => Some(
+            scopes
+                .split(|c: char| c.is_whitespace() || c == ',')
+                .filter(|scope| !scope.is_empty())
+                .map(String::from)
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::traverser::OpenApiTraverser;
+    use crate::types::security::{Credential, SecurityHandler, SecurityRejection};
+    use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+    use crate::validator::custom::RequestValidator;
+    use serde_json::json;
+
+    struct ApiKeyStore;
+
+    impl SecurityHandler for ApiKeyStore {
+        fn verify(
+            &self,
+            _scheme_name: &str,
+            credential: &Credential,
+        ) -> Result<Vec<String>, SecurityRejection> {
+            match credential {
+                Credential::ApiKey(key) if key == "admin-key" => Ok(vec!["pets:write".to_string()]),
+                _ => Err(SecurityRejection::new("unknown API key")),
+            }
+        }
+    }
+
+    struct RejectAll;
+
+    impl RequestValidator for RejectAll {
+        fn validate(
+            &self,
+            _traverser: &OpenApiTraverser,
+            operation: &Operation,
+            _request: &DecodedRequest<'_>,
+        ) -> Result<(), ValidationError> {
+            Err(ValidationError::validation_error(
+                format!("Rejected {}", operation.operation_id().unwrap_or_default()),
+                &Value::Null,
+            ))
+        }
+    }
+
+    fn create_builder() -> OpenApiPayloadValidatorBuilder {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "operationId": "createPet",
+                        "requestBody": {
+                            "required": true,
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "object",
+                                        "required": ["name"],
+                                        "properties": { "name": { "type": "string" } }
+                                    }
+                                }
+                            }
+                        },
+                        "security": [{ "oauth": ["pets:write"] }, { "apiKey": ["pets:write"] }]
+                    }
+                },
+                "/pets/{petId}": {
+                    "get": {
+                        "operationId": "getPet",
+                        "parameters": [
+                            { "name": "petId", "in": "path", "required": true, "schema": { "type": "integer" } },
+                            { "name": "limit", "in": "query", "schema": { "type": "integer" } }
+                        ],
+                        "security": [{ "oauth": ["pets:read"] }]
+                    }
+                }
+            },
+            "components": {
+                "securitySchemes": {
+                    "oauth": {
+                        "type": "oauth2",
+                        "flows": {
+                            "clientCredentials": {
+                                "tokenUrl": "https://example.com/token",
+                                "scopes": { "pets:read": "Read pets", "pets:write": "Write pets" }
+                            }
+                        }
+                    },
+                    "apiKey": { "type": "apiKey", "in": "header", "name": "X-API-Key" }
+                }
+            }
+        });
+        OpenApiPayloadValidatorBuilder::new().load_from_value(spec)
+    }
+
+    /// An API Gateway REST API (v1) event of the `prod` stage.
+    fn rest_event(pet_id: &str, authorizer: Value) -> Request {
+        let event = json!({
+            "resource": "/pets/{petId}",
+            "path": format!("/pets/{}", pet_id),
+            "httpMethod": "GET",
+            "headers": { "Host": "abc123.execute-api.us-east-1.amazonaws.com" },
+            "multiValueHeaders": { "Host": ["abc123.execute-api.us-east-1.amazonaws.com"] },
+            "queryStringParameters": { "limit": "10" },
+            "multiValueQueryStringParameters": { "limit": ["10"] },
+            "pathParameters": { "petId": pet_id },
+            "stageVariables": null,
+            "requestContext": {
+                "accountId": "123456789012",
+                "apiId": "abc123",
+                "authorizer": authorizer,
+                "domainName": "abc123.execute-api.us-east-1.amazonaws.com",
+                "httpMethod": "GET",
+                "identity": { "sourceIp": "203.0.113.1" },
+                "path": format!("/prod/pets/{}", pet_id),
+                "protocol": "HTTP/1.1",
+                "requestId": "c6af9ac6-7b61-11e6-9a41-93e8deadbeef",
+                "requestTimeEpoch": 1583798639428u64,
+                "resourceId": "us4z18",
+                "resourcePath": "/pets/{petId}",
+                "stage": "prod"
+            },
+            "body": null,
+            "isBase64Encoded": false
+        });
+        lambda_http::request::from_str(&event.to_string()).unwrap()
+    }
+
+    /// An API Gateway HTTP API (v2) event of the `$default` stage.
+    fn http_event(body: &str, authorizer: Value) -> Request {
+        let event = json!({
+            "version": "2.0",
+            "routeKey": "POST /pets",
+            "rawPath": "/pets",
+            "rawQueryString": "",
+            "headers": {
+                "content-type": "application/json",
+                "host": "abc123.execute-api.us-east-1.amazonaws.com"
+            },
+            "requestContext": {
+                "accountId": "123456789012",
+                "apiId": "abc123",
+                "authorizer": authorizer,
+                "domainName": "abc123.execute-api.us-east-1.amazonaws.com",
+                "domainPrefix": "abc123",
+                "http": {
+                    "method": "POST",
+                    "path": "/pets",
+                    "protocol": "HTTP/1.1",
+                    "sourceIp": "203.0.113.1",
+                    "userAgent": "curl/8.0"
+                },
+                "requestId": "id",
+                "routeKey": "POST /pets",
+                "stage": "$default",
+                "time": "12/Mar/2020:19:03:58 +0000",
+                "timeEpoch": 1583348638390u64
+            },
+            "body": body,
+            "isBase64Encoded": false
+        });
+        lambda_http::request::from_str(&event.to_string()).unwrap()
+    }
+
+    /// An Application Load Balancer event.
+    fn alb_event(api_key: &str, body: &str) -> Request {
+        let event = json!({
+            "requestContext": {
+                "elb": {
+                    "targetGroupArn": "arn:aws:elasticloadbalancing:us-east-1:123456789012:targetgroup/pets/6d0ecf831eec9f09"
+                }
+            },
+            "httpMethod": "POST",
+            "path": "/pets",
+            "queryStringParameters": {},
+            "headers": {
+                "content-type": "application/json",
+                "host": "pets-123456789.us-east-1.elb.amazonaws.com",
+                "x-api-key": api_key
+            },
+            "isBase64Encoded": false,
+            "body": body
+        });
+        lambda_http::request::from_str(&event.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_join_multi_values() {
+        let pairs = vec![("tag", "a"), ("tag", "b"), ("limit", "10")];
+        let joined = join_multi_values(pairs.into_iter());
+        assert_eq!(joined.get("tag").unwrap(), "a,b");
+        assert_eq!(joined.get("limit").unwrap(), "10");
+    }
+
+    #[test]
+    fn test_extract_scopes_from_jwt_authorizer() {
+        let context = json!({
+            "authorizer": { "jwt": { "claims": { "scope": "read write" }, "scopes": null } }
+        });
+        assert_eq!(
+            extract_authorizer_scopes(&context).unwrap(),
+            vec!["read".to_string(), "write".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_extract_scopes_from_lambda_authorizer() {
+        let context = json!({ "authorizer": { "lambda": { "scopes": ["admin"] } } });
+        assert_eq!(
+            extract_authorizer_scopes(&context).unwrap(),
+            vec!["admin".to_string()]
+        );
+        let context = json!({ "authorizer": { "principalId": "user", "scope": "read" } });
+        assert_eq!(
+            extract_authorizer_scopes(&context).unwrap(),
+            vec!["read".to_string()]
+        );
+        assert!(extract_authorizer_scopes(&json!({ "stage": "prod" })).is_none());
+    }
+
+    #[test]
+    fn test_authorizer_without_scope_claim() {
+        let context = json!({ "authorizer": { "jwt": { "claims": { "sub": "user" } } } });
+        assert_eq!(
+            extract_authorizer_scopes(&context).unwrap(),
+            Vec::<String>::new()
+        );
+        assert!(extract_authorizer_scopes(&json!({ "authorizer": {} })).is_none());
+    }
+
+    #[test]
+    fn test_api_gateway_rest_event() {
+        let validator = create_builder().build().unwrap();
+        let authorizer = json!({ "principalId": "user", "claims": { "scope": "pets:read" } });
+
+        let request = rest_event("42", authorizer.clone());
+        assert_eq!(request.raw_http_path(), "/pets/42");
+        assert!(validator.validate_lambda_request(&request).is_ok());
+        assert!(validator
+            .validate_lambda_request(&rest_event("abc", authorizer))
+            .is_err());
+
+        let authorizer = json!({ "principalId": "user", "claims": { "sub": "user" } });
+        assert!(validator
+            .validate_lambda_request(&rest_event("42", authorizer))
+            .is_err());
+    }
+
+    #[test]
+    fn test_api_gateway_http_event() {
+        let validator = create_builder().build().unwrap();
+        let authorizer =
+            json!({ "jwt": { "claims": { "sub": "user" }, "scopes": ["pets:write"] } });
+        assert!(validator
+            .validate_lambda_request(&http_event(r#"{"name":"Rex"}"#, authorizer.clone()))
+            .is_ok());
+        assert!(validator
+            .validate_lambda_request(&http_event("{}", authorizer))
+            .is_err());
+
+        let authorizer = json!({ "jwt": { "claims": { "sub": "user" } } });
+        assert!(validator
+            .validate_lambda_request(&http_event(r#"{"name":"Rex"}"#, authorizer))
+            .is_err());
+    }
+
+    #[test]
+    fn test_alb_event() {
+        let validator = create_builder().build().unwrap();
+        assert!(validator
+            .validate_lambda_request(&alb_event("", r#"{"name":"Rex"}"#))
+            .is_ok());
+        assert!(validator
+            .validate_lambda_request(&alb_event("", r#"{"name":1}"#))
+            .is_err());
+
+        let validator = create_builder()
+            .security_handler("apiKey", ApiKeyStore)
+            .build()
+            .unwrap();
+        assert!(validator
+            .validate_lambda_request(&alb_event("admin-key", r#"{"name":"Rex"}"#))
+            .is_ok());
+        assert!(validator
+            .validate_lambda_request(&alb_event("stolen-key", r#"{"name":"Rex"}"#))
+            .is_err());
+    }
+
+    #[test]
+    fn test_custom_validators() {
+        let validator = create_builder()
+            .operation_validator("createPet", RejectAll)
+            .build()
+            .unwrap();
+        let authorizer =
+            json!({ "jwt": { "claims": { "sub": "user" }, "scopes": ["pets:write"] } });
+        let result =
+            validator.validate_lambda_request(&http_event(r#"{"name":"Rex"}"#, authorizer));
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .contains("Rejected createPet"));
+    }
+}
@@ -1,84 +1,85 @@
+mod access_mode;
 pub mod builder;
+pub mod custom;
+mod discriminator;
+pub mod example;
+#[cfg(feature = "lambda_http")]
+mod lambda;
 mod request_body;
 mod request_parameter;
+mod response_body;
 mod scope;
+mod security;
 
 use crate::converter::HttpLike;
+use crate::dialect;
+#[cfg(feature = "jwt")]
+use crate::jwt::JwtVerifier;
 use crate::error::ValidationErrorType;
 use crate::traverser::{OpenApiTraverser, TraverserError};
 use crate::types::json_path::JsonPath;
 use crate::types::primitive::PrimitiveError;
-use crate::types::version::OpenApiVersion;
-use crate::types::{operation::Operation, ParameterLocation};
+use crate::types::security::{SchemeScopes, SecurityMatch};
+use crate::types::{operation::Operation, Direction, ParameterLocation};
+use crate::validator::access_mode::{AccessModeFilter, AccessModes};
+use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+use crate::validator::custom::{CustomValidators, DecodedRequest};
 use crate::validator::request_body::RequestBodyValidator;
 use crate::validator::request_parameter::RequestParameterValidator;
+use crate::validator::response_body::ResponseBodyValidator;
 use crate::validator::scope::RequestScopeValidator;
-use crate::{OPENAPI_FIELD, REF_FIELD};
+use crate::validator::security::{RequestSecurityValidator, SecurityHandlers};
+use crate::{CONTENT_FIELD, REF_FIELD, REQUEST_BODY_FIELD, RESPONSES_FIELD, SCHEMA_FIELD};
 use http::HeaderMap;
-use jsonschema::{Resource, ValidationOptions, Validator as JsonValidator};
+use jsonschema::{ValidationOptions, Validator as JsonValidator};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
 use serde_json::{json, Value};
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
-use std::str::FromStr;
 use std::sync::Arc;
 
+/// Characters of a JSON pointer that must be percent-encoded in a URI fragment, e.g. the braces
+/// of a templated path (`/pets/{petId}`).
+const FRAGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'%')
+    .add(b'<')
+    .add(b'>')
+    .add(b'\\')
+    .add(b'^')
+    .add(b'`')
+    .add(b'{')
+    .add(b'|')
+    .add(b'}');
+
 pub struct OpenApiPayloadValidator {
     traverser: OpenApiTraverser,
     options: ValidationOptions,
+    security_handlers: SecurityHandlers,
+    #[cfg(feature = "jwt")]
+    jwt_verifier: Option<Arc<JwtVerifier>>,
+    custom_validators: CustomValidators,
+    /// Whether the `security` requirements of operations are evaluated, see
+    /// `OpenApiPayloadValidatorBuilder::enforce_security`.
+    enforce_security: bool,
+    access_modes: AccessModes,
 }
 
 impl OpenApiPayloadValidator {
+    /// Creates a validator with the default options, see `OpenApiPayloadValidatorBuilder` to
+    /// set others. Same as `OpenApiPayloadValidatorBuilder::new().load_from_value(value).build()`.
     pub fn new(value: Value) -> Result<Self, ValidationErrorType> {
-        let mut value = value;
-        value["$id"] = json!("@@root");
-        let version = match OpenApiTraverser::get_as_str(&value, OPENAPI_FIELD) {
-            Ok(version) => version,
-            Err(e) => {
-                return Err(ValidationErrorType::traversal_failed(
-                    e,
-                    "Failed to get 'openapi' from provided specification.",
-                ));
-            }
-        };
-        let version = match OpenApiVersion::from_str(version) {
-            Ok(version) => version,
-            Err(e) => {
-                return Err(ValidationErrorType::version_failed(
-                    e,
-                    "Failed to parse version from provided specification.",
-                ));
-            }
-        };
-        let draft = version.get_draft();
-
-        // Create this resource once and re-use it for multiple validation calls.
-        let resource = match Resource::from_contents(value.clone()) {
-            Ok(res) => res,
-            Err(e) => {
-                return Err(ValidationErrorType::resource_load_error(
-                    e,
-                    "Failed to create resource from provided specification",
-                ));
-            }
-        };
-
-        // Assign draft and provide resource
-        let options = JsonValidator::options()
-            .with_draft(draft)
-            .with_resource("@@inner", resource);
-
-        // Create the traverser with owned value
-        let traverser = match OpenApiTraverser::new(value) {
-            Ok(traverser) => traverser,
-            Err(e) => {
-                return Err(ValidationErrorType::traversal_failed(
-                    e,
-                    "Failed to create traverser from provided specification.",
-                ));
-            }
-        };
-
-        Ok(Self { traverser, options })
+        match OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(value)
+            .build()
+        {
+            Ok(validator) => Ok(validator),
+            Err(e) => Err(ValidationErrorType::builder_failed(
+                e,
+                "Failed to create validator from provided specification.",
+            )),
+        }
     }
 
     pub fn traverser(&self) -> &OpenApiTraverser {
@@ -112,6 +113,37 @@ impl OpenApiPayloadValidator {
         None
     }
 
+    /// Parses a raw query string into percent-decoded key-value pairs.
+    fn parse_query_string(query_params: &str) -> HashMap<String, String> {
+        query_params
+            .split("&")
+            .filter_map(|pair| {
+                let mut parts = pair.split('=');
+                match (parts.next(), parts.next()) {
+                    (Some(key), Some(value)) => {
+                        let key = percent_encoding::percent_decode_str(key)
+                            .decode_utf8_lossy()
+                            .to_string();
+                        let value = percent_encoding::percent_decode_str(value)
+                            .decode_utf8_lossy()
+                            .to_string();
+                        Some((key, value))
+                    }
+                    (Some(key), None) => {
+                        let key = percent_encoding::percent_decode_str(key)
+                            .decode_utf8_lossy()
+                            .to_string();
+                        Some((key, "".to_string()))
+                    }
+                    _ => {
+                        log::warn!("Invalid query parameter: {}", pair);
+                        None
+                    }
+                }
+            })
+            .collect()
+    }
+
     /// # find_operation
     ///
     /// Retrieves an OpenAPI Operation object that matches the specified path and HTTP method.
@@ -130,8 +162,10 @@ impl OpenApiPayloadValidator {
     /// ## Returns
     ///
     /// * `Ok(Arc<Operation>)` - Pointer to the Operation object if a matching path and method combination is found in the specification.
-    /// * `Err(ValidationErrorType)` - An error indicating why the operation couldn't be found,
-    ///   typically `ValidationErrorType::FieldExpected` when no matching path+method is found
+    /// * `Err(ValidationError)` - An error indicating why the operation couldn't be found,
+    ///   typically `ValidationTraversalError(PathNotFound)` when no matching path+method is found,
+    ///   or `ValidationTraversalError(OperationNotServed)` when the builder's operation filter
+    ///   excludes it
     ///
     /// ## Example
     ///
@@ -177,7 +211,7 @@ impl OpenApiPayloadValidator {
             .get_operation_from_path_and_method(path, method)
         {
             Ok(op) => Ok(op),
-            Err(_) => todo!(),
+            Err(e) => Err(ValidationError::validation_traversal_error(e)),
         }
     }
 
@@ -256,16 +290,132 @@ impl OpenApiPayloadValidator {
         let body_instance = request.converted_body();
         match serde_json::to_value(body_instance) {
             Ok(body) => {
-                let validator = RequestBodyValidator::new(Some(&body), content_type);
+                let validator =
+                    RequestBodyValidator::new(Some(&body), content_type, &self.access_modes);
                 validator.validate(&self.traverser, operation, &self.options)
             }
             Err(_) => {
-                let validator = RequestBodyValidator::new(None, content_type);
+                let validator = RequestBodyValidator::new(None, content_type, &self.access_modes);
                 validator.validate(&self.traverser, operation, &self.options)
             }
         }
     }
 
+    /// # validate_response_body
+    ///
+    /// Validates a response body against the response an OpenAPI operation defines for its status.
+    ///
+    /// The response is looked up by the exact status code, then by its range (e.g. `2XX`), then
+    /// `default`. `writeOnly` properties are handled according to the `AccessModePolicy`.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The OpenAPI operation specification to validate against
+    /// * `status` - The status code of the response
+    /// * `content_type` - The media type of the response, without parameters
+    /// * `body` - The response body, if any
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If the response body is valid, according to the OpenAPI specification
+    /// * `Err(ValidationError)` - If the status is not defined, or the body does not match the schema.
+    pub fn validate_response_body(
+        &self,
+        operation: &Operation,
+        status: u16,
+        content_type: Option<&str>,
+        body: Option<&Value>,
+    ) -> Result<(), ValidationError> {
+        let validator = ResponseBodyValidator::new(status, body, content_type, &self.access_modes);
+        validator.validate(&self.traverser, operation, &self.options)
+    }
+
+    /// Removes the `readOnly` properties from a request body, e.g. before passing it on to a
+    /// handler that should not see them.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The OpenAPI operation the request is for
+    /// * `content_type` - The media type of the request body
+    /// * `body` - The request body
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` - The JSON pointers of the removed properties
+    /// * `Err(ValidationError)` - If the operation does not define the content type.
+    pub fn strip_request_body(
+        &self,
+        operation: &Operation,
+        content_type: &str,
+        body: &mut Value,
+    ) -> Result<Vec<String>, ValidationError> {
+        match self.traverser.get_optional(&operation.data, REQUEST_BODY_FIELD) {
+            Ok(Some(request_body)) => {
+                self.strip_body(request_body.value(), content_type, body, Direction::Request)
+            }
+            Ok(None) => Ok(vec![]),
+            Err(e) => Err(ValidationError::validation_traversal_error(e)),
+        }
+    }
+
+    /// Removes the `writeOnly` properties from a response body, e.g. before sending it.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - The OpenAPI operation the response is for
+    /// * `status` - The status code of the response
+    /// * `content_type` - The media type of the response body
+    /// * `body` - The response body
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<String>)` - The JSON pointers of the removed properties
+    /// * `Err(ValidationError)` - If the operation does not define the status or content type.
+    pub fn strip_response_body(
+        &self,
+        operation: &Operation,
+        status: u16,
+        content_type: &str,
+        body: &mut Value,
+    ) -> Result<Vec<String>, ValidationError> {
+        let responses = match self.traverser.get_required(&operation.data, RESPONSES_FIELD) {
+            Ok(responses) => responses,
+            Err(e) => return Err(ValidationError::validation_traversal_error(e)),
+        };
+        match ResponseBodyValidator::find_response(responses.value(), status) {
+            Some((_, response)) => {
+                self.strip_body(response, content_type, body, Direction::Response)
+            }
+            None => Err(ValidationError::validation_error(
+                format!("Status '{}' is not defined in the responses of the operation", status),
+                &operation.data,
+            )),
+        }
+    }
+
+    fn strip_body(
+        &self,
+        body_def: &Value,
+        content_type: &str,
+        body: &mut Value,
+        direction: Direction,
+    ) -> Result<Vec<String>, ValidationError> {
+        let content_def = match self.traverser.get_required(body_def, CONTENT_FIELD) {
+            Ok(content_def) => content_def,
+            Err(e) => return Err(ValidationError::validation_traversal_error(e)),
+        };
+        let media_def = match self.traverser.get_required(content_def.value(), content_type) {
+            Ok(media_def) => media_def,
+            Err(e) => return Err(ValidationError::validation_traversal_error(e)),
+        };
+        match self.traverser.get_optional(media_def.value(), SCHEMA_FIELD) {
+            Ok(Some(schema)) => Ok(AccessModeFilter::new(self.traverser.specification(), direction)
+                .apply(schema.value(), body, true)),
+            Ok(None) => Ok(vec![]),
+            Err(e) => Err(ValidationError::validation_traversal_error(e)),
+        }
+    }
+
     /// # validate_request
     ///
     /// Validates an HTTP request against an OpenAPI specification.
@@ -275,7 +425,13 @@ impl OpenApiPayloadValidator {
     /// - Validating the request body against the schema for the specified content type
     /// - Validating request headers against parameter requirements
     /// - Validating query parameters against parameter requirements
+    /// - Validating the templated path segments against the path parameters
     /// - Validating that the request has the required scopes (if applicable)
+    /// - Verifying credentials with the registered `SecurityHandler`s (and bearer tokens with the
+    ///   registered `JwtVerifier`), when security is enforced, see
+    ///   `OpenApiPayloadValidatorBuilder::enforce_security`. Credentials neither of them verifies
+    ///   grant the provided scopes
+    /// - Running the custom `RequestValidator`s registered for the operation
     ///
     /// # Arguments
     ///
@@ -296,19 +452,102 @@ impl OpenApiPayloadValidator {
         T: serde::ser::Serialize,
     {
         let operation = self.find_operation(request.path_ref(), request.method_ref().as_str())?;
-        self.validate_request_body(&operation, request)?;
-        self.validate_request_header_params(&operation, request.headers_ref())?;
+        let decoded_request = Self::decode_request(&operation, request);
+        self.validate_decoded_request(&operation, &decoded_request, scopes)
+    }
+
+    /// Runs the stages of `validate_request` on a request matched to `operation`. Every runtime
+    /// specific entry point (e.g. `validate_lambda_request`) decodes its request and ends here,
+    /// so that they all validate the same way.
+    pub(crate) fn validate_decoded_request(
+        &self,
+        operation: &Operation,
+        request: &DecodedRequest<'_>,
+        scopes: Option<&Vec<String>>,
+    ) -> Result<(), ValidationError> {
+        let content_type = Self::extract_content_type(request.headers);
+        let validator =
+            RequestBodyValidator::new(request.body.as_ref(), content_type, &self.access_modes);
+        validator.validate(&self.traverser, operation, &self.options)?;
 
-        if let Some(query_params) = request.query_ref() {
-            self.validate_request_query_parameters(&operation, query_params)?;
+        self.validate_request_header_params(operation, request.headers)?;
+
+        let validator =
+            RequestParameterValidator::new(&request.query_params, ParameterLocation::Query);
+        validator.validate(&self.traverser, operation, &self.options)?;
+
+        self.validate_request_path_parameters(operation, &request.path_params)?;
+
+        match (scopes, self.enforce_security) {
+            (scopes, true) => {
+                self.evaluate_security(operation, request.headers, &request.query_params, scopes)?;
+            }
+            (Some(scopes), false) => self.validate_request_scopes(operation, scopes)?,
+            (None, false) => {}
         }
 
-        if let Some(scopes) = scopes {
-            self.validate_request_scopes(&operation, scopes)?;
+        self.run_custom_validators(operation, request)
+    }
+
+    /// Decodes the parts of a request the validation stages work on.
+    fn decode_request<'request, T>(
+        operation: &Operation,
+        request: &'request impl HttpLike<T>,
+    ) -> DecodedRequest<'request>
+    where
+        T: serde::ser::Serialize,
+    {
+        DecodedRequest {
+            method: request.method_ref(),
+            path: request.path_ref(),
+            headers: request.headers_ref(),
+            body: request.converted_body(),
+            query_params: match request.query_ref() {
+                Some(query_params) => Self::parse_query_string(query_params),
+                None => HashMap::new(),
+            },
+            path_params: operation.extract_path_parameters(request.path_ref()),
         }
+    }
+
+    /// # validate_request_custom
+    ///
+    /// Runs the custom `RequestValidator`s registered for an operation: the global ones, then
+    /// the ones registered for its `operationId`, then the ones registered for its tags.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - A reference to an Operation object containing the OpenAPI operation definition
+    /// * `request` - The request the operation was resolved from
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If every custom validator passes
+    /// * `Err(ValidationError)` - The error of the first custom validator that fails.
+    pub fn validate_request_custom<T>(
+        &self,
+        operation: &Operation,
+        request: &impl HttpLike<T>,
+    ) -> Result<(), ValidationError>
+    where
+        T: serde::ser::Serialize,
+    {
+        let decoded_request = Self::decode_request(operation, request);
+        self.run_custom_validators(operation, &decoded_request)
+    }
 
+    /// Runs the custom validators registered for an operation on a decoded request.
+    fn run_custom_validators(
+        &self,
+        operation: &Operation,
+        request: &DecodedRequest<'_>,
+    ) -> Result<(), ValidationError> {
+        for validator in self.custom_validators.for_operation(operation) {
+            validator.validate(&self.traverser, operation, request)?;
+        }
         Ok(())
     }
+
     /// # validate_request_header_params
     ///
     /// Validates HTTP request headers against OpenAPI operation specification parameters.
@@ -434,37 +673,101 @@ impl OpenApiPayloadValidator {
         operation: &Operation,
         query_params: &str,
     ) -> Result<(), ValidationError> {
-        let query_params: HashMap<String, String> = query_params
-            .split("&")
-            .filter_map(|pair| {
-                let mut parts = pair.split('=');
-                match (parts.next(), parts.next()) {
-                    (Some(key), Some(value)) => {
-                        let key = percent_encoding::percent_decode_str(key)
-                            .decode_utf8_lossy()
-                            .to_string();
-                        let value = percent_encoding::percent_decode_str(value)
-                            .decode_utf8_lossy()
-                            .to_string();
-                        Some((key, value))
-                    }
-                    (Some(key), None) => {
-                        let key = percent_encoding::percent_decode_str(key)
-                            .decode_utf8_lossy()
-                            .to_string();
-                        Some((key, "".to_string()))
-                    }
-                    _ => {
-                        log::warn!("Invalid query parameter: {}", pair);
-                        None
-                    }
-                }
-            })
-            .collect();
+        let query_params = Self::parse_query_string(query_params);
         let validator = RequestParameterValidator::new(&query_params, ParameterLocation::Query);
         validator.validate(&self.traverser, operation, &self.options)
     }
 
+    /// # validate_request_security
+    ///
+    /// Validates that the request carries credentials for the security schemes required by an
+    /// operation (falling back to the global `security` requirements).
+    ///
+    /// Each scheme referenced by a requirement is looked up in `components/securitySchemes` and
+    /// its credential is extracted from the request:
+    /// - `apiKey` - The named header, query parameter or cookie
+    /// - `http` - The `Authorization` header with the declared scheme (`basic` credentials are
+    ///   decoded, `bearer` tokens must look like a JWT when `bearerFormat` is `JWT`)
+    /// - `oauth2` / `openIdConnect` - A bearer token in the `Authorization` header
+    /// - `mutualTLS` - Assumed to be enforced by the transport
+    ///
+    /// Credentials of schemes with a registered `SecurityHandler` are passed to it, and bearer
+    /// tokens of other `oauth2`/`openIdConnect` schemes to the registered `JwtVerifier`. The
+    /// scopes they grant must include the scopes listed for the scheme in the requirement. A
+    /// scheme that lists scopes is not satisfied by a credential neither of them verified.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - A reference to an Operation object containing the OpenAPI operation definition
+    /// * `request` - The request to extract the credentials from
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<SecurityMatch>)` - The schemes of the satisfied requirement and their credentials;
+    ///   empty if the operation has no security requirements or allows anonymous access
+    /// * `Err(ValidationError)` - If no requirement is satisfied or a credential is malformed:
+    ///   `Forbidden` if an accepted credential lacks the required scopes, `Unauthorized` otherwise.
+    pub fn validate_request_security<T>(
+        &self,
+        operation: &Operation,
+        request: &impl HttpLike<T>,
+    ) -> Result<Vec<SecurityMatch>, ValidationError>
+    where
+        T: serde::ser::Serialize,
+    {
+        let query_params = match request.query_ref() {
+            Some(query_params) => Self::parse_query_string(query_params),
+            None => HashMap::new(),
+        };
+        self.evaluate_security(operation, request.headers_ref(), &query_params, None)
+    }
+
+    /// Evaluates the security requirements of an operation with the registered `SecurityHandler`s
+    /// and `JwtVerifier`. Credentials neither of them verifies grant the `scopes` provided by the
+    /// caller, if any.
+    fn evaluate_security(
+        &self,
+        operation: &Operation,
+        headers: &HeaderMap,
+        query_params: &HashMap<String, String>,
+        scopes: Option<&Vec<String>>,
+    ) -> Result<Vec<SecurityMatch>, ValidationError> {
+        let validator = RequestSecurityValidator::new(headers, query_params)
+            .with_handlers(&self.security_handlers);
+        let validator = match scopes {
+            Some(scopes) => validator.with_granted_scopes(scopes),
+            None => validator,
+        };
+        #[cfg(feature = "jwt")]
+        let validator = match &self.jwt_verifier {
+            Some(jwt_verifier) => validator.with_jwt_verifier(jwt_verifier),
+            None => validator,
+        };
+        validator.evaluate(&self.traverser, operation)
+    }
+
+    /// # validate_request_path_parameters
+    ///
+    /// Validates already extracted path parameters against an OpenAPI operation definition.
+    ///
+    /// ## Arguments
+    ///
+    /// * `operation` - A reference to an Operation object containing the OpenAPI operation definition
+    /// * `path_params` - The path parameters of the request keyed by their template name
+    ///
+    /// ## Returns
+    ///
+    /// * `Ok(())` - If all path parameters are valid, according to the operation definition
+    /// * `Err(ValidationError)` - If validation fails.
+    pub fn validate_request_path_parameters(
+        &self,
+        operation: &Operation,
+        path_params: &HashMap<String, String>,
+    ) -> Result<(), ValidationError> {
+        let validator = RequestParameterValidator::new(path_params, ParameterLocation::Path);
+        validator.validate(&self.traverser, operation, &self.options)
+    }
+
     /// # validate_request_scopes
     ///
     /// Validates that the provided request scopes satisfy the security requirements defined in the OpenAPI specification.
@@ -481,7 +784,7 @@ impl OpenApiPayloadValidator {
     /// # Returns
     ///
     /// * `Ok(())` - If the provided scopes satisfy the security requirements for the operation
-    /// * `Err(ValidationErrorType)` - If validation fails, with the specific error type describing the reason
+    /// * `Err(ValidationErrorType)` - `Forbidden` if the scopes satisfy no security requirement
     ///
     /// # Example
     ///
@@ -526,6 +829,33 @@ impl OpenApiPayloadValidator {
         let validator = RequestScopeValidator::new(scopes);
         validator.validate(&self.traverser, operation, &self.options)
     }
+
+    /// # validate_request_scheme_scopes
+    ///
+    /// Validates scopes granted per security scheme against the security requirements of an operation.
+    ///
+    /// Unlike `validate_request_scopes`, where the same scopes apply to every scheme, a scheme
+    /// that is missing from `scopes` is treated as not presented. A requirement object is only
+    /// satisfied when every scheme in it is present with all of its required scopes, e.g.
+    /// `{"oauth2": ["read"], "apiKey": []}` requires both `oauth2` (with `read`) and `apiKey`.
+    ///
+    /// # Arguments
+    ///
+    /// * `operation` - A reference to an `Operation` struct representing the API operation being validated
+    /// * `scopes` - The scopes granted to the request, keyed by the name of the security scheme
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(())` - If at least one security requirement of the operation is satisfied
+    /// * `Err(ValidationError)` - `Forbidden` if no requirement is satisfied.
+    pub fn validate_request_scheme_scopes(
+        &self,
+        operation: &Operation,
+        scopes: &SchemeScopes,
+    ) -> Result<(), ValidationError> {
+        let validator = RequestScopeValidator::per_scheme(scopes);
+        validator.validate(&self.traverser, operation, &self.options)
+    }
 }
 
 pub(crate) trait Validator {
@@ -555,6 +885,7 @@ pub(crate) trait Validator {
     ///
     /// # Arguments
     /// * `options` - The validation options used to configure the validator
+    /// * `root` - The resource the path is resolved in
     /// * `json_path` - A path reference to the schema to validate against
     /// * `instance` - The JSON value to validate
     /// * `section` - The section context for error reporting
@@ -564,10 +895,12 @@ pub(crate) trait Validator {
     /// * `Err(ValidationError)` - If validation fails.
     fn complex_validation_by_path<'a>(
         options: &ValidationOptions,
+        root: &str,
         json_path: &JsonPath,
         instance: &Value,
     ) -> Result<(), ValidationError> {
-        let full_pointer_path = format!("@@root#/{}", json_path.format_path());
+        let pointer = utf8_percent_encode(&json_path.format_path(), FRAGMENT).to_string();
+        let full_pointer_path = format!("{}#/{}", root, pointer);
         let schema = json!({
             REF_FIELD: full_pointer_path
         });
@@ -635,7 +968,13 @@ pub(crate) trait Validator {
         validation_options: &ValidationOptions,
         schema: &Value,
     ) -> Result<JsonValidator, ValidationError> {
-        let validator = match validation_options.build(&schema) {
+        // The options pin the draft of the specification, a schema's own '$schema' takes precedence.
+        let built = match dialect::declared_draft(schema) {
+            Some(Ok(draft)) => validation_options.clone().with_draft(draft).build(schema),
+            Some(Err(_)) => return Err(ValidationError::validator_builder_error(schema)),
+            None => validation_options.build(schema),
+        };
+        let validator = match built {
             Ok(val) => val,
             Err(e) => {
                 return Err(ValidationError::validator_builder_error(schema));
@@ -651,6 +990,10 @@ pub enum ValidationError {
     ValidationError(String),
     ValidationTraversalError(TraverserError),
     ValidationPrimitiveError(PrimitiveError),
+    /// No security requirement is satisfied: the credentials are missing or were rejected.
+    Unauthorized(String),
+    /// The credentials were accepted, but do not grant the scopes a requirement lists.
+    Forbidden(String),
 }
 
 impl ValidationError {
@@ -669,6 +1012,18 @@ impl ValidationError {
         ValidationError::ValidationError(full_msg)
     }
 
+    #[inline]
+    pub fn unauthorized(msg: impl Into<String>, instance: &Value) -> Self {
+        let full_msg = format!("{} for {}", msg.into(), instance);
+        ValidationError::Unauthorized(full_msg)
+    }
+
+    #[inline]
+    pub fn forbidden(msg: impl Into<String>, instance: &Value) -> Self {
+        let full_msg = format!("{} for {}", msg.into(), instance);
+        ValidationError::Forbidden(full_msg)
+    }
+
     #[inline]
     pub const fn validation_traversal_error(e: TraverserError) -> Self {
         ValidationError::ValidationTraversalError(e)
@@ -695,6 +1050,12 @@ impl Display for ValidationError {
             ValidationError::ValidationPrimitiveError(e) => {
                 write!(f, "Primitive error occurred while validating: {}", e)
             }
+            ValidationError::Unauthorized(msg) => {
+                write!(f, "Unauthorized: {}", msg)
+            }
+            ValidationError::Forbidden(msg) => {
+                write!(f, "Forbidden: {}", msg)
+            }
         }
     }
 }
@@ -706,6 +1067,7 @@ mod test {
     use super::*;
     use http::{HeaderMap, HeaderValue, Method, Request, Uri};
     use serde_json::json;
+    use crate::types::AccessModePolicy;
 
     // Helper function to create a validator for testing
     fn create_test_validator() -> OpenApiPayloadValidator {
@@ -947,4 +1309,363 @@ mod test {
         let result = validator.validate_request(&request, None);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_oas30_nullable_and_exclusive_bounds() {
+        let spec = json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/items": {
+                    "post": {
+                        "parameters": [
+                            {
+                                "name": "limit",
+                                "in": "query",
+                                "schema": { "type": "integer", "nullable": true }
+                            }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Item" }
+                                }
+                            }
+                        },
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Item": {
+                        "type": "object",
+                        "properties": {
+                            "name": { "type": "string", "nullable": true, "example": "pen" },
+                            "price": { "type": "number", "minimum": 0, "exclusiveMinimum": true }
+                        }
+                    }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidator::new(spec).unwrap();
+        let request = |query: &str, body: Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("https://example.com/items{}", query))
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+
+        let valid = request("?limit=5", json!({ "name": null, "price": 1.5 }));
+        assert!(validator.validate_request(&valid, None).is_ok());
+
+        // 'exclusiveMinimum: true' excludes the minimum itself
+        let zero_price = request("", json!({ "name": "pen", "price": 0 }));
+        assert!(validator.validate_request(&zero_price, None).is_err());
+
+        let invalid_limit = request("?limit=many", json!({ "price": 1 }));
+        assert!(validator.validate_request(&invalid_limit, None).is_err());
+    }
+
+    #[test]
+    fn test_oas31_dialects_and_dynamic_refs() {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/trees": {
+                    "post": {
+                        "parameters": [
+                            { "name": "depth", "in": "query", "schema": { "type": ["integer", "null"] } },
+                            {
+                                "name": "order",
+                                "in": "query",
+                                "schema": { "oneOf": [{ "const": 1 }, { "enum": ["asc", "desc"] }] }
+                            }
+                        ],
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/StringTree" }
+                                }
+                            }
+                        },
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                },
+                "/prices": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "$schema": "http://json-schema.org/draft-04/schema#",
+                                        "type": "number",
+                                        "minimum": 0,
+                                        "exclusiveMinimum": true
+                                    }
+                                }
+                            }
+                        },
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Tree": {
+                        "$id": "tree",
+                        "$dynamicAnchor": "node",
+                        "type": "object",
+                        "properties": {
+                            "value": true,
+                            "children": { "type": "array", "items": { "$dynamicRef": "#node" } }
+                        }
+                    },
+                    "StringTree": {
+                        "$id": "string-tree",
+                        "$dynamicAnchor": "node",
+                        "$ref": "tree",
+                        "properties": { "value": { "type": "string" } }
+                    }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidator::new(spec).unwrap();
+        let request = |uri: &str, body: Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri(format!("https://example.com{}", uri))
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+
+        let tree = json!({ "value": "a", "children": [{ "value": "b" }] });
+        let valid = request("/trees?depth=3&order=desc", tree);
+        assert!(validator.validate_request(&valid, None).is_ok());
+        let valid = request("/trees?depth=null&order=1", json!({ "value": "a" }));
+        assert!(validator.validate_request(&valid, None).is_ok());
+
+        // '$dynamicRef' resolves to the string tree, so nested values must be strings as well
+        let tree = json!({ "value": "a", "children": [{ "value": 1 }] });
+        assert!(validator.validate_request(&request("/trees", tree), None).is_err());
+        let invalid_order = request("/trees?order=2", json!({ "value": "a" }));
+        assert!(validator.validate_request(&invalid_order, None).is_err());
+
+        // The Draft 4 schema gives 'exclusiveMinimum' its boolean meaning
+        assert!(validator.validate_request(&request("/prices", json!(1)), None).is_ok());
+        assert!(validator.validate_request(&request("/prices", json!(0)), None).is_err());
+    }
+
+    #[test]
+    fn test_oas31_json_schema_dialect() {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "jsonSchemaDialect": "http://json-schema.org/draft-07/schema#",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/items": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "type": "array",
+                                        "items": [{ "type": "string" }, { "type": "integer" }]
+                                    }
+                                }
+                            }
+                        },
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidator::new(spec).unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("https://example.com/items")
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+        // Draft 7 'items' arrays validate positionally
+        assert!(validator.validate_request(&request(json!(["a", 1])), None).is_ok());
+        assert!(validator.validate_request(&request(json!(["a", "b"])), None).is_err());
+
+        let spec = json!({
+            "openapi": "3.1.0",
+            "jsonSchemaDialect": "urn:unknown",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {}
+        });
+        assert!(OpenApiPayloadValidator::new(spec).is_err());
+    }
+
+    #[test]
+    fn test_access_modes() {
+        let spec = json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/users": {
+                    "post": {
+                        "operationId": "createUser",
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/User" }
+                                }
+                            }
+                        },
+                        "responses": {
+                            "201": {
+                                "description": "Created",
+                                "content": {
+                                    "application/json": {
+                                        "schema": { "$ref": "#/components/schemas/User" }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "User": {
+                        "type": "object",
+                        "required": ["id", "name", "password"],
+                        "properties": {
+                            "id": { "type": "integer", "readOnly": true },
+                            "name": { "type": "string" },
+                            "password": { "type": "string", "writeOnly": true }
+                        }
+                    }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidator::new(spec.clone()).unwrap();
+        let operation = validator.find_operation("/users", "POST").unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("https://example.com/users")
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+
+        // 'id' is not required in requests, and not allowed either
+        let created = json!({ "name": "a", "password": "secret" });
+        assert!(validator.validate_request(&request(created.clone()), None).is_ok());
+        let with_id = json!({ "id": 1, "name": "a", "password": "secret" });
+        let result = validator.validate_request(&request(with_id.clone()), None);
+        assert!(result.unwrap_err().to_string().contains("/id"));
+        assert!(validator.validate_request(&request(json!({ "name": "a" })), None).is_err());
+
+        // 'password' is not required in responses, and not allowed either
+        let content_type = Some("application/json");
+        let user = json!({ "id": 1, "name": "a" });
+        assert!(validator.validate_response_body(&operation, 201, content_type, Some(&user)).is_ok());
+        let result = validator.validate_response_body(&operation, 201, content_type, Some(&with_id));
+        assert!(result.unwrap_err().to_string().contains("/password"));
+        assert!(validator.validate_response_body(&operation, 500, content_type, Some(&user)).is_err());
+
+        let mut response = with_id.clone();
+        let stripped = validator
+            .strip_response_body(&operation, 201, "application/json", &mut response)
+            .unwrap();
+        assert_eq!(stripped, vec!["/password".to_string()]);
+        assert_eq!(response, user);
+
+        let validator = OpenApiPayloadValidatorBuilder::new()
+            .load_from_value(spec)
+            .access_mode_policy(AccessModePolicy::Strip)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&request(with_id), None).is_ok());
+        let invalid = json!({ "id": "not validated", "name": 1, "password": "secret" });
+        assert!(validator.validate_request(&request(invalid), None).is_err());
+    }
+
+    #[test]
+    fn test_discriminated_one_of() {
+        let spec = json!({
+            "openapi": "3.0.3",
+            "info": { "title": "Test API", "version": "1.0.0" },
+            "paths": {
+                "/pets": {
+                    "post": {
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": {
+                                        "oneOf": [
+                                            { "$ref": "#/components/schemas/Cat" },
+                                            { "$ref": "#/components/schemas/Dog" }
+                                        ],
+                                        "discriminator": { "propertyName": "petType" }
+                                    }
+                                }
+                            }
+                        },
+                        "responses": { "200": { "description": "Success" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Cat": {
+                        "type": "object",
+                        "required": ["petType"],
+                        "properties": {
+                            "petType": { "type": "string" },
+                            "lives": { "type": "integer" }
+                        }
+                    },
+                    "Dog": {
+                        "type": "object",
+                        "required": ["petType"],
+                        "properties": {
+                            "petType": { "type": "string" },
+                            "bark": { "type": "string" }
+                        }
+                    }
+                }
+            }
+        });
+        let validator = OpenApiPayloadValidator::new(spec).unwrap();
+        let request = |body: Value| {
+            Request::builder()
+                .method(Method::POST)
+                .uri("https://example.com/pets")
+                .header("content-type", "application/json")
+                .body(body)
+                .unwrap()
+        };
+
+        // Matches both subschemas, which a plain 'oneOf' rejects
+        let cat = json!({ "petType": "Cat", "lives": 9 });
+        assert!(validator.validate_request(&request(cat), None).is_ok());
+
+        let cat = json!({ "petType": "Cat", "lives": "many" });
+        let error = validator.validate_request(&request(cat), None).unwrap_err().to_string();
+        assert!(error.contains("#/components/schemas/Cat"));
+        assert!(error.contains("\"many\" is not of type \"integer\""));
+
+        let cow = json!({ "petType": "Cow" });
+        let error = validator.validate_request(&request(cow), None).unwrap_err().to_string();
+        assert!(error.contains("Unknown value 'Cow' of discriminator property 'petType'"));
+
+        // Only the schemas registered for validation are relaxed, the specification is not
+        let schema = &validator.traverser().specification()["paths"]["/pets"]["post"]
+            ["requestBody"]["content"]["application/json"]["schema"];
+        assert!(schema["oneOf"].is_array());
+        assert!(schema.get("anyOf").is_none());
+    }
 }
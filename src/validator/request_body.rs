@@ -1,19 +1,27 @@
 use crate::traverser::OpenApiTraverser;
 use crate::types::operation::Operation;
+use crate::types::Direction;
+use crate::validator::access_mode::{restricted_properties, AccessModes};
+use crate::validator::discriminator::DiscriminatorValidator;
 use crate::validator::{ValidationError, Validator};
 use crate::{CONTENT_FIELD, REQUEST_BODY_FIELD, REQUIRED_FIELD, SCHEMA_FIELD};
 use jsonschema::ValidationOptions;
 use serde_json::Value;
+use std::collections::HashSet;
+
+const PROPERTIES_FIELD: &str = "properties";
 
 pub(crate) struct RequestBodyValidator<'v> {
     request_instance: Option<&'v Value>,
     content_type: Option<&'v str>,
+    access_modes: &'v AccessModes,
 }
 
 impl<'v> RequestBodyValidator<'v> {
     pub(crate) fn new<'node>(
         request_instance: Option<&'node Value>,
         content_type: Option<&'v str>,
+        access_modes: &'v AccessModes,
     ) -> Self
     where
         'node: 'v,
@@ -21,16 +29,31 @@ impl<'v> RequestBodyValidator<'v> {
         Self {
             request_instance,
             content_type,
+            access_modes,
         }
     }
 
     /// Validates that all required fields specified in a schema are present in the request body.
+    ///
+    /// `readOnly` properties are exempt, as they are not sent in requests.
     fn check_required_body(
         traverser: &OpenApiTraverser,
         body_schema: &Value,
         request_body: Option<&Value>,
         operation_id: &str,
     ) -> Result<(), ValidationError> {
+        let read_only = match traverser.get_optional(body_schema, PROPERTIES_FIELD) {
+            Ok(Some(properties)) => restricted_properties(
+                traverser.specification(),
+                properties.value(),
+                Direction::Request,
+            ),
+            Ok(None) => HashSet::new(),
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+
         if let Some(required_fields) = match traverser.get_optional(body_schema, REQUIRED_FIELD) {
             Ok(req) => req,
             Err(e) => {
@@ -56,7 +79,7 @@ impl<'v> RequestBodyValidator<'v> {
             }
 
             if let Some(body) = request_body {
-                for required in required_fields {
+                for required in required_fields.iter().filter(|r| !read_only.contains(*r)) {
                     let required_field = match OpenApiTraverser::require_str(required) {
                         Ok(required_field) => required_field,
                         Err(e) => {
@@ -150,13 +173,24 @@ impl Validator for RequestBodyValidator<'_> {
             Self::check_required_body(traverser, media_schema.value(), body, &operation_id)?;
 
             if let Some(body_instance) = body {
+                let body_instance = self.access_modes.filter(
+                    traverser.specification(),
+                    media_schema.value(),
+                    body_instance,
+                    Direction::Request,
+                    operation_id,
+                )?;
                 op_path
                     .add(REQUEST_BODY_FIELD)
                     .add(CONTENT_FIELD)
                     .add(&content_type)
                     .add(SCHEMA_FIELD);
 
-                Self::complex_validation_by_path(&validation_opts, &op_path, body_instance)?
+                let root = self.access_modes.root_resource(Direction::Request);
+                DiscriminatorValidator::new(media_schema.value(), &body_instance, root.clone())
+                    .validate(traverser, op, validation_opts)?;
+
+                Self::complex_validation_by_path(&validation_opts, &root, &op_path, &body_instance)?
 
             // if the body does not exist, make sure 'required' is set to false.
             } else if is_body_required {
@@ -0,0 +1,192 @@
+use crate::traverser::OpenApiTraverser;
+use crate::types::json_path::JsonPath;
+use crate::types::operation::Operation;
+use crate::types::Direction;
+use crate::validator::access_mode::AccessModes;
+use crate::validator::discriminator::DiscriminatorValidator;
+use crate::validator::{ValidationError, Validator};
+use crate::{CONTENT_FIELD, PATH_SEPARATOR, REF_FIELD, RESPONSES_FIELD, SCHEMA_FIELD};
+use jsonschema::ValidationOptions;
+use serde_json::Value;
+
+const DEFAULT_RESPONSE: &str = "default";
+
+pub(crate) struct ResponseBodyValidator<'v> {
+    status: u16,
+    response_instance: Option<&'v Value>,
+    content_type: Option<&'v str>,
+    access_modes: &'v AccessModes,
+}
+
+impl<'v> ResponseBodyValidator<'v> {
+    pub(crate) fn new<'node>(
+        status: u16,
+        response_instance: Option<&'node Value>,
+        content_type: Option<&'v str>,
+        access_modes: &'v AccessModes,
+    ) -> Self
+    where
+        'node: 'v,
+    {
+        Self {
+            status,
+            response_instance,
+            content_type,
+            access_modes,
+        }
+    }
+
+    /// Finds the Response Object of a status code: the exact code first, then its range
+    /// (e.g. `2XX`), then `default`.
+    ///
+    /// # Returns
+    /// The key the response is defined under and the (possibly referenced) response.
+    pub(crate) fn find_response(responses: &Value, status: u16) -> Option<(String, &Value)> {
+        let responses = responses.as_object()?;
+        let status_code = status.to_string();
+        let status_range = format!("{}XX", status / 100);
+        responses
+            .iter()
+            .find(|(key, _)| **key == status_code)
+            .or_else(|| {
+                responses
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(&status_range))
+            })
+            .or_else(|| responses.iter().find(|(key, _)| *key == DEFAULT_RESPONSE))
+            .map(|(key, response)| (key.clone(), response))
+    }
+
+    /// The path of a Response Object, following it when it is a local `$ref`.
+    pub(crate) fn response_path(operation: &Operation, key: &str, response: &Value) -> JsonPath {
+        if let Some(pointer) = OpenApiTraverser::get_as_str(response, REF_FIELD)
+            .ok()
+            .and_then(|reference| reference.strip_prefix("#/"))
+        {
+            return JsonPath(pointer.split(PATH_SEPARATOR).map(String::from).collect());
+        }
+        let mut path = operation.path.clone();
+        path.add(RESPONSES_FIELD).add(key);
+        path
+    }
+}
+
+impl Validator for ResponseBodyValidator<'_> {
+    /// Validates the response body of an OpenAPI operation against the specification.
+    fn validate(
+        &self,
+        traverser: &OpenApiTraverser,
+        op: &Operation,
+        validation_opts: &ValidationOptions,
+    ) -> Result<(), ValidationError> {
+        let op_def = &op.data;
+        let body = self.response_instance.filter(|body| !body.is_null());
+        let operation_id = OpenApiTraverser::get_as_str(op_def, "operationId")
+            .unwrap_or("default_operation_id");
+
+        let responses = match traverser.get_required(op_def, RESPONSES_FIELD) {
+            Ok(responses) => responses,
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+        let (key, response_def) = match Self::find_response(responses.value(), self.status) {
+            Some(found) => found,
+            None => {
+                return Err(ValidationError::validation_error(
+                    format!(
+                        "Status '{}' is not defined in the responses of operation '{}'",
+                        self.status, operation_id
+                    ),
+                    op_def,
+                ));
+            }
+        };
+        let mut response_path = Self::response_path(op, &key, response_def);
+
+        let content_def = match traverser.get_optional(response_def, CONTENT_FIELD) {
+            Ok(content_def) => content_def,
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+        let (content_def, body) = match (content_def, body) {
+            (_, None) => return Ok(()),
+            (None, Some(body)) => {
+                return Err(ValidationError::validation_error(
+                    format!(
+                        "Response body is present, but response '{}' of operation '{}' has no content",
+                        key, operation_id
+                    ),
+                    body,
+                ));
+            }
+            (Some(content_def), Some(body)) => (content_def, body),
+        };
+        let content_type = match self.content_type {
+            Some(content_type) => content_type,
+            None => {
+                return Err(ValidationError::validation_error(
+                    format!(
+                        "Content-Type header is missing, but is required for response '{}' of operation '{}'",
+                        key, operation_id
+                    ),
+                    body,
+                ));
+            }
+        };
+
+        let media_def = match traverser.get_required(content_def.value(), content_type) {
+            Ok(media_def) => media_def,
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+        let media_schema = match traverser.get_optional(media_def.value(), SCHEMA_FIELD) {
+            Ok(Some(media_schema)) => media_schema,
+            Ok(None) => return Ok(()),
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+
+        let body = self.access_modes.filter(
+            traverser.specification(),
+            media_schema.value(),
+            body,
+            Direction::Response,
+            operation_id,
+        )?;
+        response_path
+            .add(CONTENT_FIELD)
+            .add(content_type)
+            .add(SCHEMA_FIELD);
+        let root = self.access_modes.root_resource(Direction::Response);
+        DiscriminatorValidator::new(media_schema.value(), &body, root.clone()).validate(
+            traverser,
+            op,
+            validation_opts,
+        )?;
+        Self::complex_validation_by_path(validation_opts, &root, &response_path, &body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_find_response() {
+        let responses = json!({
+            "200": { "description": "OK" },
+            "4XX": { "description": "Client error" },
+            "default": { "description": "Unexpected error" }
+        });
+        let find = |status| ResponseBodyValidator::find_response(&responses, status).map(|(key, _)| key);
+        assert_eq!(find(200), Some("200".to_string()));
+        assert_eq!(find(404), Some("4XX".to_string()));
+        assert_eq!(find(500), Some("default".to_string()));
+        assert_eq!(ResponseBodyValidator::find_response(&json!({ "200": {} }), 201), None);
+    }
+}
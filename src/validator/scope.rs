@@ -1,14 +1,52 @@
 use crate::error::ValidationErrorType;
 use crate::traverser::OpenApiTraverser;
 use crate::types::operation::Operation;
+use crate::types::security::SchemeScopes;
 use crate::validator::{ValidationError, Validator};
 use crate::SECURITY_FIELD;
 use jsonschema::ValidationOptions;
-use serde_json::Value;
+use serde_json::{Map, Value};
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// The scopes granted to a request.
+enum GrantedScopes<'validator> {
+    /// A single list of scopes that applies to every scheme in a requirement.
+    Shared(&'validator Vec<String>),
+
+    /// Scopes keyed by the scheme that granted them. Schemes that are absent were not presented.
+    PerScheme(&'validator SchemeScopes),
+}
+
+impl GrantedScopes<'_> {
+    fn for_scheme(&self, scheme_name: &str) -> Option<HashSet<&str>> {
+        match self {
+            GrantedScopes::Shared(scopes) => Some(scopes.iter().map(|s| s.as_str()).collect()),
+            GrantedScopes::PerScheme(scopes) => scopes
+                .get(scheme_name)
+                .map(|scopes| scopes.iter().map(|s| s.as_str()).collect()),
+        }
+    }
+}
+
+impl Display for GrantedScopes<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GrantedScopes::Shared(scopes) => write!(f, "{}", scopes.join(", ")),
+            GrantedScopes::PerScheme(scopes) => {
+                let mut schemes: Vec<String> = scopes
+                    .iter()
+                    .map(|(scheme, scopes)| format!("{}: [{}]", scheme, scopes.join(", ")))
+                    .collect();
+                schemes.sort();
+                write!(f, "{}", schemes.join(", "))
+            }
+        }
+    }
+}
 
 pub(crate) struct RequestScopeValidator<'validator> {
-    request_instance: &'validator Vec<String>,
+    request_instance: GrantedScopes<'validator>,
 }
 
 impl<'validator> RequestScopeValidator<'validator> {
@@ -16,12 +54,27 @@ impl<'validator> RequestScopeValidator<'validator> {
     where
         'node: 'validator,
     {
-        Self { request_instance }
+        Self {
+            request_instance: GrantedScopes::Shared(request_instance),
+        }
+    }
+
+    pub(crate) fn per_scheme<'node>(request_instance: &'node SchemeScopes) -> Self
+    where
+        'node: 'validator,
+    {
+        Self {
+            request_instance: GrantedScopes::PerScheme(request_instance),
+        }
     }
 
+    /// Checks the security requirements of an operation.
+    ///
+    /// The requirement objects in the array are alternatives (OR), while every scheme inside a
+    /// single requirement object has to be satisfied (AND).
     fn validate_scopes_using_schema(
+        &self,
         security_definitions: &Value,
-        request_scopes: &HashSet<&str>,
         operation_id: &str,
     ) -> Result<(), ValidationError> {
         let security_defs = match OpenApiTraverser::require_array(security_definitions) {
@@ -44,46 +97,53 @@ impl<'validator> RequestScopeValidator<'validator> {
                 }
             };
 
-            for (schema_name, scope_list) in security_def {
-                let scope_list = match OpenApiTraverser::require_array(scope_list) {
-                    Ok(scope_list) => scope_list,
+            if self.requirement_satisfied(security_def)? {
+                log::debug!(
+                    "Scopes match {}",
+                    security_def.keys().cloned().collect::<Vec<String>>().join(" + ")
+                );
+                return Ok(());
+            }
+        }
+        Err(ValidationError::forbidden(
+            format!(
+                "Request scopes {} did not match any security definition in operation '{}'",
+                self.request_instance, operation_id
+            ),
+            security_definitions,
+        ))
+    }
+
+    fn requirement_satisfied(
+        &self,
+        security_def: &Map<String, Value>,
+    ) -> Result<bool, ValidationError> {
+        for (schema_name, scope_list) in security_def {
+            let scope_list = match OpenApiTraverser::require_array(scope_list) {
+                Ok(scope_list) => scope_list,
+                Err(e) => {
+                    return Err(ValidationError::validation_traversal_error(e));
+                }
+            };
+
+            let request_scopes = match self.request_instance.for_scheme(schema_name) {
+                Some(request_scopes) => request_scopes,
+                None => return Ok(false),
+            };
+
+            for scope in scope_list {
+                let scope = match OpenApiTraverser::require_str(scope) {
+                    Ok(scope) => scope,
                     Err(e) => {
                         return Err(ValidationError::validation_traversal_error(e));
                     }
                 };
-
-                let mut scopes_match_schema = true;
-                'scope_match: for scope in scope_list {
-                    let scope = match OpenApiTraverser::require_str(scope) {
-                        Ok(scope) => scope,
-                        Err(e) => {
-                            return Err(ValidationError::validation_traversal_error(e));
-                        }
-                    };
-                    if !request_scopes.contains(scope) {
-                        scopes_match_schema = false;
-                        break 'scope_match;
-                    }
-                }
-
-                if scopes_match_schema {
-                    log::debug!("Scopes match {schema_name}");
-                    return Ok(());
+                if !request_scopes.contains(scope) {
+                    return Ok(false);
                 }
             }
         }
-        Err(ValidationError::validation_error(
-            &format!(
-                "Request scopes {} did not match any security definition in operation '{}'",
-                request_scopes
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect::<Vec<String>>()
-                    .join(", "),
-                operation_id
-            ),
-            security_definitions,
-        ))
+        Ok(true)
     }
 }
 
@@ -98,7 +158,6 @@ impl Validator for RequestScopeValidator<'_> {
         let operation_id = OpenApiTraverser::get_as_str(&op, "operationId")
             .unwrap_or_else(|_| "default_operation_id");
 
-        let scopes: HashSet<&str> = self.request_instance.iter().map(|s| s.as_str()).collect();
         let security_defs = match traverser.get_optional(op, SECURITY_FIELD) {
             Ok(security_defs) => security_defs,
             Err(e) => {
@@ -107,11 +166,7 @@ impl Validator for RequestScopeValidator<'_> {
         };
 
         if let Some(security_defs) = security_defs {
-            return Self::validate_scopes_using_schema(
-                security_defs.value(),
-                &scopes,
-                &operation_id,
-            );
+            return self.validate_scopes_using_schema(security_defs.value(), &operation_id);
         }
 
         let global_security_defs =
@@ -123,11 +178,7 @@ impl Validator for RequestScopeValidator<'_> {
             };
 
         if let Some(security_definitions) = global_security_defs {
-            return Self::validate_scopes_using_schema(
-                security_definitions.value(),
-                &scopes,
-                &operation_id,
-            );
+            return self.validate_scopes_using_schema(security_definitions.value(), &operation_id);
         }
         Ok(())
     }
@@ -137,6 +188,7 @@ impl Validator for RequestScopeValidator<'_> {
 mod test {
     use crate::types::json_path::JsonPath;
     use crate::types::operation::Operation;
+    use crate::types::security::SchemeScopes;
     use crate::validator::OpenApiPayloadValidator;
     use serde_json::{json, Value};
 
@@ -345,6 +397,44 @@ mod test {
     //        assert!(result.is_err());
     //    }
 
+    #[test]
+    fn test_validate_scheme_scopes_requires_every_scheme_in_requirement() {
+        let validator = create_validator_with_security_definitions(json!({}));
+        let operation = create_operation_with_security(json!([
+            { "oauth2": ["read"], "apiKey": [] },
+            { "admin": ["write"] }
+        ]));
+
+        let scopes = SchemeScopes::from([("oauth2".to_string(), vec!["read".to_string()])]);
+        let result = validator.validate_request_scheme_scopes(&operation, &scopes);
+        assert!(result.is_err());
+
+        let scopes = SchemeScopes::from([
+            ("oauth2".to_string(), vec!["read".to_string()]),
+            ("apiKey".to_string(), vec![]),
+        ]);
+        let result = validator.validate_request_scheme_scopes(&operation, &scopes);
+        assert!(result.is_ok());
+
+        let scopes = SchemeScopes::from([("admin".to_string(), vec!["write".to_string()])]);
+        let result = validator.validate_request_scheme_scopes(&operation, &scopes);
+        assert!(result.is_ok());
+
+        // Scopes granted by one scheme do not satisfy another
+        let scopes = SchemeScopes::from([("apiKey".to_string(), vec!["write".to_string()])]);
+        let result = validator.validate_request_scheme_scopes(&operation, &scopes);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_validate_request_scopes_anonymous_requirement() {
+        let validator = create_validator_with_security_definitions(json!({}));
+        let operation = create_operation_with_security(json!([{ "oauth2": ["read"] }, {}]));
+        let scopes = vec![];
+        let result = validator.validate_request_scopes(&operation, &scopes);
+        assert!(result.is_ok());
+    }
+
     #[test]
     fn test_validate_request_scopes_no_security_requirement() {
         let validator = create_validator_with_security_definitions(json!({}));
@@ -0,0 +1,861 @@
+#[cfg(feature = "jwt")]
+use crate::jwt::JwtVerifier;
+use crate::traverser::{OpenApiTraverser, TraverserError};
+use crate::types::operation::Operation;
+use crate::types::security::{Credential, SecurityHandler, SecurityMatch, SecuritySchemeType};
+use crate::types::ParameterLocation;
+use crate::validator::{ValidationError, Validator};
+use crate::{
+    COMPONENTS_FIELD, IN_FIELD, NAME_FIELD, SECURITY_FIELD, SECURITY_SCHEMES_FIELD, TYPE_FIELD,
+};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use http::header::{AUTHORIZATION, COOKIE};
+use http::HeaderMap;
+use jsonschema::ValidationOptions;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// The `SecurityHandler`s registered on the builder, keyed by `securitySchemes` name.
+pub(crate) type SecurityHandlers = HashMap<String, Arc<dyn SecurityHandler>>;
+
+/// The scopes a verified credential grants, with the claims of a verified token.
+type Grant = (Vec<String>, Option<Value>);
+
+/// Why a credential does not satisfy its scheme.
+enum Refusal {
+    /// The credential is malformed, or was not accepted by its verifier.
+    Rejected(String),
+    /// The credential is not proven to grant the scopes the requirement lists.
+    MissingScopes(String),
+}
+
+const SCHEME_FIELD: &str = "scheme";
+const BEARER_FORMAT_FIELD: &str = "bearerFormat";
+const BEARER_SCHEME: &str = "bearer";
+const BASIC_SCHEME: &str = "basic";
+
+pub(crate) struct RequestSecurityValidator<'validator> {
+    headers: &'validator HeaderMap,
+    query_params: &'validator HashMap<String, String>,
+    handlers: Option<&'validator SecurityHandlers>,
+    granted_scopes: Option<&'validator Vec<String>>,
+    #[cfg(feature = "jwt")]
+    jwt_verifier: Option<&'validator JwtVerifier>,
+}
+
+impl<'validator> RequestSecurityValidator<'validator> {
+    pub(crate) fn new<'node>(
+        headers: &'node HeaderMap,
+        query_params: &'node HashMap<String, String>,
+    ) -> Self
+    where
+        'node: 'validator,
+    {
+        Self {
+            headers,
+            query_params,
+            handlers: None,
+            granted_scopes: None,
+            #[cfg(feature = "jwt")]
+            jwt_verifier: None,
+        }
+    }
+
+    /// Verifies credentials with the handlers registered for their scheme and checks their scopes.
+    pub(crate) fn with_handlers<'node>(mut self, handlers: &'node SecurityHandlers) -> Self
+    where
+        'node: 'validator,
+    {
+        self.handlers = Some(handlers);
+        self
+    }
+
+    /// Takes the scopes of the credentials nothing verifies from the scopes granted to the
+    /// request by the caller, e.g. by an upstream authorizer.
+    pub(crate) fn with_granted_scopes<'node>(mut self, scopes: &'node Vec<String>) -> Self
+    where
+        'node: 'validator,
+    {
+        self.granted_scopes = Some(scopes);
+        self
+    }
+
+    /// Verifies the bearer tokens of `oauth2`/`openIdConnect` schemes and checks their scopes.
+    #[cfg(feature = "jwt")]
+    pub(crate) fn with_jwt_verifier<'node>(mut self, jwt_verifier: &'node JwtVerifier) -> Self
+    where
+        'node: 'validator,
+    {
+        self.jwt_verifier = Some(jwt_verifier);
+        self
+    }
+
+    /// Evaluates the security requirements of an operation (falling back to the global ones).
+    ///
+    /// The requirement objects are alternatives (OR), while every scheme inside a single
+    /// requirement object must have a credential in the request (AND).
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(Vec<SecurityMatch>)` - The schemes of the first satisfied requirement with their
+    ///   credentials. Empty if there are no requirements or anonymous access (`{}`) is allowed.
+    /// * `Err(ValidationError)` - If no requirement is satisfied or a credential is rejected:
+    ///   `Forbidden` if an accepted credential lacks the required scopes, `Unauthorized` otherwise.
+    pub(crate) fn evaluate(
+        &self,
+        traverser: &OpenApiTraverser,
+        op: &Operation,
+    ) -> Result<Vec<SecurityMatch>, ValidationError> {
+        let op_def = &op.data;
+        let operation_id = OpenApiTraverser::get_as_str(op_def, "operationId")
+            .unwrap_or("default_operation_id");
+
+        let security_defs = match traverser.get_optional(op_def, SECURITY_FIELD) {
+            Ok(security_defs) => security_defs,
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+        if let Some(security_defs) = security_defs {
+            return self.evaluate_requirements(traverser, security_defs.value(), operation_id);
+        }
+
+        let global_security_defs =
+            match traverser.get_optional(traverser.specification(), SECURITY_FIELD) {
+                Ok(global_security_defs) => global_security_defs,
+                Err(e) => {
+                    return Err(ValidationError::validation_traversal_error(e));
+                }
+            };
+        if let Some(security_defs) = global_security_defs {
+            return self.evaluate_requirements(traverser, security_defs.value(), operation_id);
+        }
+        Ok(vec![])
+    }
+
+    fn evaluate_requirements(
+        &self,
+        traverser: &OpenApiTraverser,
+        security_definitions: &Value,
+        operation_id: &str,
+    ) -> Result<Vec<SecurityMatch>, ValidationError> {
+        let security_defs = match OpenApiTraverser::require_array(security_definitions) {
+            Ok(security_defs) => security_defs,
+            Err(e) => {
+                return Err(ValidationError::validation_traversal_error(e));
+            }
+        };
+
+        if security_defs.is_empty() {
+            log::debug!("Definition is empty, security automatically passes");
+            return Ok(vec![]);
+        }
+
+        let mut allows_anonymous = false;
+        let mut rejected = vec![];
+        let mut missing_scopes = false;
+        for security_definition in security_defs {
+            let security_def = match OpenApiTraverser::require_object(security_definition) {
+                Ok(security_def) => security_def,
+                Err(e) => {
+                    return Err(ValidationError::validation_traversal_error(e));
+                }
+            };
+
+            if security_def.is_empty() {
+                allows_anonymous = true;
+                continue;
+            }
+
+            let mut matches = vec![];
+            for (scheme_name, required_scopes) in security_def {
+                let scheme_type = Self::resolve_scheme(traverser, scheme_name)
+                    .map_err(ValidationError::validation_traversal_error)?;
+                let credential = match self.extract_credential(&scheme_type) {
+                    Ok(Some(credential)) => credential,
+                    Ok(None) => break,
+                    Err(reason) => {
+                        rejected.push(format!("{}: {}", scheme_name, reason));
+                        break;
+                    }
+                };
+                match self.authorize(scheme_name, &scheme_type, &credential, required_scopes) {
+                    Ok((scopes, claims)) => matches.push(SecurityMatch {
+                        scheme_name: scheme_name.to_string(),
+                        scheme_type,
+                        credential,
+                        scopes,
+                        claims,
+                    }),
+                    Err(Refusal::Rejected(reason)) => {
+                        rejected.push(format!("{}: {}", scheme_name, reason));
+                        break;
+                    }
+                    Err(Refusal::MissingScopes(reason)) => {
+                        rejected.push(format!("{}: {}", scheme_name, reason));
+                        missing_scopes = true;
+                        break;
+                    }
+                }
+            }
+
+            // Every scheme in a requirement object has to be satisfied
+            if matches.len() == security_def.len() {
+                log::debug!(
+                    "Credentials match {}",
+                    security_def.keys().cloned().collect::<Vec<String>>().join(" + ")
+                );
+                return Ok(matches);
+            }
+        }
+
+        if allows_anonymous && rejected.is_empty() {
+            log::debug!("Optional security requirement present, allowing anonymous access");
+            return Ok(vec![]);
+        }
+
+        let reason = if rejected.is_empty() {
+            String::from("no credentials were found")
+        } else {
+            format!("rejected credentials ({})", rejected.join(", "))
+        };
+        let msg = format!(
+            "Request did not satisfy any security requirement in operation '{}': {}",
+            operation_id, reason
+        );
+        // An accepted credential that lacks scopes is forbidden rather than unauthenticated.
+        if missing_scopes {
+            Err(ValidationError::forbidden(msg, security_definitions))
+        } else {
+            Err(ValidationError::unauthorized(msg, security_definitions))
+        }
+    }
+
+    /// Checks a credential beyond its shape, returning the scopes and claims it grants.
+    ///
+    /// A `SecurityHandler` registered for the scheme decides first. Otherwise, bearer tokens of
+    /// `oauth2`/`openIdConnect` schemes are verified with the `JwtVerifier`, when one is set.
+    /// Verified credentials must grant every scope listed for the scheme in the requirement.
+    /// Any other credential is accepted as is and grants the scopes granted to the request by the
+    /// caller. Without those, it grants no scopes, so it only satisfies a scheme that requires
+    /// none.
+    fn authorize(
+        &self,
+        scheme_name: &str,
+        scheme_type: &SecuritySchemeType,
+        credential: &Credential,
+        required_scopes: &Value,
+    ) -> Result<Grant, Refusal> {
+        let handler = self.handlers.and_then(|handlers| handlers.get(scheme_name));
+        let (scopes, claims, verified) = match handler {
+            Some(handler) => match handler.verify(scheme_name, credential) {
+                Ok(scopes) => (scopes, None, true),
+                Err(rejection) => return Err(Refusal::Rejected(rejection.reason().to_string())),
+            },
+            None => match self.verify_jwt(scheme_type, credential) {
+                Ok(Some((scopes, claims))) => (scopes, claims, true),
+                Ok(None) => match self.granted_scopes {
+                    Some(granted_scopes) => (granted_scopes.clone(), None, true),
+                    None => (vec![], None, false),
+                },
+                Err(reason) => return Err(Refusal::Rejected(reason)),
+            },
+        };
+
+        let required: Vec<&str> = required_scopes
+            .as_array()
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if !required.is_empty() && !verified {
+            return Err(Refusal::MissingScopes(format!(
+                "nothing verifies the credential for the required scopes [{}]",
+                required.join(", ")
+            )));
+        }
+        let missing: Vec<&str> = required
+            .into_iter()
+            .filter(|scope| !scopes.iter().any(|granted| granted == scope))
+            .collect();
+        if !missing.is_empty() {
+            return Err(Refusal::MissingScopes(format!(
+                "credential is missing scopes [{}]",
+                missing.join(", ")
+            )));
+        }
+        Ok((scopes, claims))
+    }
+
+    #[cfg(not(feature = "jwt"))]
+    fn verify_jwt(
+        &self,
+        _scheme_type: &SecuritySchemeType,
+        _credential: &Credential,
+    ) -> Result<Option<Grant>, String> {
+        Ok(None)
+    }
+
+    #[cfg(feature = "jwt")]
+    fn verify_jwt(
+        &self,
+        scheme_type: &SecuritySchemeType,
+        credential: &Credential,
+    ) -> Result<Option<Grant>, String> {
+        let (jwt_verifier, token) = match (self.jwt_verifier, scheme_type, credential) {
+            (
+                Some(jwt_verifier),
+                SecuritySchemeType::OAuth2 | SecuritySchemeType::OpenIdConnect,
+                Credential::Bearer(token),
+            ) => (jwt_verifier, token),
+            _ => return Ok(None),
+        };
+        match jwt_verifier.verify(token) {
+            Ok(verified) => Ok(Some((
+                verified.scopes().clone(),
+                Some(verified.claims().clone()),
+            ))),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    /// Looks up a scheme by name in `components/securitySchemes`.
+    pub(crate) fn resolve_scheme(
+        traverser: &OpenApiTraverser,
+        scheme_name: &str,
+    ) -> Result<SecuritySchemeType, TraverserError> {
+        let components = traverser.get_required(traverser.specification(), COMPONENTS_FIELD)?;
+        let schemes = traverser.get_required(components.value(), SECURITY_SCHEMES_FIELD)?;
+        let scheme = traverser.get_required(schemes.value(), scheme_name)?;
+        let scheme = scheme.value();
+        let scheme_type = traverser.get_required(scheme, TYPE_FIELD)?;
+        match OpenApiTraverser::require_str(scheme_type.value())? {
+            "apiKey" => {
+                let name = traverser.get_required(scheme, NAME_FIELD)?;
+                let name = OpenApiTraverser::require_str(name.value())?;
+                let location = traverser.get_required(scheme, IN_FIELD)?;
+                let location = match OpenApiTraverser::require_str(location.value())? {
+                    "header" => ParameterLocation::Header,
+                    "query" => ParameterLocation::Query,
+                    "cookie" => ParameterLocation::Cookie,
+                    other => {
+                        return Err(TraverserError::invalid_structure(format!(
+                            "Invalid apiKey location '{}' in security scheme '{}'",
+                            other, scheme_name
+                        )));
+                    }
+                };
+                Ok(SecuritySchemeType::ApiKey {
+                    name: name.to_string(),
+                    location,
+                })
+            }
+            "http" => {
+                let http_scheme = traverser.get_required(scheme, SCHEME_FIELD)?;
+                let http_scheme = OpenApiTraverser::require_str(http_scheme.value())?;
+                let bearer_format = OpenApiTraverser::get_as_str(scheme, BEARER_FORMAT_FIELD)
+                    .ok()
+                    .map(String::from);
+                Ok(SecuritySchemeType::Http {
+                    scheme: http_scheme.to_lowercase(),
+                    bearer_format,
+                })
+            }
+            "oauth2" => Ok(SecuritySchemeType::OAuth2),
+            "openIdConnect" => Ok(SecuritySchemeType::OpenIdConnect),
+            "mutualTLS" => Ok(SecuritySchemeType::MutualTls),
+            other => Err(TraverserError::invalid_structure(format!(
+                "Unknown type '{}' in security scheme '{}'",
+                other, scheme_name
+            ))),
+        }
+    }
+
+    /// Extracts the credential for a scheme from the request.
+    ///
+    /// Returns `Ok(None)` when the credential is absent and `Err` with a reason when it is
+    /// present but does not have the expected shape.
+    pub(crate) fn extract_credential(
+        &self,
+        scheme_type: &SecuritySchemeType,
+    ) -> Result<Option<Credential>, String> {
+        match scheme_type {
+            SecuritySchemeType::ApiKey { name, location } => {
+                let value = match location {
+                    ParameterLocation::Header => self.header_value(name),
+                    ParameterLocation::Query => self.query_params.get(name).cloned(),
+                    ParameterLocation::Cookie => self.cookie_value(name),
+                    ParameterLocation::Path => None,
+                };
+                match value {
+                    None => Ok(None),
+                    Some(value) if value.trim().is_empty() => {
+                        Err(format!("API key '{}' in {} is empty", name, location))
+                    }
+                    Some(value) => Ok(Some(Credential::ApiKey(value))),
+                }
+            }
+            SecuritySchemeType::Http {
+                scheme,
+                bearer_format,
+            } => self.authorization_credential(scheme, bearer_format.as_deref()),
+            SecuritySchemeType::OAuth2 | SecuritySchemeType::OpenIdConnect => {
+                self.authorization_credential(BEARER_SCHEME, None)
+            }
+            SecuritySchemeType::MutualTls => {
+                log::debug!("mutualTLS is enforced by the transport layer, assuming it is present");
+                Ok(Some(Credential::Transport))
+            }
+        }
+    }
+
+    fn authorization_credential(
+        &self,
+        expected_scheme: &str,
+        bearer_format: Option<&str>,
+    ) -> Result<Option<Credential>, String> {
+        let header = match self.header_value(AUTHORIZATION.as_str()) {
+            None => return Ok(None),
+            Some(header) => header,
+        };
+        let (scheme, value) = match header.trim().split_once(' ') {
+            Some((scheme, value)) => (scheme, value.trim()),
+            None => (header.trim(), ""),
+        };
+        if !scheme.eq_ignore_ascii_case(expected_scheme) {
+            return Ok(None);
+        }
+        if value.is_empty() {
+            return Err(format!(
+                "Authorization header for scheme '{}' has no credentials",
+                expected_scheme
+            ));
+        }
+
+        match expected_scheme.to_lowercase().as_str() {
+            BASIC_SCHEME => Self::decode_basic(value).map(Some),
+            BEARER_SCHEME => {
+                let expects_jwt = bearer_format.is_some_and(|f| f.eq_ignore_ascii_case("jwt"));
+                if expects_jwt && !Self::is_jwt_shaped(value) {
+                    return Err(String::from("bearer token is not a JWT"));
+                }
+                Ok(Some(Credential::Bearer(value.to_string())))
+            }
+            _ => Ok(Some(Credential::Authorization {
+                scheme: scheme.to_string(),
+                value: value.to_string(),
+            })),
+        }
+    }
+
+    fn decode_basic(value: &str) -> Result<Credential, String> {
+        let decoded = match BASE64_STANDARD.decode(value) {
+            Ok(decoded) => decoded,
+            Err(_) => return Err(String::from("basic credentials are not valid base64")),
+        };
+        let decoded = match String::from_utf8(decoded) {
+            Ok(decoded) => decoded,
+            Err(_) => return Err(String::from("basic credentials are not valid UTF-8")),
+        };
+        match decoded.split_once(':') {
+            Some((username, password)) => Ok(Credential::Basic {
+                username: username.to_string(),
+                password: password.to_string(),
+            }),
+            None => Err(String::from("basic credentials are missing the ':' separator")),
+        }
+    }
+
+    /// Checks for three dot-separated base64url segments (the signature may be empty).
+    fn is_jwt_shaped(token: &str) -> bool {
+        let segments: Vec<&str> = token.split('.').collect();
+        segments.len() == 3
+            && !segments[0].is_empty()
+            && !segments[1].is_empty()
+            && segments.iter().all(|segment| {
+                segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '=')
+            })
+    }
+
+    fn header_value(&self, name: &str) -> Option<String> {
+        self.headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    }
+
+    fn cookie_value(&self, name: &str) -> Option<String> {
+        self.headers
+            .get_all(COOKIE)
+            .iter()
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|header| header.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    }
+}
+
+impl Validator for RequestSecurityValidator<'_> {
+    fn validate(
+        &self,
+        traverser: &OpenApiTraverser,
+        op: &Operation,
+        _validation_options: &ValidationOptions,
+    ) -> Result<(), ValidationError> {
+        self.evaluate(traverser, op).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::types::security::{Credential, SecurityHandler, SecurityRejection};
+    use crate::validator::builder::OpenApiPayloadValidatorBuilder;
+    use crate::validator::{OpenApiPayloadValidator, ValidationError};
+    use http::Request;
+    use serde_json::{json, Value};
+
+    struct ApiKeyStore;
+
+    impl SecurityHandler for ApiKeyStore {
+        fn verify(
+            &self,
+            _scheme_name: &str,
+            credential: &Credential,
+        ) -> Result<Vec<String>, SecurityRejection> {
+            match credential {
+                Credential::ApiKey(key) if key == "admin-key" => Ok(vec!["read".to_string()]),
+                Credential::ApiKey(key) if key == "guest-key" => Ok(vec![]),
+                _ => Err(SecurityRejection::new("unknown API key")),
+            }
+        }
+    }
+
+    fn create_builder(security: Value) -> OpenApiPayloadValidatorBuilder {
+        let spec = json!({
+            "openapi": "3.1.0",
+            "info": {
+                "title": "Test API",
+                "version": "1.0.0"
+            },
+            "paths": {
+                "/test": {
+                    "get": {
+                        "security": security
+                    }
+                }
+            },
+            "components": {
+                "securitySchemes": {
+                    "headerKey": { "type": "apiKey", "name": "X-API-Key", "in": "header" },
+                    "queryKey": { "type": "apiKey", "name": "api_key", "in": "query" },
+                    "cookieKey": { "type": "apiKey", "name": "session", "in": "cookie" },
+                    "basicAuth": { "type": "http", "scheme": "basic" },
+                    "jwtAuth": { "type": "http", "scheme": "bearer", "bearerFormat": "JWT" },
+                    "oauth": {
+                        "type": "oauth2",
+                        "flows": {
+                            "clientCredentials": {
+                                "tokenUrl": "https://example.com/token",
+                                "scopes": { "read": "Read access" }
+                            }
+                        }
+                    }
+                }
+            }
+        });
+        OpenApiPayloadValidatorBuilder::new().load_from_value(spec)
+    }
+
+    fn create_validator(security: Value) -> OpenApiPayloadValidator {
+        create_builder(security).build().unwrap()
+    }
+
+    fn request(uri: &str, headers: &[(&str, &str)]) -> Request<Value> {
+        let mut builder = Request::builder().uri(uri);
+        for (name, value) in headers {
+            builder = builder.header(*name, *value);
+        }
+        builder.body(Value::Null).unwrap()
+    }
+
+    #[test]
+    fn test_api_key_locations() {
+        let validator = create_validator(json!([
+            { "headerKey": [] },
+            { "queryKey": [] },
+            { "cookieKey": [] }
+        ]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+
+        let req = request("/test", &[("X-API-Key", "secret")]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scheme_name(), "headerKey");
+        assert_eq!(matched.credential(), &Credential::ApiKey("secret".to_string()));
+
+        let req = request("/test?api_key=secret", &[]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scheme_name(), "queryKey");
+
+        let req = request("/test", &[("Cookie", "theme=dark; session=abc")]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scheme_name(), "cookieKey");
+        assert_eq!(matched.credential(), &Credential::ApiKey("abc".to_string()));
+
+        let req = request("/test", &[]);
+        assert!(validator.validate_request_security(&operation, &req).is_err());
+    }
+
+    #[test]
+    fn test_basic_credentials() {
+        let validator = create_validator(json!([{ "basicAuth": [] }]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+
+        // "user:pass"
+        let req = request("/test", &[("Authorization", "Basic dXNlcjpwYXNz")]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(
+            matched.credential(),
+            &Credential::Basic {
+                username: "user".to_string(),
+                password: "pass".to_string()
+            }
+        );
+
+        let req = request("/test", &[("Authorization", "Basic not-base64!")]);
+        assert!(validator.validate_request_security(&operation, &req).is_err());
+    }
+
+    #[test]
+    fn test_bearer_credentials() {
+        let validator = create_validator(json!([{ "jwtAuth": [] }, { "oauth": [] }]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+
+        let req = request("/test", &[("Authorization", "Bearer aaa.bbb.ccc")]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scheme_name(), "jwtAuth");
+
+        // Not JWT shaped, so only the oauth2 scheme accepts it.
+        let req = request("/test", &[("Authorization", "Bearer opaque-token")]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scheme_name(), "oauth");
+    }
+
+    #[test]
+    fn test_unverified_credential_lacks_scopes() {
+        let validator = create_validator(json!([{ "oauth": ["read"] }]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+        let req = request("/test", &[("Authorization", "Bearer opaque-token")]);
+        assert!(validator.validate_request_security(&operation, &req).is_err());
+    }
+
+    #[test]
+    fn test_optional_security() {
+        let validator = create_validator(json!([{ "headerKey": [] }, {}]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+        let req = request("/test", &[]);
+        assert!(validator
+            .validate_request_security(&operation, &req)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_requirement_object_requires_all_schemes() {
+        let validator = create_validator(json!([
+            { "oauth": [], "headerKey": [] },
+            { "basicAuth": [] }
+        ]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+
+        let req = request("/test", &[("Authorization", "Bearer token")]);
+        assert!(validator.validate_request_security(&operation, &req).is_err());
+
+        let req = request(
+            "/test",
+            &[("Authorization", "Bearer token"), ("X-API-Key", "secret")],
+        );
+        let matches = validator.validate_request_security(&operation, &req).unwrap();
+        let mut names: Vec<&str> = matches.iter().map(|m| m.scheme_name()).collect();
+        names.sort();
+        assert_eq!(names, vec!["headerKey", "oauth"]);
+
+        let req = request("/test", &[("Authorization", "Basic dXNlcjpwYXNz")]);
+        let matches = validator.validate_request_security(&operation, &req).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].scheme_name(), "basicAuth");
+    }
+
+    #[test]
+    fn test_security_handler_grants_scopes() {
+        let validator = create_builder(json!([{ "headerKey": ["read"] }]))
+            .security_handler("headerKey", ApiKeyStore)
+            .build()
+            .unwrap();
+        let operation = validator.find_operation("/test", "get").unwrap();
+
+        let req = request("/test", &[("X-API-Key", "admin-key")]);
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scopes(), &vec!["read".to_string()]);
+        assert!(validator.validate_request(&req, None).is_ok());
+
+        // Accepted, but missing the required scope
+        let req = request("/test", &[("X-API-Key", "guest-key")]);
+        assert!(matches!(
+            validator.validate_request_security(&operation, &req),
+            Err(ValidationError::Forbidden(_))
+        ));
+
+        let req = request("/test", &[("X-API-Key", "stolen-key")]);
+        assert!(matches!(
+            validator.validate_request_security(&operation, &req),
+            Err(ValidationError::Unauthorized(_))
+        ));
+        assert!(validator.validate_request(&req, None).is_err());
+
+        let req = request("/test", &[]);
+        assert!(matches!(
+            validator.validate_request(&req, None),
+            Err(ValidationError::Unauthorized(_))
+        ));
+    }
+
+    #[test]
+    fn test_security_enforcement_switch() {
+        let security = json!([{ "headerKey": [] }]);
+        let anonymous = request("/test", &[]);
+        let with_key = request("/test", &[("X-API-Key", "anything")]);
+
+        // Nothing verifies credentials, so requirements are not evaluated by default
+        let validator = create_validator(security.clone());
+        assert!(validator.validate_request(&anonymous, None).is_ok());
+
+        let validator = create_builder(security.clone())
+            .enforce_security(true)
+            .build()
+            .unwrap();
+        assert!(matches!(
+            validator.validate_request(&anonymous, None),
+            Err(ValidationError::Unauthorized(_))
+        ));
+        assert!(validator.validate_request(&with_key, None).is_ok());
+
+        let validator = create_builder(security)
+            .security_handler("headerKey", ApiKeyStore)
+            .enforce_security(false)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&anonymous, None).is_ok());
+    }
+
+    #[test]
+    fn test_scopes_of_schemes_without_handler() {
+        // The handler is for another scheme, nothing can verify the scopes of the oauth token.
+        let validator = create_builder(json!([{ "oauth": ["read"] }]))
+            .security_handler("headerKey", ApiKeyStore)
+            .build()
+            .unwrap();
+        let req = request("/test", &[("Authorization", "Bearer anything")]);
+        assert!(validator.validate_request(&req, None).is_err());
+
+        let validator = create_builder(json!([{ "oauth": [] }]))
+            .security_handler("headerKey", ApiKeyStore)
+            .build()
+            .unwrap();
+        assert!(validator.validate_request(&req, None).is_ok());
+    }
+
+    #[test]
+    fn test_granted_scopes_of_schemes_without_handler() {
+        let validator = create_builder(json!([{ "oauth": ["read"] }]))
+            .security_handler("headerKey", ApiKeyStore)
+            .build()
+            .unwrap();
+        let req = request("/test", &[("Authorization", "Bearer anything")]);
+        let granted = vec!["read".to_string()];
+        assert!(validator.validate_request(&req, Some(&granted)).is_ok());
+
+        let granted = vec!["write".to_string()];
+        assert!(matches!(
+            validator.validate_request(&req, Some(&granted)),
+            Err(ValidationError::Forbidden(_))
+        ));
+        // The granted scopes do not stand in for a missing credential
+        let granted = vec!["read".to_string()];
+        assert!(matches!(
+            validator.validate_request(&request("/test", &[]), Some(&granted)),
+            Err(ValidationError::Unauthorized(_))
+        ));
+
+        // Without enforcement, only the granted scopes are checked
+        let validator = create_validator(json!([{ "oauth": ["read"] }]));
+        assert!(validator.validate_request(&request("/test", &[]), Some(&granted)).is_ok());
+    }
+
+    #[cfg(feature = "jwt")]
+    #[test]
+    fn test_jwt_verifier() {
+        use crate::jwt::{Algorithm, JwtVerifier};
+        use jsonwebtoken::{encode, get_current_timestamp, EncodingKey, Header};
+
+        let jwks = r#"{"keys": [{"kty": "oct", "kid": "k1", "alg": "HS256", "k": "c2VjcmV0"}]}"#;
+        let verifier = JwtVerifier::new()
+            .with_jwks(jwks)
+            .unwrap()
+            .with_audience(&["test-api"]);
+        let validator = create_builder(json!([{ "oauth": ["read"] }]))
+            .jwt_verifier(verifier)
+            .build()
+            .unwrap();
+        let operation = validator.find_operation("/test", "get").unwrap();
+        let bearer_request = |claims: Value| {
+            let token = encode(
+                &Header::new(Algorithm::HS256),
+                &claims,
+                &EncodingKey::from_secret(b"secret"),
+            )
+            .unwrap();
+            request("/test", &[("Authorization", &format!("Bearer {}", token))])
+        };
+        let exp = get_current_timestamp() + 600;
+
+        let req = bearer_request(json!({ "aud": "test-api", "exp": exp, "scp": "read" }));
+        assert!(validator.validate_request(&req, None).is_ok());
+        let matched = validator.validate_request_security(&operation, &req).unwrap().remove(0);
+        assert_eq!(matched.scopes(), &vec!["read"]);
+        assert_eq!(matched.claims().unwrap()["aud"], "test-api");
+
+        let req = bearer_request(json!({ "aud": "test-api", "exp": exp, "scp": "write" }));
+        assert!(validator.validate_request(&req, None).is_err());
+
+        let req = bearer_request(json!({ "aud": "other-api", "exp": exp, "scp": "read" }));
+        assert!(validator.validate_request(&req, None).is_err());
+
+        let req = request("/test", &[]);
+        assert!(validator.validate_request(&req, None).is_err());
+    }
+
+    #[test]
+    fn test_security_handler_for_unknown_scheme() {
+        let result = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.0.2.json")
+            .security_handler("petstore_auth", ApiKeyStore)
+            .build();
+        assert!(result.is_ok());
+
+        let result = OpenApiPayloadValidatorBuilder::new()
+            .load_from_file("./test/openapi-v3.0.2.json")
+            .security_handler("missing_auth", ApiKeyStore)
+            .build();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_undefined_security_scheme() {
+        let validator = create_validator(json!([{ "missingScheme": [] }]));
+        let operation = validator.find_operation("/test", "get").unwrap();
+        let req = request("/test", &[("Authorization", "Bearer token")]);
+        assert!(validator.validate_request_security(&operation, &req).is_err());
+    }
+}